@@ -1,7 +1,12 @@
 use std::path::Path;
 use std::process::Command;
+use std::sync::{Mutex, MutexGuard, OnceLock};
 
-use crate::models::BranchListItem;
+use crate::models::{BranchListItem, DevToolPreset, SyncOpenResult, WorkspaceOpenResult};
+use crate::system::{self, EditorOpenParams, TerminalOpenParams};
+
+// 本次运行中为查看历史提交创建的工作树：(仓库路径, 工作树路径)。
+static COMMIT_WORKTREES: OnceLock<Mutex<Vec<(String, String)>>> = OnceLock::new();
 
 /// 列出仓库下所有分支名称。
 pub fn list_branches(base_path: &str) -> Vec<BranchListItem> {
@@ -32,6 +37,151 @@ pub fn list_branches(base_path: &str) -> Vec<BranchListItem> {
         .collect()
 }
 
+/// 在临时目录中以分离 HEAD 检出指定提交并用编辑器打开，返回工作树路径。
+/// 编辑器打开失败时会立即移除工作树。
+pub fn open_at_commit(
+    base_path: &str,
+    commit: &str,
+    preset: DevToolPreset,
+) -> Result<String, String> {
+    let worktree = add_commit_worktree(base_path, commit)?;
+    if let Err(err) = system::open_in_editor(EditorOpenParams::from_preset(preset, &worktree)) {
+        // 工作树刚创建，没有用户改动，可以强制移除。
+        if let Err(cleanup_err) = remove_commit_worktree(&worktree, true) {
+            log::warn!("清理工作树失败: {}", cleanup_err);
+        }
+        return Err(err);
+    }
+    Ok(worktree)
+}
+
+/// 为指定提交创建分离 HEAD 的临时工作树，并记录下来供后续清理。
+pub fn add_commit_worktree(base_path: &str, commit: &str) -> Result<String, String> {
+    if !is_git_repo(base_path) {
+        return Err("不是 Git 仓库".to_string());
+    }
+    let commit = commit.trim();
+    // 以 `-` 开头的值会被 git 当作选项解析。
+    if commit.is_empty() || commit.starts_with('-') {
+        return Err(format!("无效的提交: {commit}"));
+    }
+    let object = format!("{commit}^{{commit}}");
+    if !execute_git_command(base_path, &["cat-file", "-e", &object]).success {
+        return Err(format!("提交不存在: {commit}"));
+    }
+
+    let worktree = std::env::temp_dir()
+        .join(format!("devhaven-worktree-{}", uuid::Uuid::new_v4()))
+        .to_string_lossy()
+        .to_string();
+    let result = execute_git_command(
+        base_path,
+        &["worktree", "add", "--detach", &worktree, commit],
+    );
+    if !result.success {
+        return Err(format!("创建工作树失败: {}", result.output));
+    }
+    commit_worktrees().push((base_path.to_string(), worktree.clone()));
+    Ok(worktree)
+}
+
+/// 移除由 [`add_commit_worktree`] 创建的工作树。工作树有未提交的修改时 git 会拒绝移除，
+/// 只有 `force` 为 true（用户明确要求丢弃修改）时才强制移除。
+pub fn remove_commit_worktree(worktree: &str, force: bool) -> Result<(), String> {
+    let mut worktrees = commit_worktrees();
+    let index = worktrees
+        .iter()
+        .position(|(_, path)| path == worktree)
+        .ok_or_else(|| format!("未知的工作树: {worktree}"))?;
+    let (repo, path) = &worktrees[index];
+    let mut args = vec!["worktree", "remove"];
+    if force {
+        args.push("--force");
+    }
+    args.push(path);
+    let result = execute_git_command(repo, &args);
+    if !result.success {
+        return Err(format!("移除工作树失败: {}", result.output));
+    }
+    worktrees.remove(index);
+    Ok(())
+}
+
+/// 用编辑器打开项目，同时打开终端执行 `git pull`，结束后留在交互式 shell 中；
+/// 终端原有的 `initial_command` 会被替换。路径不是 Git 仓库或当前分支没有上游分支时
+/// 照常打开终端但不拉取，并在结果中说明原因。
+pub fn open_and_sync(
+    path: &str,
+    preset: DevToolPreset,
+    terminal: TerminalOpenParams,
+) -> SyncOpenResult {
+    let sync_skipped = sync_skip_reason(path);
+    let initial_command = match sync_skipped {
+        Some(_) => terminal.initial_command,
+        None => Some(git_pull_terminal_command()),
+    };
+    let WorkspaceOpenResult { editor, terminal } = system::open_workspace(
+        path,
+        Some(preset),
+        Some(TerminalOpenParams {
+            initial_command,
+            ..terminal
+        }),
+        false,
+    );
+    SyncOpenResult {
+        editor,
+        terminal,
+        sync_skipped,
+    }
+}
+
+// 判断能否直接 `git pull`，不能时返回原因。
+fn sync_skip_reason(path: &str) -> Option<String> {
+    if !is_git_repo(path) {
+        return Some("不是 Git 仓库".to_string());
+    }
+    let upstream = execute_git_command(
+        path,
+        &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+    );
+    (!upstream.success).then(|| "当前分支没有上游分支".to_string())
+}
+
+// 拉取后保持终端打开：Linux 与 Windows 的终端在程序退出后会关闭窗口。
+fn git_pull_terminal_command() -> Vec<String> {
+    if cfg!(target_os = "windows") {
+        ["powershell.exe", "-NoExit", "-Command", "git pull"]
+            .map(str::to_string)
+            .to_vec()
+    } else {
+        ["/bin/sh", "-c", "git pull; exec \"${SHELL:-/bin/sh}\""]
+            .map(str::to_string)
+            .to_vec()
+    }
+}
+
+/// 移除本次运行中创建的所有提交工作树，在应用退出时调用。
+/// 不强制移除：有未提交修改的工作树会保留下来并记录日志，避免退出时丢失用户的改动。
+pub fn remove_all_commit_worktrees() {
+    let worktrees: Vec<String> = commit_worktrees()
+        .iter()
+        .map(|(_, path)| path.clone())
+        .collect();
+    for worktree in worktrees {
+        if let Err(err) = remove_commit_worktree(&worktree, false) {
+            log::warn!("保留工作树 {}: {}", worktree, err);
+        }
+    }
+}
+
+fn commit_worktrees() -> MutexGuard<'static, Vec<(String, String)>> {
+    COMMIT_WORKTREES
+        .get_or_init(|| Mutex::new(Vec::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
 // 执行 Git 命令并统一输出格式。
 fn execute_git_command(path: &str, args: &[&str]) -> GitCommandResult {
     let output = Command::new("/usr/bin/git")
@@ -94,3 +244,78 @@ struct GitCommandResult {
     success: bool,
     output: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .expect("run git");
+        assert!(status.success(), "git {args:?}");
+    }
+
+    #[test]
+    fn commit_worktree_checks_out_commit_and_cleans_up() {
+        let repo = std::env::temp_dir().join(format!("devhaven_test_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&repo).expect("create repo dir");
+        git(&repo, &["init", "-q"]);
+        fs::write(repo.join("README.md"), "v1").expect("write file");
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-q", "-m", "first"]);
+        fs::write(repo.join("README.md"), "v2").expect("write file");
+        git(&repo, &["commit", "-q", "-am", "second"]);
+        let repo_str = repo.to_string_lossy().to_string();
+
+        assert!(add_commit_worktree(&repo_str, "does-not-exist").is_err());
+        assert!(add_commit_worktree(&repo_str, "--help").is_err());
+
+        let worktree = add_commit_worktree(&repo_str, "HEAD~1").expect("add worktree");
+        assert_eq!(
+            fs::read_to_string(Path::new(&worktree).join("README.md")).expect("read file"),
+            "v1"
+        );
+        fs::write(Path::new(&worktree).join("README.md"), "edited").expect("edit file");
+        assert!(remove_commit_worktree(&worktree, false).is_err());
+        assert!(Path::new(&worktree).exists());
+        remove_commit_worktree(&worktree, true).expect("remove worktree");
+        assert!(!Path::new(&worktree).exists());
+        assert!(remove_commit_worktree(&worktree, true).is_err());
+
+        let _ = fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn sync_skip_reason_requires_repo_with_upstream() {
+        let root = std::env::temp_dir().join(format!("devhaven_test_{}", uuid::Uuid::new_v4()));
+        let origin = root.join("origin");
+        fs::create_dir_all(&origin).expect("create origin dir");
+        let origin_str = origin.to_string_lossy().to_string();
+        assert_eq!(
+            sync_skip_reason(&origin_str).as_deref(),
+            Some("不是 Git 仓库")
+        );
+
+        git(&origin, &["init", "-q"]);
+        fs::write(origin.join("README.md"), "v1").expect("write file");
+        git(&origin, &["add", "."]);
+        git(&origin, &["commit", "-q", "-m", "first"]);
+        assert_eq!(
+            sync_skip_reason(&origin_str).as_deref(),
+            Some("当前分支没有上游分支")
+        );
+
+        git(&root, &["clone", "-q", &origin_str, "clone"]);
+        assert_eq!(
+            sync_skip_reason(&root.join("clone").to_string_lossy()),
+            None
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,140 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::models::LaunchStrategy;
+
+const AUDIT_FILE: &str = "launch-audit.jsonl";
+// 超过该大小时轮转为 `.1` 文件，只保留一份旧日志。
+const MAX_AUDIT_FILE_SIZE: u64 = 1024 * 1024;
+
+static AUDIT_PATH: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+
+/// 单次启动的审计记录。
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchAuditEntry<'a> {
+    pub preset_id: Option<&'a str>,
+    pub path: Option<&'a str>,
+    pub command: &'a str,
+    /// 启动成功时实际采用的方式。
+    pub strategy: Option<LaunchStrategy>,
+    pub error: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct AuditLine<'a> {
+    timestamp: String,
+    #[serde(flatten)]
+    entry: &'a LaunchAuditEntry<'a>,
+}
+
+/// 开启或关闭启动审计日志，日志以 JSON Lines 格式写在应用日志目录下。
+pub fn enable_launch_audit(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let path = if enabled {
+        let dir = app
+            .path()
+            .app_log_dir()
+            .map_err(|err| format!("无法获取日志目录: {err}"))?;
+        fs::create_dir_all(&dir).map_err(|err| format!("无法创建目录: {err}"))?;
+        Some(dir.join(AUDIT_FILE))
+    } else {
+        None
+    };
+    *audit_path() = path;
+    Ok(())
+}
+
+/// 写入一条审计记录；未开启时直接返回，写入失败只记录警告。
+pub fn record_launch(entry: &LaunchAuditEntry) {
+    // 写入期间持有锁，保证并发启动时每条记录完整地占一行。
+    let path = audit_path();
+    let Some(path) = path.as_deref() else {
+        return;
+    };
+    if let Err(err) = append_entry(path, entry, MAX_AUDIT_FILE_SIZE) {
+        log::warn!("写入启动审计日志失败: {}", err);
+    }
+}
+
+fn audit_path() -> MutexGuard<'static, Option<PathBuf>> {
+    AUDIT_PATH
+        .get_or_init(|| Mutex::new(None))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn append_entry(path: &Path, entry: &LaunchAuditEntry, max_size: u64) -> std::io::Result<()> {
+    rotate_if_needed(path, max_size)?;
+    let mut line = serde_json::to_string(&AuditLine {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        entry,
+    })?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+fn rotate_if_needed(path: &Path, max_size: u64) -> std::io::Result<()> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.len() >= max_size => fs::rename(path, rotated_path(path)),
+        _ => Ok(()),
+    }
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_entry_writes_json_lines_and_rotates() {
+        let root = std::env::temp_dir().join(format!("devhaven-audit-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("create dir");
+        let path = root.join(AUDIT_FILE);
+        let entry = LaunchAuditEntry {
+            preset_id: Some("vscode"),
+            path: Some("/work/app"),
+            command: "/usr/bin/code",
+            strategy: Some(LaunchStrategy::CommandPath),
+            error: None,
+        };
+
+        append_entry(&path, &entry, 1024 * 1024).expect("append");
+        append_entry(&path, &entry, 1024 * 1024).expect("append");
+        let content = fs::read_to_string(&path).expect("read log");
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let line: serde_json::Value = serde_json::from_str(lines[0]).expect("parse line");
+        assert_eq!(line["presetId"], "vscode");
+        assert_eq!(line["strategy"], "commandPath");
+        assert!(line["timestamp"].is_string());
+
+        append_entry(&path, &entry, 1).expect("append after rotation");
+        assert_eq!(
+            fs::read_to_string(&path).expect("read log").lines().count(),
+            1
+        );
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path))
+                .expect("read rotated log")
+                .lines()
+                .count(),
+            2
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
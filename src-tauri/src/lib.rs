@@ -1,7 +1,9 @@
 mod codex_sessions;
 mod git_daily;
 mod git_ops;
+mod launch_audit;
 mod markdown;
+mod md5;
 mod models;
 mod notes;
 mod project_loader;
@@ -18,15 +20,29 @@ use tauri::State;
 use tauri_plugin_log::{Target, TargetKind};
 
 use crate::models::{
-    AppStateFile, BranchListItem, CodexSessionSummary, DevToolPreset, GitDailyResult, GitIdentity,
-    HeatmapCacheFile, MarkdownFileEntry, Project,
+    AppStateFile, BootstrapResult, BranchListItem, CloudProvider, CodesignStatus,
+    CodexSessionSummary, DeadlineRunResult, EditorAboutResult, GitDailyResult, GitIdentity,
+    GotoSpec, HeatmapCacheFile, Language, MarkdownFileEntry, OpenOutcome, PathDiagnostics,
+    PathMeta, PathOpenResult, PresetHealth, PresetImportResult, PresetTestResult, PresetWarning,
+    Priority, Project, SyncOpenResult, SystemError, VolumeInfo, WindowMode, WorkspaceOpenResult,
+};
+use crate::system::{
+    EditorOpenParams, PathListOptions, RecentSubprojectOptions, Shell, TerminalOpenParams,
+};
+
+// 对外暴露预设扩展点，供下游应用注册自定义的工具检测与启动参数改写。
+pub use crate::models::DevToolPreset;
+pub use crate::system::{
+    register_argument_hook, register_preset_provider, ArgumentHook, PresetProvider,
 };
-use crate::system::{EditorOpenParams, TerminalOpenParams};
 use crate::terminal::{
     TerminalManager, TerminalSessionInfo, TmuxPaneCursor, TmuxPaneInfo, TmuxSupportStatus,
     TmuxWindowInfo,
 };
 
+// tauri.conf.json 中未指定 label 的窗口默认为 `main`。
+const MAIN_WINDOW_LABEL: &str = "main";
+
 #[tauri::command]
 /// 读取应用状态。
 fn load_app_state(app: AppHandle) -> Result<AppStateFile, String> {
@@ -73,6 +89,50 @@ fn build_projects(paths: Vec<String>, existing: Vec<Project>) -> Vec<Project> {
     })
 }
 
+#[tauri::command(async)]
+/// 在临时工作树中检出指定提交并用编辑器打开，返回工作树路径；
+/// 检出大型仓库耗时较长，因此在后台线程中执行，避免阻塞界面。
+fn open_at_commit(path: String, commit: String, preset: DevToolPreset) -> Result<String, String> {
+    log_command_result("open_at_commit", || {
+        log::info!("open_at_commit path={} commit={}", path, commit);
+        git_ops::open_at_commit(&path, &commit, preset)
+    })
+}
+
+#[tauri::command(async)]
+/// 用编辑器打开项目，同时在终端中执行 git pull；需要先检查仓库状态，
+/// 因此在后台线程中执行，避免阻塞界面。
+fn open_and_sync(
+    path: String,
+    preset: DevToolPreset,
+    terminal: Option<TerminalOpenParams>,
+) -> SyncOpenResult {
+    log_command("open_and_sync", || {
+        log::info!("open_and_sync path={}", path);
+        git_ops::open_and_sync(
+            &path,
+            preset,
+            terminal.unwrap_or_else(|| TerminalOpenParams {
+                path: path.clone(),
+                ..Default::default()
+            }),
+        )
+    })
+}
+
+#[tauri::command(async)]
+/// 移除 open_at_commit 创建的工作树；有未提交的修改时需 force 才会移除。与 open_at_commit 一样在后台线程中执行。
+fn remove_commit_worktree(worktree: String, force: Option<bool>) -> Result<(), String> {
+    log_command_result("remove_commit_worktree", || {
+        log::info!(
+            "remove_commit_worktree worktree={} force={:?}",
+            worktree,
+            force
+        );
+        git_ops::remove_commit_worktree(&worktree, force.unwrap_or(false))
+    })
+}
+
 #[tauri::command]
 /// 获取分支列表。
 fn list_branches(base_path: String) -> Vec<BranchListItem> {
@@ -84,15 +144,154 @@ fn list_branches(base_path: String) -> Vec<BranchListItem> {
 
 #[tauri::command]
 /// 在文件管理器中定位路径。
-fn open_in_finder(path: String) -> Result<(), String> {
+fn open_in_finder(path: String, new_tab: Option<bool>) -> Result<(), String> {
     log_command_result("open_in_finder", || {
         log::info!("open_in_finder path={}", path);
-        system::open_in_finder(&path)
+        system::open_in_finder(&path, new_tab.unwrap_or(false))
+    })
+}
+
+#[tauri::command]
+/// 在文件管理器中打开编辑器的用户配置目录。
+fn open_editor_config_dir(preset_id: String) -> Result<(), String> {
+    log_command_result("open_editor_config_dir", || {
+        log::info!("open_editor_config_dir preset_id={}", preset_id);
+        system::open_editor_config_dir(&preset_id)
+    })
+}
+
+#[tauri::command]
+/// 判断路径是否位于云盘同步目录中。
+fn detect_cloud_sync(path: String) -> Option<CloudProvider> {
+    log_command("detect_cloud_sync", || {
+        log::info!("detect_cloud_sync path={}", path);
+        system::detect_cloud_sync(&path)
+    })
+}
+
+#[tauri::command]
+/// 列出已挂载的卷或驱动器。
+fn list_volumes() -> Vec<VolumeInfo> {
+    log_command("list_volumes", system::list_volumes)
+}
+
+#[tauri::command]
+/// 列出 VS Code 中已创建的配置文件。
+fn list_vscode_profiles() -> Result<Vec<String>, String> {
+    log_command_result("list_vscode_profiles", system::list_vscode_profiles)
+}
+
+#[tauri::command]
+/// 列出 PATH 中可用的交互式解释器。
+fn list_repl_presets() -> Vec<DevToolPreset> {
+    log_command("list_repl_presets", system::list_repl_presets)
+}
+
+#[tauri::command]
+/// 在终端中打开路径并运行交互式解释器。
+fn open_repl(
+    path: String,
+    preset: DevToolPreset,
+    reuse_window: Option<bool>,
+) -> Result<(), String> {
+    log_command_result("open_repl", || {
+        log::info!("open_repl path={} preset={}", path, preset.id);
+        system::open_repl(&path, &preset, reuse_window.unwrap_or(false))
+    })
+}
+
+#[tauri::command(async)]
+/// 在目录中启动 Jupyter 服务并在浏览器中打开，返回访问地址。
+fn open_in_jupyter(path: String, classic: Option<bool>) -> Result<String, String> {
+    log_command_result("open_in_jupyter", || {
+        log::info!("open_in_jupyter path={} classic={:?}", path, classic);
+        system::open_in_jupyter(&path, classic.unwrap_or(false))
+    })
+}
+
+#[tauri::command]
+/// 读取路径的修改时间、创建时间与仓库状态。
+fn get_path_metadata(path: String) -> Result<PathMeta, String> {
+    log_command_result("get_path_metadata", || {
+        log::info!("get_path_metadata path={}", path);
+        system::get_path_metadata(&path)
+    })
+}
+
+#[tauri::command(async)]
+/// 统计目录占用的字节数，可用 token 配合 cancel_directory_size 取消。
+fn get_directory_size(
+    path: String,
+    skip_heavy_dirs: Option<bool>,
+    token: Option<String>,
+) -> Result<u64, String> {
+    log_command_result("get_directory_size", || {
+        log::info!("get_directory_size path={}", path);
+        system::get_directory_size(&path, skip_heavy_dirs.unwrap_or(false), token.as_deref())
+    })
+}
+
+#[tauri::command]
+/// 取消进行中的目录大小统计。
+fn cancel_directory_size(token: String) -> bool {
+    log_command("cancel_directory_size", || {
+        log::info!("cancel_directory_size token={}", token);
+        system::cancel_directory_size(&token)
+    })
+}
+
+#[tauri::command]
+/// 返回路径所在卷的可用空间（字节）。
+fn get_free_space(path: String) -> Result<u64, String> {
+    log_command_result("get_free_space", || {
+        log::info!("get_free_space path={}", path);
+        system::get_free_space(&path)
+    })
+}
+
+#[tauri::command]
+/// 识别命令返回的错误信息中需要界面单独处理的类型，例如缺少 macOS 自动化权限。
+fn classify_system_error(message: String) -> Option<SystemError> {
+    log_command("classify_system_error", || {
+        system::classify_system_error(&message)
     })
 }
 
 #[tauri::command]
-/// 在终端中打开路径。
+/// 生成可粘贴到目标 shell 执行的命令行预览。
+fn format_command_line(program: String, args: Vec<String>, shell: Shell) -> String {
+    log_command("format_command_line", || {
+        system::format_command_line(&program, &args, shell)
+    })
+}
+
+#[tauri::command(async)]
+/// 对比应用进程与登录 shell 的 PATH。
+fn diagnose_path() -> PathDiagnostics {
+    log_command("diagnose_path", system::diagnose_path)
+}
+
+#[tauri::command]
+/// 打开系统的默认应用设置。
+fn open_default_apps_settings() -> Result<(), String> {
+    log_command_result(
+        "open_default_apps_settings",
+        system::open_default_apps_settings,
+    )
+}
+
+#[tauri::command]
+/// 把文件或目录移到系统回收站。
+fn move_to_trash(path: String) -> Result<(), String> {
+    log_command_result("move_to_trash", || {
+        log::info!("move_to_trash path={}", path);
+        system::move_to_trash(&path)
+    })
+}
+
+#[tauri::command(async)]
+/// 在终端中打开路径；首次使用某个终端时需要运行其帮助来探测参数，
+/// 因此在后台线程中执行，避免阻塞界面。
 fn open_in_terminal(params: TerminalOpenParams) -> Result<(), String> {
     log_command_result("open_in_terminal", || {
         log::info!("open_in_terminal path={}", params.path);
@@ -101,18 +300,359 @@ fn open_in_terminal(params: TerminalOpenParams) -> Result<(), String> {
 }
 
 #[tauri::command]
-/// 使用外部编辑器打开路径。
-fn open_in_editor(params: EditorOpenParams) -> Result<(), String> {
+/// 把 `file:line:col`、`--line N file` 等跳转写法解析为统一的位置。
+fn parse_goto_argument(arg: String) -> GotoSpec {
+    log_command("parse_goto_argument", || system::parse_goto_argument(&arg))
+}
+
+#[tauri::command]
+/// 使用外部编辑器打开路径，可在成功后最小化 DevHaven 主窗口。
+fn open_in_editor(
+    app: AppHandle,
+    params: EditorOpenParams,
+    hide_after_launch: Option<bool>,
+) -> Result<(), String> {
     log_command_result("open_in_editor", || {
         log::info!("open_in_editor path={}", params.path);
-        system::open_in_editor(params)
+        system::open_in_editor(params)?;
+        if hide_after_launch == Some(true) {
+            minimize_main_window_after_launch(&app);
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+/// 在新的编辑器窗口中打开路径，并返回实际执行的命令。
+fn open_in_new_editor_window(params: EditorOpenParams) -> Result<OpenOutcome, String> {
+    log_command_result("open_in_new_editor_window", || {
+        log::info!("open_in_new_editor_window path={}", params.path);
+        system::open_in_new_editor_window(params)
+    })
+}
+
+#[tauri::command]
+/// 在文件管理器中定位路径，并返回实际执行的命令。
+fn open_in_finder_verbose(path: String, new_tab: Option<bool>) -> Result<OpenOutcome, String> {
+    log_command_result("open_in_finder_verbose", || {
+        log::info!("open_in_finder_verbose path={}", path);
+        system::open_in_finder_verbose(&path, new_tab.unwrap_or(false))
+    })
+}
+
+#[tauri::command(async)]
+/// 在终端中打开路径，并返回实际执行的命令；与 open_in_terminal 一样在后台线程中执行。
+fn open_in_terminal_verbose(params: TerminalOpenParams) -> Result<OpenOutcome, String> {
+    log_command_result("open_in_terminal_verbose", || {
+        log::info!("open_in_terminal_verbose path={}", params.path);
+        system::open_in_terminal_verbose(params)
+    })
+}
+
+#[tauri::command]
+/// 使用外部编辑器打开路径，并返回实际执行的命令；可在成功后最小化 DevHaven 主窗口。
+fn open_in_editor_verbose(
+    app: AppHandle,
+    params: EditorOpenParams,
+    hide_after_launch: Option<bool>,
+) -> Result<OpenOutcome, String> {
+    log_command_result("open_in_editor_verbose", || {
+        log::info!("open_in_editor_verbose path={}", params.path);
+        let outcome = system::open_in_editor_verbose(params)?;
+        if hide_after_launch == Some(true) {
+            minimize_main_window_after_launch(&app);
+        }
+        Ok(outcome)
+    })
+}
+
+// 编辑器已经启动，最小化窗口失败不影响打开结果。最小化而不是隐藏，
+// 使窗口在任务栏与 Dock 中仍可找回。
+fn minimize_main_window_after_launch(app: &AppHandle) {
+    if let Err(error) = apply_main_window_mode(app, WindowMode::MinimizeAfterLaunch) {
+        log::warn!("最小化主窗口失败: {}", error);
+    }
+}
+
+#[tauri::command]
+/// 同时在编辑器与终端中打开项目，可要求编辑器成功后才打开终端。
+fn open_workspace(
+    path: String,
+    editor: Option<DevToolPreset>,
+    terminal: Option<TerminalOpenParams>,
+    require_editor: Option<bool>,
+) -> WorkspaceOpenResult {
+    log_command("open_workspace", || {
+        log::info!("open_workspace path={}", path);
+        system::open_workspace(&path, editor, terminal, require_editor.unwrap_or(false))
+    })
+}
+
+#[tauri::command]
+/// 写入项目初始化文件（不覆盖已有文件）后用预设打开项目。
+fn open_with_bootstrap(
+    path: String,
+    preset: DevToolPreset,
+    bootstrap_files: Vec<(String, String)>,
+) -> Result<BootstrapResult, String> {
+    log_command_result("open_with_bootstrap", || {
+        log::info!(
+            "open_with_bootstrap path={} preset={} files={}",
+            path,
+            preset.id,
+            bootstrap_files.len()
+        );
+        system::open_with_bootstrap(&path, preset, &bootstrap_files)
+    })
+}
+
+#[tauri::command]
+/// 打开 monorepo 中最近修改的子项目，返回选中的路径。
+fn open_recent_subproject(
+    root: String,
+    preset: DevToolPreset,
+    options: Option<RecentSubprojectOptions>,
+) -> Result<String, String> {
+    log_command_result("open_recent_subproject", || {
+        log::info!("open_recent_subproject root={} preset={}", root, preset.id);
+        system::open_recent_subproject(&root, preset, &options.unwrap_or_default())
+    })
+}
+
+#[tauri::command]
+/// 清除 VS Code 为项目保存的工作区存储，返回被删除的目录。
+fn clear_workspace_storage(path: String, confirm: bool) -> Result<Option<String>, String> {
+    log_command_result("clear_workspace_storage", || {
+        log::info!("clear_workspace_storage path={} confirm={}", path, confirm);
+        system::clear_workspace_storage(&path, confirm)
+    })
+}
+
+#[tauri::command]
+/// 以多根工作区打开多个目录。
+fn open_multiroot(paths: Vec<String>, preset: DevToolPreset) -> Result<OpenOutcome, String> {
+    log_command_result("open_multiroot", || {
+        log::info!("open_multiroot paths={} preset={}", paths.len(), preset.id);
+        system::open_multiroot(paths, preset)
+    })
+}
+
+#[tauri::command(async)]
+/// 使用同一开发工具批量打开多个路径；逐个启动时会在两次启动之间等待，
+/// 因此在后台线程中执行，避免阻塞界面。
+fn open_many_in_editor(
+    paths: Vec<String>,
+    preset: DevToolPreset,
+    combine: bool,
+    priority: Option<Priority>,
+) -> Vec<PathOpenResult> {
+    log_command("open_many_in_editor", || {
+        let priority = priority.unwrap_or_default();
+        log::info!(
+            "open_many_in_editor paths={} preset={} combine={} priority={:?}",
+            paths.len(),
+            preset.id,
+            combine,
+            priority
+        );
+        let results = system::open_many_in_editor(paths.clone(), preset, combine, priority);
+        paths
+            .into_iter()
+            .zip(results)
+            .map(|(path, result)| PathOpenResult {
+                path,
+                error: result.err(),
+            })
+            .collect()
+    })
+}
+
+#[tauri::command]
+/// 根据文件类型推荐开发工具预设。
+fn suggest_preset_for_path(path: String, presets: Vec<DevToolPreset>) -> Option<String> {
+    system::suggest_preset_for_path(&path, &presets)
+}
+
+#[tauri::command]
+/// 推断项目的主要编程语言。
+fn detect_project_language(path: String) -> Option<Language> {
+    system::detect_project_language(&path)
+}
+
+#[tauri::command]
+/// 根据项目类型推荐在终端中执行的命令。
+fn suggest_terminal_command(path: String) -> Option<String> {
+    system::suggest_terminal_command(&path)
+}
+
+#[tauri::command(async)]
+/// 推测项目是否已在指定编辑器中打开；需要读取进程列表，因此在后台线程中执行，避免阻塞界面。
+fn is_project_open(path: String, preset_id: String) -> bool {
+    system::is_project_open(&path, &preset_id)
+}
+
+#[tauri::command]
+/// 在默认浏览器中打开链接。
+fn open_url_in_browser(url: String) -> Result<(), String> {
+    log_command_result("open_url_in_browser", || {
+        log::info!("open_url_in_browser url={}", url);
+        system::open_url_in_browser(&url)
+    })
+}
+
+#[tauri::command]
+/// 列出已检测到的开发工具预设，可选扫描应用目录发现更多编辑器（仅 macOS），
+/// 并把用户偏好的预设排在最前面。
+fn list_dev_tool_presets(
+    discover_apps: Option<bool>,
+    preferred_ids: Option<Vec<String>>,
+) -> Vec<DevToolPreset> {
+    log_command("list_dev_tool_presets", || {
+        let presets = if discover_apps.unwrap_or(false) {
+            system::list_dev_tool_presets_with_discovery()
+        } else {
+            system::list_dev_tool_presets()
+        };
+        system::order_presets(presets, &preferred_ids.unwrap_or_default())
+    })
+}
+
+#[tauri::command(async)]
+/// 列出已安装的 VS Code 扩展。
+fn list_vscode_extensions() -> Result<Vec<String>, String> {
+    log_command_result("list_vscode_extensions", system::list_vscode_extensions)
+}
+
+#[tauri::command(async)]
+/// 在临时目录上试启动预设，检验配置是否可用。
+fn test_launch_preset(preset: DevToolPreset) -> PresetTestResult {
+    log_command("test_launch_preset", || {
+        log::info!("test_launch_preset preset={}", preset.id);
+        system::test_launch_preset(&preset)
+    })
+}
+
+#[tauri::command]
+/// 返回预设实际会启动的程序位置。
+fn resolve_preset_path(preset: DevToolPreset) -> Option<String> {
+    log_command("resolve_preset_path", || {
+        log::info!("resolve_preset_path preset={}", preset.id);
+        system::resolve_preset_path(&preset)
+    })
+}
+
+#[tauri::command(async)]
+/// 运行编辑器的命令行入口并返回版本或帮助信息。
+fn launch_editor_about(preset_id: String) -> Result<EditorAboutResult, String> {
+    log_command_result("launch_editor_about", || {
+        log::info!("launch_editor_about preset_id={}", preset_id);
+        system::launch_editor_about(&preset_id)
+    })
+}
+
+#[tauri::command(async)]
+/// 限时运行开发工具并捕获输出，超时时结束整个进程树（用于 CI 与测试）。
+fn launch_with_deadline(
+    preset: DevToolPreset,
+    path: String,
+    timeout_ms: u64,
+) -> Result<DeadlineRunResult, String> {
+    log_command_result("launch_with_deadline", || {
+        log::info!(
+            "launch_with_deadline preset={} path={} timeout_ms={}",
+            preset.id,
+            path,
+            timeout_ms
+        );
+        system::launch_with_deadline(&preset, &path, timeout_ms)
+    })
+}
+
+#[tauri::command(async)]
+/// 检查 macOS 上可执行文件的签名与公证状态；codesign 与 spctl 可能需要联网校验，
+/// 因此在后台线程中执行，避免阻塞界面。
+fn check_macos_codesign(path: String) -> Result<CodesignStatus, String> {
+    log_command_result("check_macos_codesign", || {
+        log::info!("check_macos_codesign path={}", path);
+        system::check_macos_codesign(&path)
     })
 }
 
 #[tauri::command]
-/// 列出已检测到的开发工具预设。
-fn list_dev_tool_presets() -> Vec<DevToolPreset> {
-    log_command("list_dev_tool_presets", system::list_dev_tool_presets)
+/// 预设是否支持以只读模式打开。
+fn supports_read_only(preset: DevToolPreset) -> bool {
+    system::supports_read_only(&preset)
+}
+
+#[tauri::command]
+/// 检查预设的命令与参数模板。
+fn validate_preset(preset: DevToolPreset) -> Vec<PresetWarning> {
+    system::validate_preset(&preset)
+}
+
+#[tauri::command(async)]
+/// 并行检查全部预设，返回诊断页使用的健康报告。
+fn verify_all_presets(presets: Vec<DevToolPreset>) -> Vec<PresetHealth> {
+    log_command("verify_all_presets", || {
+        log::info!("verify_all_presets presets={}", presets.len());
+        system::verify_all_presets(presets)
+    })
+}
+
+#[tauri::command]
+/// 把预设导出为 JSON 配置。
+fn export_presets(presets: Vec<DevToolPreset>) -> String {
+    log_command("export_presets", || {
+        log::info!("export_presets presets={}", presets.len());
+        system::export_presets(&presets)
+    })
+}
+
+#[tauri::command]
+/// 导入 JSON 预设配置，逐条报告未通过检查的预设。
+fn import_presets(json: String) -> Result<PresetImportResult, String> {
+    log_command_result("import_presets", || {
+        log::info!("import_presets bytes={}", json.len());
+        system::import_presets(&json)
+    })
+}
+
+#[tauri::command]
+/// 开启或关闭启动审计日志。
+fn enable_launch_audit(app: AppHandle, enabled: bool) -> Result<(), String> {
+    log_command_result("enable_launch_audit", || {
+        log::info!("enable_launch_audit enabled={}", enabled);
+        launch_audit::enable_launch_audit(&app, enabled)
+    })
+}
+
+#[tauri::command]
+/// 调整 DevHaven 主窗口：隐藏、最小化、置顶或恢复正常显示。
+fn set_main_window_mode(app: AppHandle, mode: WindowMode) -> Result<(), String> {
+    log_command_result("set_main_window_mode", || {
+        log::info!("set_main_window_mode mode={:?}", mode);
+        apply_main_window_mode(&app, mode)
+    })
+}
+
+fn apply_main_window_mode(app: &AppHandle, mode: WindowMode) -> Result<(), String> {
+    let window = app
+        .get_webview_window(MAIN_WINDOW_LABEL)
+        .ok_or_else(|| "窗口不存在".to_string())?;
+    let result = match mode {
+        WindowMode::Normal => window
+            .set_always_on_top(false)
+            .and_then(|_| window.unminimize())
+            .and_then(|_| window.show())
+            .and_then(|_| window.set_focus()),
+        // 没有托盘图标，Windows 与 Linux 上隐藏后无法再找回窗口，改为最小化；
+        // macOS 点击 Dock 图标时由 `RunEvent::Reopen` 恢复。
+        WindowMode::Hide if cfg!(target_os = "macos") => window.hide(),
+        WindowMode::Hide => window.minimize(),
+        WindowMode::MinimizeAfterLaunch => window.minimize(),
+        WindowMode::StayOnTop => window.set_always_on_top(true),
+    };
+    result.map_err(|error| error.to_string())
 }
 
 #[tauri::command]
@@ -137,11 +677,45 @@ fn set_window_fullscreen_auxiliary(
 }
 
 #[tauri::command]
-/// 复制文本到剪贴板。
-fn copy_to_clipboard(app: AppHandle, content: String) -> Result<(), String> {
+/// 复制文本到剪贴板，可选同时写入 Linux 的 PRIMARY 选区。
+fn copy_to_clipboard(
+    app: AppHandle,
+    content: String,
+    also_primary: Option<bool>,
+) -> Result<(), String> {
     log_command_result("copy_to_clipboard", || {
         log::info!("copy_to_clipboard size={}", content.len());
-        system::copy_to_clipboard(&app, &content)
+        system::copy_to_clipboard(&app, &content, also_primary.unwrap_or(false))
+    })
+}
+
+#[tauri::command]
+/// 把多个路径拼接后复制到剪贴板。
+fn copy_paths(
+    app: AppHandle,
+    paths: Vec<String>,
+    options: Option<PathListOptions>,
+) -> Result<(), String> {
+    log_command_result("copy_paths", || {
+        log::info!("copy_paths paths={}", paths.len());
+        system::copy_paths(&app, &paths, &options.unwrap_or_default())
+    })
+}
+
+#[tauri::command]
+/// 在文件管理器中定位路径并复制其绝对路径。
+fn reveal_and_copy(app: AppHandle, path: String) -> Result<(), String> {
+    log_command_result("reveal_and_copy", || {
+        log::info!("reveal_and_copy path={}", path);
+        system::reveal_and_copy(&app, &path)
+    })
+}
+
+#[tauri::command]
+/// 读取剪贴板中的文件路径列表。
+fn read_clipboard_paths(app: AppHandle) -> Result<Vec<String>, String> {
+    log_command_result("read_clipboard_paths", || {
+        system::read_clipboard_paths(&app)
     })
 }
 
@@ -514,12 +1088,63 @@ pub fn run() {
             discover_projects,
             build_projects,
             list_branches,
+            open_at_commit,
+            open_and_sync,
+            remove_commit_worktree,
             open_in_finder,
+            open_editor_config_dir,
+            list_vscode_profiles,
+            list_volumes,
+            detect_cloud_sync,
+            list_repl_presets,
+            open_repl,
+            open_in_jupyter,
+            move_to_trash,
+            get_directory_size,
+            cancel_directory_size,
+            get_path_metadata,
+            get_free_space,
+            open_default_apps_settings,
+            diagnose_path,
+            format_command_line,
+            classify_system_error,
             open_in_terminal,
+            parse_goto_argument,
             open_in_editor,
+            open_in_finder_verbose,
+            open_in_terminal_verbose,
+            open_in_editor_verbose,
+            open_in_new_editor_window,
+            open_workspace,
+            open_with_bootstrap,
+            open_many_in_editor,
+            open_multiroot,
+            open_recent_subproject,
+            clear_workspace_storage,
+            detect_project_language,
+            suggest_preset_for_path,
+            suggest_terminal_command,
+            is_project_open,
+            open_url_in_browser,
             list_dev_tool_presets,
+            test_launch_preset,
+            resolve_preset_path,
+            validate_preset,
+            verify_all_presets,
+            export_presets,
+            import_presets,
+            supports_read_only,
+            check_macos_codesign,
+            launch_editor_about,
+            launch_with_deadline,
+            list_vscode_extensions,
+            enable_launch_audit,
             set_window_fullscreen_auxiliary,
+            set_main_window_mode,
             copy_to_clipboard,
+            copy_paths,
+            reveal_and_copy,
+            read_clipboard_paths,
             read_project_notes,
             write_project_notes,
             list_project_markdown_files,
@@ -550,8 +1175,22 @@ pub fn run() {
             capture_tmux_pane,
             get_tmux_pane_cursor,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_app, event| match event {
+            tauri::RunEvent::Exit => git_ops::remove_all_commit_worktrees(),
+            // 主窗口被隐藏后点击 Dock 图标时恢复显示。
+            #[cfg(target_os = "macos")]
+            tauri::RunEvent::Reopen {
+                has_visible_windows: false,
+                ..
+            } => {
+                if let Err(error) = apply_main_window_mode(_app, WindowMode::Normal) {
+                    log::warn!("恢复主窗口失败: {}", error);
+                }
+            }
+            _ => {}
+        });
 }
 
 #[cfg(target_os = "macos")]
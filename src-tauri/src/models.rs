@@ -123,6 +123,357 @@ pub struct DevToolPreset {
     pub name: String,
     pub command_path: String,
     pub arguments: Vec<String>,
+    /// 除 0 以外同样视为启动成功的退出码，用于成功时也返回非零值的启动器。
+    #[serde(default)]
+    pub success_exit_codes: Option<Vec<i32>>,
+    /// 启动方式，缺省时执行 `command_path`。
+    #[serde(default)]
+    pub kind: PresetKind,
+    /// 发现该工具的安装来源，自定义预设默认为独立安装。
+    #[serde(default)]
+    pub install_kind: InstallKind,
+}
+
+/// 界面需要单独处理的系统错误，由 `classify_system_error` 从命令返回的错误信息中识别。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SystemError {
+    /// 缺少 macOS 自动化权限（errAEEventNotPermitted），`app` 为被拒绝控制的应用，
+    /// 需要在“系统设置 > 隐私与安全性 > 自动化”中授权。
+    AutomationPermissionDenied { app: String },
+}
+
+/// 预设的启动方式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PresetKind {
+    /// 执行 `command_path` 指向的命令。
+    #[default]
+    Command,
+    /// 浏览器中的 VS Code（code-server）：`command_path` 为实例地址，在默认浏览器中
+    /// 打开 `<地址>/?folder=<路径>`。只能由用户手动配置，不参与自动检测。
+    CodeServer,
+}
+
+/// 开发工具的安装来源，便于界面区分同一工具的多处安装。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InstallKind {
+    JetBrainsToolbox,
+    #[default]
+    Standalone,
+    /// 通过 PATH 中的命令行入口（含 Chocolatey 等生成的 shim）找到。
+    PathShim,
+    AppBundle,
+    Flatpak,
+    Snap,
+    Scoop,
+    Registry,
+    MicrosoftStore,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathOpenResult {
+    pub path: String,
+    pub error: Option<String>,
+}
+
+/// 组合启动中单个步骤的执行结果。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", content = "error", rename_all = "camelCase")]
+pub enum LaunchStepStatus {
+    NotRequested,
+    Succeeded,
+    Failed(String),
+    /// 前一步骤失败，按要求未执行。
+    Skipped,
+}
+
+impl From<Result<(), String>> for LaunchStepStatus {
+    fn from(result: Result<(), String>) -> Self {
+        match result {
+            Ok(()) => Self::Succeeded,
+            Err(error) => Self::Failed(error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceOpenResult {
+    pub editor: LaunchStepStatus,
+    pub terminal: LaunchStepStatus,
+}
+
+/// 打开编辑器并在终端中执行 `git pull` 的结果。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncOpenResult {
+    pub editor: LaunchStepStatus,
+    pub terminal: LaunchStepStatus,
+    /// 未执行 `git pull` 的原因（不是 Git 仓库、没有上游分支等），执行时为 None。
+    pub sync_skipped: Option<String>,
+}
+
+/// DevHaven 主窗口的显示方式，用于启动外部编辑器前后调整窗口。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WindowMode {
+    /// 恢复显示：取消隐藏、最小化与置顶，并聚焦窗口。
+    Normal,
+    /// 隐藏窗口，点击 Dock 图标时恢复；Windows 与 Linux 没有恢复入口，改为最小化。
+    Hide,
+    MinimizeAfterLaunch,
+    StayOnTop,
+}
+
+/// 项目的主要编程语言，由 `detect_project_language` 根据标记文件与源文件扩展名推断。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Language {
+    Rust,
+    Go,
+    Python,
+    Java,
+    Kotlin,
+    TypeScript,
+    JavaScript,
+    Php,
+    CSharp,
+    Cpp,
+    C,
+    Ruby,
+    Swift,
+}
+
+/// 启动外部命令时子进程的调度优先级，默认 `Normal`。
+/// - Unix：通过 `setpriority` 设置 nice 值，`Low` 为 10，`High` 为 -5；
+///   提高优先级需要特权，普通用户启动时保持默认优先级。
+/// - Windows：`Low` / `High` 分别对应 `BELOW_NORMAL_PRIORITY_CLASS` 与
+///   `ABOVE_NORMAL_PRIORITY_CLASS`。
+///
+/// 只作用于直接启动的命令及其子进程；macOS 经 `open` 交给 LaunchServices 启动的应用不受影响。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// 打开操作实际采用的启动方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LaunchStrategy {
+    /// `open -a <应用名>`。
+    MacOpenApp,
+    /// `open -b <Bundle ID>`。
+    MacOpenBundle,
+    /// 直接执行命令。
+    CommandPath,
+    /// 经 `$SHELL -lc` 执行命令。
+    LoginShell,
+    /// 经 `cmd.exe /C` 执行 .cmd/.bat。
+    WindowsCmd,
+    /// 经 PowerShell 执行脚本。
+    WindowsPowerShell,
+    /// 经 osascript 执行 AppleScript。
+    Osascript,
+    /// Windows Terminal（wt.exe）。
+    WindowsTerminal,
+    /// explorer.exe。
+    Explorer,
+    /// 系统默认打开方式（open / xdg-open）。
+    SystemOpen,
+    /// Linux 终端模拟器。
+    LinuxTerminal,
+}
+
+/// 打开操作成功时实际执行的命令，供前端记录与排查。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenOutcome {
+    pub strategy: LaunchStrategy,
+    pub command: String,
+    pub args: Vec<String>,
+    /// 已打开但部分选项未生效时的提示，例如编辑器不支持只读模式。
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// 路径的时间信息与仓库状态，时间均为 Unix 毫秒。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathMeta {
+    pub modified_at: Option<i64>,
+    /// 平台或文件系统不记录创建时间时为 None。
+    pub created_at: Option<i64>,
+    pub is_git_repo: bool,
+}
+
+/// 已挂载的卷或驱动器，供选择项目位置时使用。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeInfo {
+    pub name: String,
+    pub mount_path: String,
+    /// 当前用户可用的剩余空间（字节），卷无法访问时为 None。
+    pub free_bytes: Option<u64>,
+}
+
+/// 同步路径所在的云盘服务。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CloudProvider {
+    ICloud,
+    OneDrive,
+    Dropbox,
+}
+
+/// macOS 上可执行文件或应用包的签名状态。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CodesignStatus {
+    /// 签名有效，但未经公证或 Gatekeeper 无法评估（如命令行工具）。
+    Signed,
+    /// 签名有效且 Gatekeeper 确认已公证。
+    Notarized,
+    Unsigned,
+    /// 签名无效或已被篡改。
+    Rejected,
+    /// 非 macOS 平台。
+    UnsupportedPlatform,
+}
+
+/// 写入项目初始化文件的结果，路径均为相对项目根目录的路径。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BootstrapResult {
+    pub written: Vec<String>,
+    /// 已存在而未覆盖的文件。
+    pub skipped: Vec<String>,
+}
+
+/// 进程 PATH 与登录 shell PATH 的对比，用于排查从 Dock/Finder 启动时找不到命令的问题。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathDiagnostics {
+    pub process_path: Vec<String>,
+    /// 无法探测（如 Windows 或 shell 执行失败）时为 None。
+    pub login_shell_path: Option<Vec<String>>,
+    /// 登录 shell PATH 中能找到、进程 PATH 中找不到的常用命令。
+    pub missing_from_process: Vec<String>,
+}
+
+/// 保存自定义预设前的配置检查结果。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PresetWarning {
+    EmptyCommandPath,
+    /// 命令不存在或无法在 PATH 中找到。
+    CommandNotFound {
+        command: String,
+    },
+    /// 参数未引用 `{path}`，路径会被追加到参数末尾。
+    PathNotReferenced,
+    /// 除 `{path}` 外不支持其他占位符，原样传给程序。
+    UnknownPlaceholder {
+        placeholder: String,
+    },
+    /// 双引号不成对或引号包裹了整个参数；参数不经 shell 解析，引号会原样传给程序。
+    SuspiciousQuotes {
+        argument: String,
+    },
+}
+
+/// 导入预设配置时未通过检查的条目。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedPreset {
+    /// 在导入数组中的位置，从 0 开始。
+    pub index: usize,
+    pub id: Option<String>,
+    /// 条目无法解析或 ID 重复时的原因。
+    pub error: Option<String>,
+    pub warnings: Vec<PresetWarning>,
+}
+
+/// 导入预设配置的结果：通过检查的预设与被拒绝的条目分别列出。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetImportResult {
+    pub presets: Vec<DevToolPreset>,
+    pub rejected: Vec<RejectedPreset>,
+}
+
+/// 预设诊断中单个预设的结论。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PresetHealthStatus {
+    Ok,
+    /// 程序存在，但配置检查有提示，见 [`PresetHealth::warnings`]。
+    Warning,
+    /// 找不到预设要启动的程序。
+    Missing,
+    /// 检查在时限内未完成。
+    TimedOut,
+}
+
+/// 设置页“诊断”中单个预设的检查结果。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetHealth {
+    pub id: String,
+    /// 实际会启动的程序位置，见 `resolve_preset_path`。
+    pub resolved_path: Option<String>,
+    pub status: PresetHealthStatus,
+    /// 能识别时的版本号，例如 VS Code `--version` 输出的第一行。
+    pub version: Option<String>,
+    pub warnings: Vec<PresetWarning>,
+}
+
+/// 试启动预设的结果。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetTestResult {
+    /// 进程是否成功启动。
+    pub started: bool,
+    /// 观察期结束时进程仍在运行。
+    pub stayed_open: bool,
+    /// 观察期内退出时的退出码。
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+/// 以命令行方式运行编辑器（如 `code --version`）的输出，用于确认安装可用。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditorAboutResult {
+    /// 标准输出，标准错误不为空时追加在后面。
+    pub output: String,
+    /// 被信号结束时为 None。
+    pub exit_code: Option<i32>,
+}
+
+/// 统一格式的跳转位置，由 `file:line:col`、`--line N file` 等写法解析而来。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GotoSpec {
+    pub path: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+/// 限时运行（用于 CI 与测试）的结果，超时时整个进程树已被结束。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadlineRunResult {
+    pub stdout: String,
+    pub stderr: String,
+    /// 被信号结束时为 None。
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,715 +1,10271 @@
-use std::path::Path;
-use std::process::{Command, ExitStatus};
-
-#[cfg(target_os = "windows")]
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-#[cfg(target_os = "macos")]
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard, OnceLock};
+
+#[cfg(not(target_os = "windows"))]
 use std::io::Write;
-#[cfg(target_os = "windows")]
-use std::path::PathBuf;
-#[cfg(target_os = "macos")]
-use std::process::Stdio;
+
+use std::time::{Duration, Instant};
 
 use tauri::AppHandle;
 use tauri_plugin_clipboard_manager::ClipboardExt;
 
-use crate::models::DevToolPreset;
+use crate::launch_audit::{self, LaunchAuditEntry};
+use crate::md5::md5_hex;
+use crate::models::{
+    BootstrapResult, CloudProvider, CodesignStatus, DeadlineRunResult, DevToolPreset,
+    EditorAboutResult, GotoSpec, InstallKind, Language, LaunchStepStatus, LaunchStrategy,
+    OpenOutcome, PathDiagnostics, PathMeta, PresetHealth, PresetHealthStatus, PresetImportResult,
+    PresetKind, PresetTestResult, PresetWarning, Priority, RejectedPreset, SystemError, VolumeInfo,
+    WorkspaceOpenResult,
+};
+use crate::time_utils::system_time_to_unix_millis;
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Default, serde::Deserialize)]
 pub struct EditorOpenParams {
     pub path: String,
+    /// 应用名，或 .app 包的绝对路径（用于在多个版本中指定其一，仅 macOS）。
     pub app_name: Option<String>,
     pub bundle_id: Option<String>,
     pub command_path: Option<String>,
     pub arguments: Option<Vec<String>>,
+    /// 预设 ID，用于识别编辑器类型；缺省时根据命令与应用名推断。
+    pub preset_id: Option<String>,
+    /// 预设的启动方式，见 [`PresetKind`]；缺省时执行 `command_path`。
+    pub preset_kind: Option<PresetKind>,
+    /// 以 `path` 为项目打开后需要定位的文件，也可以是 [`parse_goto_argument`] 支持的
+    /// `file:line:col` 等写法，其中的行列号在未单独指定 `line`、`column` 时生效。
+    pub file: Option<String>,
+    /// 定位文件时跳转的行号。
+    pub line: Option<u32>,
+    /// 定位文件时跳转的列号，需同时指定行号。
+    pub column: Option<u32>,
+    /// 以安全模式启动（禁用扩展/第三方插件），编辑器不支持时忽略。
+    pub safe_mode: Option<bool>,
+    /// 通过登录 shell 启动以继承完整的用户环境（仅 Unix）。
+    pub use_login_shell: Option<bool>,
+    /// 本次启动追加在预设参数之后的额外参数，同样支持 `{path}` 占位符。
+    pub extra_arguments: Option<Vec<String>>,
+    /// 严格解析命令：不猜测扩展名、不接受相对路径，用于用户自行填写的命令。
+    pub strict_path_lookup: Option<bool>,
+    /// 目录中恰好有一个 `*.code-workspace` 时改为打开该工作区文件（仅 VS Code）。
+    pub prefer_workspace_file: Option<bool>,
+    /// 以 `--folder-uri` / `--file-uri` 明确告诉编辑器路径是目录还是文件，默认关闭，
+    /// 仅 VS Code 支持；工作区文件（`*.code-workspace`）仍按原样传入，见 [`apply_path_kind_flag`]。
+    pub explicit_path_kind: Option<bool>,
+    /// 界面语言，BCP 47 格式（如 `zh-CN`）：VS Code 使用 `--locale`，
+    /// JetBrains 通过 `JAVA_TOOL_OPTIONS` 设置 `user.language` / `user.country`，其他编辑器忽略。
+    pub locale: Option<String>,
+    /// 在 bubblewrap（bwrap）沙箱中启动编辑器，默认关闭，仅 Linux 生效。
+    /// 沙箱只读挂载系统目录、编辑器安装目录与显示服务的套接字（Wayland 套接字、
+    /// `/tmp/.X11-unix` 与 `$XAUTHORITY`），可写挂载项目路径与已存在的编辑器配置/缓存目录；
+    /// `$XDG_RUNTIME_DIR` 中的会话总线与其他编辑器实例的 IPC 套接字不可见，
+    /// 其余文件系统同样不可见；网络保持可用。
+    /// 未安装 bwrap 时记录警告并以普通方式启动。
+    pub sandbox: Option<bool>,
+    /// 在新窗口中打开，见 [`open_in_new_editor_window`]。
+    pub new_window: Option<bool>,
+    /// 以只读模式打开，仅对提供只读参数的编辑器生效（见 [`supports_read_only`]）；
+    /// VS Code 与 JetBrains 没有对应的命令行参数，此时照常打开并在结果中附带提示。
+    pub read_only: Option<bool>,
+    /// 是否恢复上次打开的标签页与布局，缺省时沿用编辑器的默认行为（恢复）。
+    /// 为 false 时 VS Code 追加 `--new-window` 以全新窗口打开；JetBrains 总是按项目的
+    /// `.idea/workspace.xml` 恢复布局，没有对应的命令行参数，因此忽略。
+    pub restore_session: Option<bool>,
+    /// 编辑器进程的调度优先级，缺省为 [`Priority::Normal`]。
+    pub priority: Option<Priority>,
+    /// 使用一次性的临时配置文件启动（VS Code 的 `--profile-temp`），不加载用户的扩展与设置，
+    /// 便于排查问题；不能与参数中显式的 `--profile` 同时使用，其他编辑器忽略。
+    pub temp_profile: Option<bool>,
+    /// 包装命令，放在编辑器命令之前执行，使编辑器在容器中启动，例如
+    /// `["distrobox", "enter", "dev", "--"]` 或 `["toolbox", "run", "-c", "dev"]`。
+    /// 编辑器命令按原样交给容器解析，不在宿主机上查找；`{path}` 同样原样传入，
+    /// 容器以不同位置挂载项目时需要自行在参数中改写路径。
+    pub wrapper: Option<Vec<String>>,
+    /// Python 解释器路径（如项目 venv 中的 `bin/python`），打开前校验其存在：
+    /// VS Code 在项目中没有 `.vscode/settings.json` 时写入 `python.defaultInterpreterPath`，
+    /// 已有该文件时不做修改并在结果中提示；PyCharm 等没有对应的命令行参数，忽略并提示。
+    pub interpreter: Option<String>,
+    /// 除 0 以外视为启动成功的退出码，见 [`DevToolPreset::success_exit_codes`]。
+    pub success_exit_codes: Option<Vec<i32>>,
+    /// 启动 JetBrains IDE 所用的 JDK 目录（其下需有 `bin/java`），通过 `IDEA_JDK`、
+    /// `PYCHARM_JDK` 等按产品区分的环境变量传入。只替换 IDE 自身运行的 JDK，不修改项目 SDK；
+    /// macOS 经 `open` 启动时无法传递环境变量，其他编辑器忽略。
+    pub jdk_path: Option<String>,
+    /// 在指定的桌面（Space，从 1 开始）打开，仅 macOS 且安装了 yabai 时生效，见 [`launch_on_space`]。
+    pub space: Option<u32>,
+    /// VS Code 颜色主题名称（如 `Default Light Modern`），打开前写入项目
+    /// `.vscode/settings.json` 的 `workbench.colorTheme`：文件不存在时新建，已存在时在开头的
+    /// `{` 之后插入该项并保留原有内容与注释；已设置主题时不做修改。其他编辑器忽略。
+    pub color_theme: Option<String>,
+}
+
+impl EditorOpenParams {
+    /// 使用开发工具预设打开指定路径。
+    pub fn from_preset(preset: DevToolPreset, path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            command_path: Some(preset.command_path),
+            arguments: Some(preset.arguments),
+            preset_id: Some(preset.id),
+            preset_kind: Some(preset.kind),
+            success_exit_codes: preset.success_exit_codes,
+            ..Self::default()
+        }
+    }
+}
+
+// 批量逐个启动编辑器时的间隔，避免同时拉起多个进程拖慢系统。
+const OPEN_MANY_STAGGER: Duration = Duration::from_millis(300);
+
+// 临时多根工作区文件的保留时长：VS Code 重新加载窗口或从最近列表打开时仍会读取该文件，
+// 因此不在打开后立即删除，而是在之后的调用中清理过期的文件。
+const MULTIROOT_WORKSPACE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+// 统计目录大小的时间上限，避免超大目录树长时间占用。
+const DIRECTORY_SIZE_TIMEOUT: Duration = Duration::from_secs(15);
+// 可选跳过的依赖与构建产物目录。
+const HEAVY_DIR_NAMES: &[&str] = &["node_modules", "target", ".git"];
+
+// 等待 VS Code 命令行输出扩展列表的时长。
+const VSCODE_CLI_TIMEOUT: Duration = Duration::from_secs(20);
+
+// 以命令行方式运行编辑器的时长上限，JetBrains 的 JVM 启动较慢。
+const EDITOR_ABOUT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 限时运行结束进程树后，等待输出管道关闭的宽限时间。
+const DEADLINE_KILL_GRACE: Duration = Duration::from_secs(1);
+
+// 启动编辑器后等待其窗口出现，再移到目标桌面的时长。
+#[cfg(target_os = "macos")]
+const SPACE_WINDOW_DELAY: Duration = Duration::from_millis(1500);
+
+// 批量诊断预设的总时长，各预设并行检查，超时未完成的单独标记。
+const PRESET_HEALTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 等待 Jupyter 服务输出访问地址的时长，首次启动需要构建扩展时较慢。
+const JUPYTER_START_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 试启动预设时观察进程的时长与轮询间隔。
+const PRESET_TEST_TIMEOUT: Duration = Duration::from_secs(3);
+const PRESET_TEST_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 编辑器家族，决定命令行参数的拼装方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditorFamily {
+    VsCode,
+    JetBrains,
+    Other,
+}
+
+const VSCODE_COMMAND_NAMES: &[&str] = &["code", "code-insiders", "codium"];
+const VSCODE_APP_NAMES: &[&str] = &["visual studio code", "vscodium"];
+const JETBRAINS_COMMAND_NAMES: &[&str] = &[
+    "idea", "pycharm", "webstorm", "goland", "rider", "clion", "phpstorm", "datagrip",
+];
+const JETBRAINS_APP_NAMES: &[&str] = &[
+    "intellij idea",
+    "pycharm",
+    "webstorm",
+    "goland",
+    "rider",
+    "clion",
+    "phpstorm",
+    "datagrip",
+];
+
+/// 编辑器家族对各启动选项的支持情况。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EditorCapabilities {
+    /// 单次调用可打开多个根目录。
+    supports_multi_root: bool,
+    /// 支持禁用扩展/插件的安全模式。
+    supports_safe_mode: bool,
+    /// 支持指定界面语言。
+    supports_locale: bool,
+    /// 显式声明以文件夹方式打开的参数，值为 file URI。
+    folder_flag: Option<&'static str>,
+    /// 显式声明以文件方式打开的参数，值为 file URI。
+    file_flag: Option<&'static str>,
+    /// 强制新开窗口的参数；为 None 时只能依靠启动新进程。
+    new_window_flag: Option<&'static str>,
+    /// 不恢复上次会话、以全新状态打开的参数。
+    fresh_session_flag: Option<&'static str>,
+    /// 以只读模式打开的参数。
+    read_only_flag: Option<&'static str>,
+    /// 使用临时配置文件启动的参数。
+    temp_profile_flag: Option<&'static str>,
+    /// 找不到命令行工具时改用编辑器注册的 URL 协议打开，见 [`editor_url_scheme_uri`]。
+    use_url_scheme_fallback: bool,
+}
+
+fn editor_capabilities(family: EditorFamily) -> EditorCapabilities {
+    match family {
+        EditorFamily::VsCode => EditorCapabilities {
+            supports_multi_root: true,
+            supports_safe_mode: true,
+            supports_locale: true,
+            folder_flag: Some("--folder-uri"),
+            file_flag: Some("--file-uri"),
+            new_window_flag: Some("--new-window"),
+            // 新窗口不会继承已打开窗口的标签页。
+            fresh_session_flag: Some("--new-window"),
+            // 只读只能通过 `files.readonlyInclude` 等设置实现，没有命令行参数。
+            read_only_flag: None,
+            temp_profile_flag: Some("--profile-temp"),
+            use_url_scheme_fallback: true,
+        },
+        // JetBrains 总是把目录当作项目打开，无需额外参数。
+        // 没有新窗口参数，单实例的 IDE 会把新进程的目录交给已运行的实例，在其中新开项目窗口。
+        EditorFamily::JetBrains => EditorCapabilities {
+            supports_multi_root: false,
+            supports_safe_mode: true,
+            supports_locale: true,
+            folder_flag: None,
+            file_flag: None,
+            new_window_flag: None,
+            fresh_session_flag: None,
+            read_only_flag: None,
+            temp_profile_flag: None,
+            use_url_scheme_fallback: true,
+        },
+        EditorFamily::Other => EditorCapabilities {
+            supports_multi_root: false,
+            supports_safe_mode: false,
+            supports_locale: false,
+            folder_flag: None,
+            file_flag: None,
+            new_window_flag: None,
+            fresh_session_flag: None,
+            // 按命令名查表，见 READ_ONLY_COMMAND_FLAGS。
+            read_only_flag: None,
+            temp_profile_flag: None,
+            use_url_scheme_fallback: false,
+        },
+    }
+}
+
+// 其他编辑器中提供只读参数的命令（小写、不含扩展名）。
+const READ_ONLY_COMMAND_FLAGS: &[(&[&str], &str)] = &[
+    (&["vim", "nvim", "gvim", "mvim", "vi"], "-R"),
+    (&["nano"], "-v"),
+];
+
+fn read_only_flag(family: EditorFamily, command_path: Option<&str>) -> Option<&'static str> {
+    editor_capabilities(family).read_only_flag.or_else(|| {
+        if family != EditorFamily::Other {
+            return None;
+        }
+        let stem = command_stem(command_path?);
+        READ_ONLY_COMMAND_FLAGS
+            .iter()
+            .find(|(commands, _)| commands.contains(&stem.as_str()))
+            .map(|(_, flag)| *flag)
+    })
+}
+
+/// 预设是否支持以只读模式打开，供界面决定是否显示只读选项。
+pub fn supports_read_only(preset: &DevToolPreset) -> bool {
+    read_only_flag(preset_editor_family(preset), Some(&preset.command_path)).is_some()
 }
 
-#[derive(Debug, serde::Deserialize)]
+// 文件扩展名到偏好预设的映射，按优先级排列；都未检测到时回退到通用编辑器。
+const PRESET_SUGGESTIONS: &[(&[&str], &[&str])] = &[
+    (&["py", "pyi", "ipynb"], &["pycharm"]),
+    (&["go"], &["goland"]),
+    (
+        &["java", "kt", "kts", "groovy", "scala"],
+        &["intellij-idea"],
+    ),
+    (&["php"], &["phpstorm"]),
+    (&["rs", "c", "cc", "cpp", "h", "hpp"], &["clion"]),
+    (&["sln", "cs", "csproj", "fs", "fsproj"], &["rider"]),
+    (&["js", "jsx", "ts", "tsx", "vue"], &["webstorm"]),
+    (&["sql"], &["datagrip"]),
+];
+const GENERAL_EDITOR_PRESETS: &[&str] = &["vscode", "vscode-insiders"];
+
+#[derive(Debug, Default, serde::Deserialize)]
 pub struct TerminalOpenParams {
     pub path: String,
     pub command_path: Option<String>,
     pub arguments: Option<Vec<String>>,
+    /// 通过登录 shell 启动以继承完整的用户环境（仅 Unix）。
+    pub use_login_shell: Option<bool>,
+    /// 复用已有窗口而非新开窗口，仅对内置终端生效：
+    /// - macOS：iTerm2 是系统默认终端时在其当前窗口新建标签页，没有窗口时新建窗口；
+    ///   否则在 Terminal 最前面的窗口中执行 cd，没有窗口时新建窗口。
+    ///   `command_path` 配置为 iTerm2（`iTerm.app` 或 `open -a iTerm`）时同样新建标签页。
+    /// - Windows：Windows Terminal 使用 `wt -w 0` 在最近的窗口中新开标签页，
+    ///   没有窗口时由 wt 自动新建；回退到 PowerShell 时总是新开窗口。
+    /// - Linux：各终端没有统一的复用窗口方式，忽略该选项，总是新开窗口。
+    pub reuse_window: Option<bool>,
+    /// 严格解析命令：不猜测扩展名、不接受相对路径，用于用户自行填写的命令。
+    pub strict_path_lookup: Option<bool>,
+    /// 在内置终端中创建或接入以该名称命名的 tmux 会话（未安装 tmux 时改用 screen），
+    /// 两者都不存在时退回普通终端。名称中的 `.` 与 `:` 会被替换（仅 macOS / Linux）。
+    pub tmux_session: Option<String>,
+    /// 内置终端进入目录后运行的程序及参数，例如 `["python3"]`；
+    /// 与 `tmux_session` 同时使用时在新建的会话中运行。
+    pub initial_command: Option<Vec<String>>,
+    /// 使用 `command_path` 启动时子进程的工作目录；缺省时为 `path` 本身，
+    /// `path` 是文件或尚未创建时为其所在目录。目录不存在时报错。
+    pub working_dir: Option<String>,
+    /// 内置终端启动前加载项目目录中的 `.env`，见 [`project_env_file`]。
+    pub load_env_file: Option<bool>,
+    /// 内置终端启动前激活项目目录中的 Python 虚拟环境（`.venv` 或 `venv`），
+    /// 见 [`python_venv_activate_script`]。macOS / Linux 由 sh 加载 `bin/activate` 后再启动
+    /// 用户的 shell，虚拟环境的变量会被继承，但不会出现提示符前缀与 `deactivate`；
+    /// Windows 在 PowerShell 会话中执行 `Scripts\Activate.ps1`，并以 `-ExecutionPolicy Bypass`
+    /// 允许当前进程运行该脚本。
+    pub activate_venv: Option<bool>,
+}
+
+/// 启动外部命令时的附加选项。
+#[derive(Debug, Default, Clone)]
+struct LaunchOptions {
+    /// 使用 `$SHELL -lc` 包装命令，使 nvm、rbenv、asdf 等版本管理器修改的 PATH 生效。
+    /// Windows 下没有登录 shell 的概念，该选项被忽略。
+    use_login_shell: bool,
+    /// 裸命令名只在 PATH 的绝对目录中按原名查找，相对路径直接拒绝，
+    /// 也不再尝试补全 Windows 扩展名，见 [`find_in_path_with`]。
+    strict_path_lookup: bool,
+    /// 写入启动审计日志的预设 ID。
+    preset_id: Option<String>,
+    /// 写入启动审计日志的目标路径。
+    target_path: Option<String>,
+    /// 子进程的工作目录，缺省时继承当前进程。
+    working_dir: Option<PathBuf>,
+    /// 追加给子进程的环境变量。
+    env: Vec<(String, String)>,
+    /// 子进程的调度优先级。
+    priority: Priority,
+    /// 除 0 以外视为成功的退出码。
+    success_exit_codes: Option<Vec<i32>>,
 }
 
 /// 在系统文件管理器中定位路径。
-pub fn open_in_finder(path: &str) -> Result<(), String> {
+/// `new_tab` 仅 macOS 生效：在最前面的 Finder 窗口中新开标签页定位，没有窗口时新建窗口。
+/// Finder 的 AppleScript 没有新建标签页的命令，需要通过“系统事件”模拟 ⌘T，
+/// 因此要求本应用具有辅助功能权限；失败时退回新窗口。
+pub fn open_in_finder(path: &str, new_tab: bool) -> Result<(), String> {
+    open_in_finder_verbose(path, new_tab).map(|_| ())
+}
+
+/// 与 [`open_in_finder`] 相同，成功时返回实际执行的命令。
+pub fn open_in_finder_verbose(path: &str, new_tab: bool) -> Result<OpenOutcome, String> {
+    let path = normalize_input_path(path)?;
+    let path = path.as_str();
+    #[cfg(target_os = "macos")]
+    if new_tab {
+        match run_osascript(
+            &finder_tab_script(&absolute_path_string(path)),
+            "无法打开 Finder:",
+            "Finder 新建标签页失败",
+        ) {
+            Ok(outcome) => return Ok(outcome),
+            Err(err) => log::warn!("{}，改为新窗口", err),
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = new_tab;
     if cfg!(target_os = "macos") {
-        let status = Command::new("/usr/bin/open")
-            .args(["-R", path])
+        let mut command = Command::new("/usr/bin/open");
+        command.args(["-R", path]);
+        let status = command
             .status()
             .map_err(|err| format!("无法打开 Finder: {err}"))?;
         if status.success() {
-            return Ok(());
+            return Ok(describe_command(LaunchStrategy::SystemOpen, &command));
         }
-        return Err("Finder 打开失败".to_string());
+        // 未挂载或响应缓慢的网络卷上 `open -R` 可能失败，退而打开所在目录，至少让用户看到文件夹。
+        #[cfg(target_os = "macos")]
+        return reveal_parent_in_finder(path);
+    }
+
+    #[cfg(target_os = "windows")]
+    if Path::new(path).is_file() {
+        return run_explorer(&explorer_select_argument(path));
     }
 
     open_with_default(path)
 }
 
-/// 在终端中打开指定目录。
-pub fn open_in_terminal(params: TerminalOpenParams) -> Result<(), String> {
-    if let Some(command_path) = params.command_path {
-        let arguments = build_command_arguments(params.arguments, &params.path);
-        return run_command_with_shell_support(
-            &command_path,
-            &arguments,
-            "无法打开终端:",
-            "终端打开失败",
+#[cfg(target_os = "macos")]
+fn reveal_parent_in_finder(path: &str) -> Result<OpenOutcome, String> {
+    let absolute = absolute_path_string(path);
+    if let Some(parent) = Path::new(&absolute)
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        log::warn!(
+            "Finder 无法定位 {}，改为打开 {}",
+            absolute,
+            parent.display()
         );
+        if let Ok(outcome) = open_with_default(&parent.to_string_lossy()) {
+            return Ok(outcome);
+        }
     }
-
-    if cfg!(target_os = "windows") {
-        return open_windows_terminal(&params.path);
+    let Some(mount_point) = volume_mount_point(Path::new(&absolute)) else {
+        return Err("Finder 打开失败".to_string());
+    };
+    if is_volume_mounted(&mount_point) {
+        Err(format!(
+            "Finder 打开失败：卷 {} 已挂载，但无法访问该路径",
+            mount_point.display()
+        ))
+    } else {
+        Err(format!(
+            "Finder 打开失败：卷 {} 未挂载，请先连接该卷",
+            mount_point.display()
+        ))
     }
+}
 
-    if cfg!(target_os = "macos") {
-        let escaped_path = params.path.replace('"', "\\\"");
-        let script = format!(
-            "tell application \"Terminal\"\n    do script \"cd \\\"{}\\\"\"\n    activate\nend tell",
-            escaped_path
-        );
-        let status = Command::new("/usr/bin/osascript")
-            .arg("-e")
-            .arg(script)
-            .status()
-            .map_err(|err| format!("无法打开终端: {err}"))?;
-        if status.success() {
-            return Ok(());
-        }
-        return Err("终端打开失败".to_string());
+// 外接与网络卷挂载在 `/Volumes/<卷名>` 下，返回该挂载点；其他路径位于启动卷，返回 None。
+#[cfg(any(target_os = "macos", test))]
+fn volume_mount_point(path: &Path) -> Option<PathBuf> {
+    let mut components = path.components();
+    if components.next() != Some(Component::RootDir) {
+        return None;
+    }
+    if components.next() != Some(Component::Normal("Volumes".as_ref())) {
+        return None;
+    }
+    match components.next() {
+        Some(Component::Normal(name)) => Some(Path::new("/Volumes").join(name)),
+        _ => None,
     }
-
-    open_with_default(&params.path)
 }
 
-/// 使用指定编辑器打开文件或目录。
-pub fn open_in_editor(params: EditorOpenParams) -> Result<(), String> {
-    if cfg!(target_os = "macos") {
-        if let Some(app_name) = params.app_name.clone() {
-            let status = Command::new("/usr/bin/open")
-                .args(["-a", app_name.as_str(), params.path.as_str()])
-                .status()
-                .map_err(|err| format!("打开编辑器失败: {err}"))?;
-            if status.success() {
-                return Ok(());
-            }
-        }
+// 已挂载的卷与 `/Volumes` 位于不同的设备上；未挂载时挂载点不存在，或只是启动卷上残留的空目录。
+#[cfg(target_os = "macos")]
+fn is_volume_mounted(mount_point: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
 
-        if let Some(bundle_id) = params.bundle_id.clone() {
-            let status = Command::new("/usr/bin/open")
-                .args(["-b", bundle_id.as_str(), params.path.as_str()])
-                .status()
-                .map_err(|err| format!("打开编辑器失败: {err}"))?;
-            if status.success() {
-                return Ok(());
-            }
-        }
+    match (fs::metadata(mount_point), fs::metadata("/Volumes")) {
+        (Ok(mount), Ok(volumes)) => mount.dev() != volumes.dev(),
+        _ => false,
     }
+}
+
+/// 在终端中打开指定目录。
+pub fn open_in_terminal(params: TerminalOpenParams) -> Result<(), String> {
+    open_in_terminal_verbose(params).map(|_| ())
+}
 
+/// 与 [`open_in_terminal`] 相同，成功时返回实际执行的命令。
+pub fn open_in_terminal_verbose(mut params: TerminalOpenParams) -> Result<OpenOutcome, String> {
+    params.path = normalize_input_path(&params.path)?;
     if let Some(command_path) = params.command_path {
+        #[cfg(target_os = "macos")]
+        if params.reuse_window == Some(true)
+            && is_iterm_command(
+                &command_path,
+                params.arguments.as_deref().unwrap_or_default(),
+            )
+        {
+            return open_iterm_tab(&params.path, None);
+        }
+        let working_dir = match params.working_dir.as_deref() {
+            Some(dir) => Some(normalize_input_path(dir)?),
+            None => None,
+        };
         let arguments = build_command_arguments(params.arguments, &params.path);
+        let options = LaunchOptions {
+            use_login_shell: params.use_login_shell.unwrap_or(false),
+            strict_path_lookup: params.strict_path_lookup.unwrap_or(false),
+            preset_id: None,
+            working_dir: Some(terminal_command_working_dir(
+                &params.path,
+                working_dir.as_deref(),
+            )?),
+            target_path: Some(params.path.clone()),
+            env: Vec::new(),
+            priority: Priority::Normal,
+            success_exit_codes: None,
+        };
         return run_command_with_shell_support(
             &command_path,
             &arguments,
-            "打开编辑器失败:",
-            "打开编辑器失败",
+            &options,
+            "无法打开终端:",
+            "终端打开失败",
         );
     }
 
-    Err("未能打开编辑器".to_string())
+    let initial_command = params.initial_command.filter(|command| !command.is_empty());
+    let env_file = if params.load_env_file == Some(true) {
+        project_env_file(
+            &terminal_working_dir(&params.path, None),
+            find_in_path("direnv").is_some(),
+        )
+    } else {
+        None
+    };
+    let venv_activate = if params.activate_venv == Some(true) {
+        python_venv_activate_script(
+            &terminal_working_dir(&params.path, None),
+            cfg!(target_os = "windows"),
+        )
+    } else {
+        None
+    };
+    #[cfg(not(target_os = "windows"))]
+    let program = match params
+        .tmux_session
+        .as_deref()
+        .and_then(|name| terminal_session_command(name, &params.path))
+    {
+        Some(mut session) => {
+            session.extend(initial_command.into_iter().flatten());
+            Some(session)
+        }
+        None => initial_command,
+    };
+    #[cfg(not(target_os = "windows"))]
+    let program = if env_file.is_some() || venv_activate.is_some() {
+        Some(posix_session_command(
+            env_file.as_ref().map(|(env_file, _)| env_file.as_path()),
+            venv_activate.as_deref(),
+            program.as_deref(),
+        ))
+    } else {
+        program
+    };
+    #[cfg(target_os = "windows")]
+    let initial_command = if env_file.is_some() || venv_activate.is_some() {
+        Some(powershell_session_command(
+            env_file
+                .as_ref()
+                .map_or(&[][..], |(_, variables)| variables.as_slice()),
+            venv_activate.as_deref(),
+            initial_command.as_deref(),
+        ))
+    } else {
+        initial_command
+    };
+    #[cfg(target_os = "macos")]
+    let result = {
+        let reuse_window = params.reuse_window.unwrap_or(false);
+        if reuse_window && macos_default_terminal_is_iterm() {
+            open_iterm_tab(&params.path, program.as_deref())
+        } else {
+            open_macos_terminal(&params.path, reuse_window, program.as_deref())
+        }
+    };
+    #[cfg(target_os = "windows")]
+    let result = open_windows_terminal(
+        &params.path,
+        params.reuse_window.unwrap_or(false),
+        initial_command.as_deref(),
+    );
+    #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+    let result = {
+        // Linux 终端没有通用的“在已有窗口新开标签页”接口，忽略 reuse_window。
+        let _ = params.reuse_window;
+        open_linux_terminal(&params.path, program.as_deref())
+    };
+    result
 }
 
-/// 列出已检测到的开发工具预设。
-pub fn list_dev_tool_presets() -> Vec<DevToolPreset> {
-    #[cfg(target_os = "macos")]
-    {
-        return list_dev_tool_presets_macos();
+/// 查找需要在终端启动前加载的 `.env`，返回其路径与解析出的变量。
+/// 目录中有 `.envrc` 且已安装 direnv 时交给 direnv 的 shell hook 处理，不再重复加载；
+/// 文件中出现命令替换、未加引号的空白或 shell 元字符等可疑内容时不加载。
+fn project_env_file(
+    dir: &Path,
+    direnv_installed: bool,
+) -> Option<(PathBuf, Vec<(String, String)>)> {
+    if direnv_installed && dir.join(".envrc").is_file() {
+        log::info!("{} 由 direnv 加载环境变量", dir.display());
+        return None;
     }
-    #[cfg(target_os = "windows")]
-    {
-        return list_dev_tool_presets_windows();
+    let env_file = dir.join(".env");
+    let content = fs::read_to_string(&env_file).ok()?;
+    match parse_env_file(&content) {
+        Some(variables) => Some((env_file, variables)),
+        None => {
+            log::warn!("{} 包含无法安全加载的内容，已跳过", env_file.display());
+            None
+        }
     }
-    #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
-    {
-        return list_dev_tool_presets_linux();
+}
+
+// 只接受 `KEY=VALUE`（可带 `export` 前缀）、注释与空行；任何一行可疑时返回 None。
+fn parse_env_file(content: &str) -> Option<Vec<(String, String)>> {
+    let mut variables = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+        let (key, value) = line.split_once('=')?;
+        let mut key_chars = key.chars();
+        if !key_chars
+            .next()
+            .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_')
+            || !key_chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        {
+            return None;
+        }
+        let value = if let Some(inner) = value
+            .strip_prefix('\'')
+            .and_then(|rest| rest.strip_suffix('\''))
+        {
+            if inner.contains('\'') {
+                return None;
+            }
+            inner
+        } else if let Some(inner) = value
+            .strip_prefix('"')
+            .and_then(|rest| rest.strip_suffix('"'))
+        {
+            if inner.contains(['"', '`', '\\']) || inner.contains("$(") {
+                return None;
+            }
+            inner
+        } else {
+            if value
+                .chars()
+                .any(|ch| ch.is_whitespace() || "\"'`\\$;&|<>(){}*?[]!#~".contains(ch))
+            {
+                return None;
+            }
+            value
+        };
+        variables.push((key.to_string(), value.to_string()));
     }
+    Some(variables)
 }
 
-fn build_command_arguments(arguments: Option<Vec<String>>, path: &str) -> Vec<String> {
-    let mut resolved = Vec::new();
-    let mut inserted_path = false;
+const PYTHON_VENV_DIRS: &[&str] = &[".venv", "venv"];
 
-    if let Some(arguments) = arguments {
-        for argument in arguments {
-            if argument.contains("{path}") {
-                resolved.push(argument.replace("{path}", path));
-                inserted_path = true;
+/// 查找项目目录中 Python 虚拟环境的激活脚本：依次检查 `.venv` 与 `venv`，
+/// 返回 `bin/activate`（Windows 为 `Scripts\Activate.ps1`）；脚本不存在时返回 None。
+fn python_venv_activate_script(dir: &Path, windows: bool) -> Option<PathBuf> {
+    PYTHON_VENV_DIRS
+        .iter()
+        .map(|venv| {
+            if windows {
+                dir.join(venv).join("Scripts").join("Activate.ps1")
             } else {
-                resolved.push(argument);
+                dir.join(venv).join("bin").join("activate")
             }
-        }
-    }
+        })
+        .find(|script| script.is_file())
+}
 
-    if !inserted_path {
-        resolved.push(path.to_string());
+// 由 sh 以 `set -a` 加载 `.env` 使变量自动导出、加载虚拟环境的激活脚本，
+// 再 exec 原本要运行的程序或用户的 shell。
+#[cfg(any(not(target_os = "windows"), test))]
+fn posix_session_command(
+    env_file: Option<&Path>,
+    activate: Option<&Path>,
+    program: Option<&[String]>,
+) -> Vec<String> {
+    let mut source = String::new();
+    if let Some(env_file) = env_file {
+        source.push_str(&format!(
+            "set -a; . {}; set +a; ",
+            quote_posix_argument(&env_file.to_string_lossy())
+        ));
     }
-
-    resolved
+    if let Some(activate) = activate {
+        source.push_str(&format!(
+            ". {}; ",
+            quote_posix_argument(&activate.to_string_lossy())
+        ));
+    }
+    let mut command = vec!["/bin/sh".to_string(), "-c".to_string()];
+    match program {
+        Some(program) => {
+            command.push(format!("{source}exec \"$@\""));
+            command.push("sh".to_string());
+            command.extend(program.iter().cloned());
+        }
+        None => command.push(format!("{source}exec \"${{SHELL:-/bin/sh}}\"")),
+    }
+    command
 }
 
-fn run_command_with_shell_support(
-    command_path: &str,
-    arguments: &[String],
-    spawn_error_prefix: &str,
-    failure_message: &str,
-) -> Result<(), String> {
-    let status = spawn_command_with_shell_support(command_path, arguments)
-        .map_err(|err| format!("{spawn_error_prefix} {err}"))?;
-    if status.success() {
-        Ok(())
-    } else {
-        Err(failure_message.to_string())
+// PowerShell 没有 `source .env` 的等价写法，改为逐个设置进程环境变量，
+// 再执行虚拟环境的激活脚本后保持会话。
+#[cfg(any(target_os = "windows", test))]
+fn powershell_session_command(
+    variables: &[(String, String)],
+    activate: Option<&Path>,
+    program: Option<&[String]>,
+) -> Vec<String> {
+    let mut script = String::new();
+    for (key, value) in variables {
+        script.push_str(&format!(
+            "[Environment]::SetEnvironmentVariable({}, {}, 'Process'); ",
+            quote_powershell_literal(key),
+            quote_powershell_literal(value)
+        ));
+    }
+    if let Some(activate) = activate {
+        script.push_str(&format!(
+            "& {}; ",
+            quote_powershell_literal(&activate.to_string_lossy())
+        ));
+    }
+    if let Some(program) = program {
+        script.push('&');
+        for argument in program {
+            script.push(' ');
+            script.push_str(&quote_powershell_literal(argument));
+        }
+    }
+    let mut command = vec!["powershell.exe".to_string(), "-NoExit".to_string()];
+    if activate.is_some() {
+        // 默认的执行策略会阻止运行 Activate.ps1，只对本进程放开。
+        command.extend(["-ExecutionPolicy".to_string(), "Bypass".to_string()]);
     }
+    command.extend([
+        "-EncodedCommand".to_string(),
+        encode_powershell_command(&script),
+    ]);
+    command
 }
 
-#[cfg(target_os = "windows")]
-fn spawn_command_with_shell_support(
-    command_path: &str,
-    arguments: &[String],
-) -> Result<ExitStatus, std::io::Error> {
-    if let Some(kind) = resolve_windows_command_kind(command_path) {
-        return execute_windows_command(kind, command_path, arguments);
+// 终端复用器的会话命令：优先 tmux，其次 screen；都未安装时返回 None，退回普通终端。
+#[cfg(not(target_os = "windows"))]
+fn terminal_session_command(name: &str, path: &str) -> Option<Vec<String>> {
+    let session = terminal_session_name(name, path);
+    if let Some(tmux) = find_in_path("tmux") {
+        return Some(tmux_session_arguments(&tmux, &session, path));
     }
+    if let Some(screen) = find_in_path("screen") {
+        return Some(screen_session_arguments(&screen, &session));
+    }
+    log::warn!("未找到 tmux 或 screen，改为打开普通终端");
+    None
+}
 
-    match Command::new(command_path).args(arguments).status() {
-        Ok(status) => Ok(status),
-        Err(error) => {
-            if let Some((kind, fallback_path)) =
-                resolve_windows_command_fallback(command_path, &error)
-            {
-                execute_windows_command(kind, &fallback_path, arguments)
+// tmux 会把会话名中的 `.` 与 `:` 解析为窗口/窗格分隔符，统一替换为 `_`；
+// 名称为空时使用目录名。
+#[cfg(any(not(target_os = "windows"), test))]
+fn terminal_session_name(name: &str, path: &str) -> String {
+    let name = name.trim();
+    let name = if name.is_empty() {
+        Path::new(path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("devhaven")
+    } else {
+        name
+    };
+    name.chars()
+        .map(|ch| {
+            if matches!(ch, '.' | ':') || ch.is_whitespace() || ch.is_control() {
+                '_'
             } else {
-                Err(error)
+                ch
             }
-        }
-    }
+        })
+        .collect()
 }
 
-#[cfg(not(target_os = "windows"))]
-fn spawn_command_with_shell_support(
-    command_path: &str,
-    arguments: &[String],
-) -> Result<ExitStatus, std::io::Error> {
-    Command::new(command_path).args(arguments).status()
+// `-A` 在会话已存在时直接接入，`-c` 指定新会话的起始目录。
+#[cfg(any(not(target_os = "windows"), test))]
+fn tmux_session_arguments(tmux: &str, session: &str, path: &str) -> Vec<String> {
+    [tmux, "new-session", "-A", "-s", session, "-c", path]
+        .into_iter()
+        .map(str::to_string)
+        .collect()
 }
 
-#[cfg(target_os = "windows")]
-fn resolve_windows_command_kind(command_path: &str) -> Option<WindowsCommandKind> {
-    let extension = Path::new(command_path)
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| ext.to_ascii_lowercase())?;
-    match extension.as_str() {
-        "cmd" | "bat" => Some(WindowsCommandKind::Cmd),
-        "ps1" => Some(WindowsCommandKind::PowerShell),
-        _ => None,
-    }
+// `-D -R` 接入已有会话（必要时先从其他终端分离），不存在时新建；起始目录继承终端的当前目录。
+#[cfg(not(target_os = "windows"))]
+fn screen_session_arguments(screen: &str, session: &str) -> Vec<String> {
+    [screen, "-D", "-R", "-S", session]
+        .into_iter()
+        .map(str::to_string)
+        .collect()
 }
 
-#[cfg(target_os = "windows")]
-fn resolve_windows_command_fallback(
-    command_path: &str,
-    error: &std::io::Error,
-) -> Option<(WindowsCommandKind, String)> {
-    if Path::new(command_path).extension().is_some() || !should_try_windows_fallback(error) {
-        return None;
-    }
-    let base_path = Path::new(command_path);
-    for (extension, kind) in [
-        ("cmd", WindowsCommandKind::Cmd),
-        ("bat", WindowsCommandKind::Cmd),
-        ("ps1", WindowsCommandKind::PowerShell),
-        ("exe", WindowsCommandKind::Direct),
-        ("com", WindowsCommandKind::Direct),
-    ] {
-        let candidate = base_path.with_extension(extension);
-        if candidate.is_file() {
-            return Some((kind, candidate.to_string_lossy().to_string()));
+// 终端启动后执行的命令：先进入目录，再按需运行复用器会话或其他程序。
+#[cfg(any(target_os = "macos", test))]
+fn terminal_startup_command(path: &str, program: Option<&[String]>) -> String {
+    let mut command = format!("cd {}", quote_posix_argument(path));
+    if let Some(program) = program {
+        command.push_str(" &&");
+        for argument in program {
+            command.push(' ');
+            command.push_str(&quote_posix_argument(argument));
         }
     }
-    None
+    command
 }
 
-#[cfg(target_os = "windows")]
-fn should_try_windows_fallback(error: &std::io::Error) -> bool {
-    matches!(
-        error.raw_os_error(),
-        Some(2) | Some(3) | Some(193) | Some(216)
-    )
-}
+// 常见的交互式解释器：预设 ID、显示名与按优先级排列的命令名。
+const REPL_PRESETS: &[(&str, &str, &[&str])] = &[
+    ("python", "Python", &["python3", "python"]),
+    ("node", "Node.js", &["node"]),
+    ("deno", "Deno", &["deno"]),
+    ("irb", "Ruby (irb)", &["irb"]),
+    ("iex", "Elixir (iex)", &["iex"]),
+    ("ghci", "Haskell (ghci)", &["ghci"]),
+];
 
-#[cfg(target_os = "windows")]
-#[derive(Clone, Copy)]
-enum WindowsCommandKind {
-    Direct,
-    Cmd,
-    PowerShell,
+/// 列出 PATH 中可用的交互式解释器（REPL），预设 ID 形如 `repl-python`。
+pub fn list_repl_presets() -> Vec<DevToolPreset> {
+    REPL_PRESETS
+        .iter()
+        .filter_map(|(id, name, commands)| {
+            let command_path = commands.iter().find_map(|command| find_in_path(command))?;
+            Some(DevToolPreset {
+                id: format!("repl-{id}"),
+                name: name.to_string(),
+                kind: PresetKind::Command,
+                install_kind: install_kind_from_path(&command_path)
+                    .unwrap_or(InstallKind::PathShim),
+                command_path,
+                arguments: Vec::new(),
+                success_exit_codes: None,
+            })
+        })
+        .collect()
 }
 
-#[cfg(target_os = "windows")]
-fn execute_windows_command(
-    kind: WindowsCommandKind,
-    executable: &str,
-    arguments: &[String],
-) -> Result<ExitStatus, std::io::Error> {
-    match kind {
-        WindowsCommandKind::Direct => Command::new(executable).args(arguments).status(),
-        WindowsCommandKind::Cmd => Command::new("cmd.exe")
-            .arg("/C")
-            .arg(executable)
-            .args(arguments)
-            .status(),
-        WindowsCommandKind::PowerShell => Command::new("powershell.exe")
-            .arg("-NoProfile")
-            .arg("-ExecutionPolicy")
-            .arg("Bypass")
-            .arg("-File")
-            .arg(executable)
-            .args(arguments)
-            .status(),
-    }
+/// 在内置终端中打开目录并运行 REPL，参数中的 `{path}` 会被替换为该目录。
+pub fn open_repl(path: &str, repl: &DevToolPreset, reuse_window: bool) -> Result<(), String> {
+    let path = normalize_input_path(path)?;
+    open_in_terminal(TerminalOpenParams {
+        initial_command: Some(repl_command_line(repl, &path)),
+        reuse_window: Some(reuse_window),
+        path,
+        ..TerminalOpenParams::default()
+    })
 }
 
-/// 复制文本到系统剪贴板（跨平台）。
-pub fn copy_to_clipboard(app: &AppHandle, content: &str) -> Result<(), String> {
-    if let Err(err) = app.clipboard().write_text(content.to_string()) {
-        #[cfg(target_os = "macos")]
-        {
-            let _ = err;
-            return copy_with_pbcopy(content).map_err(|err| format!("写入剪贴板失败: {err}"));
-        }
-        #[cfg(not(target_os = "macos"))]
-        {
-            return Err(format!("写入剪贴板失败: {err}"));
-        }
-    }
-    Ok(())
+fn repl_command_line(repl: &DevToolPreset, path: &str) -> Vec<String> {
+    std::iter::once(repl.command_path.clone())
+        .chain(
+            repl.arguments
+                .iter()
+                .map(|argument| expand_path_placeholder(argument, path).0),
+        )
+        .collect()
 }
 
-#[cfg(target_os = "macos")]
-fn copy_with_pbcopy(content: &str) -> Result<(), std::io::Error> {
-    let mut child = Command::new("/usr/bin/pbcopy")
-        .stdin(Stdio::piped())
-        .spawn()?;
-    if let Some(stdin) = child.stdin.as_mut() {
-        stdin.write_all(content.as_bytes())?;
-    }
-    let status = child.wait()?;
-    if status.success() {
-        Ok(())
-    } else {
-        Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "写入剪贴板失败",
-        ))
-    }
+/// 在目录中启动 Jupyter 服务（默认 `jupyter lab`，`classic` 为真时 `jupyter notebook`），
+/// 从其输出中读取带 token 的本机访问地址，在默认浏览器中打开并返回该地址。
+/// 服务作为后台进程持续运行，由后台线程继续读取输出以免管道写满阻塞；超过时限仍未输出
+/// 地址时结束进程并返回错误。
+pub fn open_in_jupyter(path: &str, classic: bool) -> Result<String, String> {
+    let path = normalize_input_path(path)?;
+    let dir = terminal_working_dir(&path, None);
+    let jupyter =
+        find_in_path("jupyter").ok_or("未找到 jupyter，请先安装 JupyterLab 或 Jupyter Notebook")?;
+    let mut child = Command::new(&jupyter)
+        .arg(if classic { "notebook" } else { "lab" })
+        .arg("--no-browser")
+        .arg(format!("--notebook-dir={}", dir.display()))
+        .current_dir(&dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("无法启动 jupyter: {err}"))?;
+    // Jupyter 把地址写到日志（标准错误），两个管道都读取以兼容不同版本。
+    let (sender, receiver) = mpsc::channel();
+    forward_jupyter_url(
+        child.stdout.take().ok_or("无法读取命令输出")?,
+        sender.clone(),
+    );
+    forward_jupyter_url(child.stderr.take().ok_or("无法读取命令输出")?, sender);
+    let url = match receiver.recv_timeout(JUPYTER_START_TIMEOUT) {
+        Ok(url) => url,
+        Err(error) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(match error {
+                mpsc::RecvTimeoutError::Timeout => "等待 Jupyter 输出访问地址超时".to_string(),
+                mpsc::RecvTimeoutError::Disconnected => "Jupyter 未能启动".to_string(),
+            });
+        }
+    };
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    log::info!("Jupyter 已启动 {}", dir.display());
+    open_url_in_browser(&url)?;
+    Ok(url)
 }
 
-// 使用系统默认方式打开路径。
-#[cfg(target_os = "macos")]
-fn open_with_default(path: &str) -> Result<(), String> {
-    let status = Command::new("/usr/bin/open")
-        .arg(path)
-        .status()
-        .map_err(|err| format!("无法打开路径: {err}"))?;
-    if status.success() {
-        Ok(())
-    } else {
-        Err("打开路径失败".to_string())
-    }
+// 逐行读取输出，发送第一个访问地址后继续读完，避免服务因管道写满而阻塞。
+fn forward_jupyter_url(pipe: impl Read + Send + 'static, sender: mpsc::Sender<String>) {
+    use std::io::{BufRead, BufReader};
+
+    std::thread::spawn(move || {
+        let mut sent = false;
+        for line in BufReader::new(pipe).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if !sent {
+                if let Some(url) = parse_jupyter_url(&line) {
+                    sent = sender.send(url).is_ok();
+                }
+            }
+        }
+    });
 }
 
-#[cfg(target_os = "macos")]
-fn list_dev_tool_presets_macos() -> Vec<DevToolPreset> {
-    let mut presets = Vec::new();
-    push_macos_app(
-        &mut presets,
-        "vscode",
-        "Visual Studio Code",
-        "Visual Studio Code",
-    );
-    push_macos_app(
-        &mut presets,
-        "vscode-insiders",
-        "Visual Studio Code - Insiders",
-        "Visual Studio Code - Insiders",
-    );
+// 取输出行中指向本机的地址，例如 `or http://127.0.0.1:8888/lab?token=abc`；
+// 同时输出的主机名地址在部分网络下无法解析，因此跳过。
+fn parse_jupyter_url(line: &str) -> Option<String> {
+    let start = line.find("http://").or_else(|| line.find("https://"))?;
+    let url = line[start..].split_whitespace().next()?;
+    let host = url.split("://").nth(1)?.split(['/', ':']).next()?;
+    matches!(host, "127.0.0.1" | "localhost").then(|| url.to_string())
+}
 
-    if !push_macos_app(
-        &mut presets,
-        "intellij-idea",
-        "IntelliJ IDEA",
-        "IntelliJ IDEA",
-    ) {
-        push_macos_app(
-            &mut presets,
-            "intellij-idea",
-            "IntelliJ IDEA Community",
-            "IntelliJ IDEA CE",
-        );
-    }
+// 沙箱内只读可见的系统目录，不存在的目录会被 bwrap 跳过。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+const SANDBOX_SYSTEM_DIRS: &[&str] = &[
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/etc",
+    "/opt",
+    "/tmp/.X11-unix",
+];
 
-    if !push_macos_app(&mut presets, "pycharm", "PyCharm", "PyCharm") {
-        push_macos_app(&mut presets, "pycharm", "PyCharm Community", "PyCharm CE");
+// 用 bwrap 包装编辑器命令；未安装 bwrap 时原样返回。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn sandboxed_command(
+    command_path: &str,
+    arguments: Vec<String>,
+    project: &str,
+    family: EditorFamily,
+) -> (String, Vec<String>) {
+    let Some(bwrap) = find_in_path("bwrap") else {
+        log::warn!("未找到 bubblewrap（bwrap），不使用沙箱启动编辑器");
+        return (command_path.to_string(), arguments);
+    };
+    let editor = resolve_command_path(command_path).unwrap_or_else(|| command_path.to_string());
+    let mut read_only: Vec<PathBuf> = SANDBOX_SYSTEM_DIRS.iter().map(PathBuf::from).collect();
+    // 编辑器安装在系统目录之外（如 ~/.local 或 Toolbox）时，额外挂载其安装目录。
+    if let Some(install_dir) = fs::canonicalize(&editor)
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+    {
+        read_only.push(install_dir);
     }
+    read_only.extend(sandbox_display_paths(|key| std::env::var(key).ok()));
+    let writable: Vec<PathBuf> = editor_data_dirs(family)
+        .into_iter()
+        .filter(|dir| dir.is_dir())
+        .collect();
 
-    push_macos_app(&mut presets, "webstorm", "WebStorm", "WebStorm");
-    push_macos_app(&mut presets, "goland", "GoLand", "GoLand");
-    push_macos_app(&mut presets, "rider", "Rider", "Rider");
-    push_macos_app(&mut presets, "clion", "CLion", "CLion");
-    push_macos_app(&mut presets, "phpstorm", "PhpStorm", "PhpStorm");
-    push_macos_app(&mut presets, "datagrip", "DataGrip", "DataGrip");
+    let mut sandboxed = bwrap_arguments(Path::new(project), &read_only, &writable);
+    sandboxed.push(editor);
+    sandboxed.extend(arguments);
+    (bwrap, sandboxed)
+}
 
-    presets
+// 沙箱内显示所需的路径：Wayland 套接字（`$WAYLAND_DISPLAY` 为相对名称时位于
+// `$XDG_RUNTIME_DIR` 下）与 X11 授权文件。只挂载这些文件而不挂载整个运行时目录，
+// 以免沙箱内经会话总线或已运行编辑器的 IPC 套接字绕过隔离。
+#[cfg(any(all(not(target_os = "macos"), not(target_os = "windows")), test))]
+fn sandbox_display_paths(lookup_var: impl Fn(&str) -> Option<String>) -> Vec<PathBuf> {
+    let lookup = |key: &str| lookup_var(key).filter(|value| !value.is_empty());
+    let mut paths = Vec::new();
+    if let Some(display) = lookup("WAYLAND_DISPLAY") {
+        let display = PathBuf::from(display);
+        if display.is_absolute() {
+            paths.push(display);
+        } else if let Some(runtime_dir) = lookup("XDG_RUNTIME_DIR") {
+            paths.push(Path::new(&runtime_dir).join(display));
+        }
+    }
+    if let Some(xauthority) = lookup("XAUTHORITY") {
+        paths.push(PathBuf::from(xauthority));
+    }
+    paths
 }
 
-#[cfg(target_os = "macos")]
-fn push_macos_app(
-    presets: &mut Vec<DevToolPreset>,
-    id: &str,
-    display_name: &str,
-    app_name: &str,
-) -> bool {
-    let bundle_path = Path::new("/Applications").join(format!("{app_name}.app"));
-    if !bundle_path.exists() {
-        return false;
+// 编辑器在用户目录下读写的配置、扩展与缓存目录。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn editor_data_dirs(family: EditorFamily) -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from);
+    let in_dir = |dir: Option<PathBuf>, names: &[&str]| -> Vec<PathBuf> {
+        dir.map(|dir| names.iter().map(|name| dir.join(name)).collect())
+            .unwrap_or_default()
+    };
+    match family {
+        EditorFamily::VsCode => [
+            in_dir(xdg_config_dir(), &["Code", "Code - Insiders"]),
+            in_dir(home, &[".vscode", ".vscode-insiders"]),
+        ]
+        .concat(),
+        EditorFamily::JetBrains => [
+            in_dir(xdg_config_dir(), &["JetBrains"]),
+            in_dir(xdg_data_dir(), &["JetBrains"]),
+            in_dir(xdg_cache_dir(), &["JetBrains"]),
+            in_dir(home, &[".java"]),
+        ]
+        .concat(),
+        EditorFamily::Other => Vec::new(),
     }
-    presets.push(DevToolPreset {
-        id: id.to_string(),
-        name: display_name.to_string(),
-        command_path: "/usr/bin/open".to_string(),
-        arguments: vec!["-a".to_string(), app_name.to_string(), "{path}".to_string()],
-    });
-    true
 }
 
-#[cfg(target_os = "windows")]
-fn list_dev_tool_presets_windows() -> Vec<DevToolPreset> {
-    let mut presets = Vec::new();
+// 新建除网络外的全部命名空间，根目录为空，只挂载给定目录；项目路径必须存在。
+#[cfg(any(all(not(target_os = "macos"), not(target_os = "windows")), test))]
+fn bwrap_arguments(project: &Path, read_only: &[PathBuf], writable: &[PathBuf]) -> Vec<String> {
+    let mut arguments: Vec<String> = [
+        "--unshare-all",
+        "--share-net",
+        "--new-session",
+        "--proc",
+        "/proc",
+        "--dev",
+        "/dev",
+        "--tmpfs",
+        "/tmp",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect();
+    let mounts = read_only
+        .iter()
+        .map(|dir| ("--ro-bind-try", dir.as_path()))
+        .chain(writable.iter().map(|dir| ("--bind-try", dir.as_path())))
+        .chain([("--bind", project)]);
+    for (flag, dir) in mounts {
+        let dir = dir.to_string_lossy().to_string();
+        arguments.extend([flag.to_string(), dir.clone(), dir]);
+    }
+    arguments.push("--".to_string());
+    arguments
+}
 
-    if let Some(path) = find_windows_vscode() {
-        presets.push(build_windows_preset("vscode", "Visual Studio Code", path));
+// 终端命令的工作目录：优先使用显式指定的目录，否则取目标目录或目标文件所在目录。
+fn terminal_working_dir(path: &str, working_dir: Option<&str>) -> PathBuf {
+    if let Some(dir) = working_dir {
+        return PathBuf::from(dir);
     }
-    if let Some(path) = find_windows_vscode_insiders() {
-        presets.push(build_windows_preset(
-            "vscode-insiders",
-            "Visual Studio Code - Insiders",
-            path,
-        ));
+    let path = Path::new(path);
+    match path.parent() {
+        Some(parent) if path.is_file() => parent.to_path_buf(),
+        _ => path.to_path_buf(),
     }
+}
 
-    if let Some(path) = find_jetbrains_toolbox_exe("IDEA-U", "idea64.exe")
-        .or_else(|| find_jetbrains_toolbox_exe("IDEA-C", "idea64.exe"))
-        .or_else(|| find_jetbrains_install_exe("idea64.exe"))
-    {
-        let name = if path.to_string_lossy().to_lowercase().contains("idea-c") {
-            "IntelliJ IDEA Community"
-        } else {
-            "IntelliJ IDEA"
-        };
-        presets.push(build_windows_preset("intellij-idea", name, path));
+// 使用 `command_path` 启动终端时的工作目录，见 [`terminal_working_dir`]。目标尚未创建时
+// 改用其所在目录；目录不存在时给出明确的错误，而不是启动时的“找不到文件”。
+fn terminal_command_working_dir(path: &str, working_dir: Option<&str>) -> Result<PathBuf, String> {
+    let dir = terminal_working_dir(path, working_dir);
+    if dir.is_dir() {
+        return Ok(dir);
     }
-
-    if let Some(path) = find_jetbrains_toolbox_exe("PyCharm-P", "pycharm64.exe")
-        .or_else(|| find_jetbrains_toolbox_exe("PyCharm-C", "pycharm64.exe"))
-        .or_else(|| find_jetbrains_install_exe("pycharm64.exe"))
-    {
-        let name = if path.to_string_lossy().to_lowercase().contains("pycharm-c") {
-            "PyCharm Community"
-        } else {
-            "PyCharm"
-        };
-        presets.push(build_windows_preset("pycharm", name, path));
+    if working_dir.is_none() && !dir.exists() {
+        if let Some(parent) = dir.parent().filter(|parent| parent.is_dir()) {
+            return Ok(parent.to_path_buf());
+        }
     }
+    Err(format!("工作目录不存在: {}", dir.display()))
+}
 
-    add_jetbrains_windows_preset(
-        &mut presets,
-        "webstorm",
-        "WebStorm",
-        "WebStorm",
-        "webstorm64.exe",
-    );
-    add_jetbrains_windows_preset(&mut presets, "goland", "GoLand", "GoLand", "goland64.exe");
-    add_jetbrains_windows_preset(&mut presets, "rider", "Rider", "Rider", "rider64.exe");
-    add_jetbrains_windows_preset(&mut presets, "clion", "CLion", "CLion", "clion64.exe");
-    add_jetbrains_windows_preset(
-        &mut presets,
-        "phpstorm",
-        "PhpStorm",
-        "PhpStorm",
-        "phpstorm64.exe",
-    );
-    add_jetbrains_windows_preset(
-        &mut presets,
-        "datagrip",
-        "DataGrip",
-        "DataGrip",
-        "datagrip64.exe",
-    );
+/// 使用指定编辑器打开文件或目录。
+pub fn open_in_editor(params: EditorOpenParams) -> Result<(), String> {
+    open_in_editor_verbose(params).map(|_| ())
+}
 
-    presets
+/// 在新的编辑器窗口中打开目录，而不是复用已打开的窗口：
+/// - VS Code 追加 `--new-window`；
+/// - JetBrains 没有对应参数，启动新进程即会新开项目窗口，但仍属于已运行的 IDE 实例；
+/// - macOS 经 `open` 启动时追加 `-n`，要求 LaunchServices 启动新的应用实例；
+///   VS Code 与 JetBrains 的新实例会把请求转交给已运行的实例，无法得到真正独立的进程；
+/// - 其他编辑器是否新开窗口取决于其自身行为。
+pub fn open_in_new_editor_window(params: EditorOpenParams) -> Result<OpenOutcome, String> {
+    open_in_editor_verbose(EditorOpenParams {
+        new_window: Some(true),
+        ..params
+    })
 }
 
-#[cfg(target_os = "windows")]
-fn add_jetbrains_windows_preset(
-    presets: &mut Vec<DevToolPreset>,
-    id: &str,
-    name: &str,
-    toolbox_code: &str,
-    exe_name: &str,
-) {
-    if let Some(path) = find_jetbrains_toolbox_exe(toolbox_code, exe_name)
-        .or_else(|| find_jetbrains_install_exe(exe_name))
+/// 与 [`open_in_editor`] 相同，成功时返回实际执行的命令。
+pub fn open_in_editor_verbose(params: EditorOpenParams) -> Result<OpenOutcome, String> {
+    let family = resolve_editor_family(&params);
+    let mut warnings = Vec::new();
+    if params.read_only == Some(true)
+        && read_only_flag(family, params.command_path.as_deref()).is_none()
     {
-        presets.push(build_windows_preset(id, name, path));
+        warnings.push("该编辑器不支持只读模式，已按普通模式打开".to_string());
+    }
+    if let Some(interpreter) = params.interpreter.as_deref() {
+        let interpreter = absolute_path_string(&normalize_input_path(interpreter)?);
+        if !Path::new(&interpreter).exists() {
+            return Err(format!("解释器不存在: {interpreter}"));
+        }
+        if family == EditorFamily::VsCode {
+            let dir = terminal_working_dir(&normalize_input_path(&params.path)?, None);
+            warnings.extend(inject_vscode_setting(
+                &dir,
+                VSCODE_INTERPRETER_SETTING,
+                &interpreter,
+            )?);
+        } else {
+            warnings.push("该编辑器不支持指定 Python 解释器，已忽略".to_string());
+        }
+    }
+    if let Some(theme) = params.color_theme.as_deref() {
+        let theme = theme.trim();
+        if theme.is_empty() {
+            return Err("颜色主题名称不能为空".to_string());
+        }
+        if family == EditorFamily::VsCode {
+            let dir = terminal_working_dir(&normalize_input_path(&params.path)?, None);
+            warnings.extend(inject_vscode_setting(&dir, VSCODE_THEME_SETTING, theme)?);
+        } else {
+            warnings.push("该编辑器不支持指定颜色主题，已忽略".to_string());
+        }
     }
+    let mut outcome = match params.space {
+        Some(space) => {
+            let app_name = params.app_name.clone();
+            launch_on_space(space, app_name, || launch_editor(params))?
+        }
+        None => launch_editor(params)?,
+    };
+    outcome.warnings.extend(warnings);
+    Ok(outcome)
 }
 
-#[cfg(target_os = "windows")]
-fn build_windows_preset(id: &str, name: &str, command_path: PathBuf) -> DevToolPreset {
-    DevToolPreset {
-        id: id.to_string(),
-        name: name.to_string(),
-        command_path: command_path.to_string_lossy().to_string(),
-        arguments: vec!["{path}".to_string()],
+/// 在指定桌面（Space）打开编辑器，任何一步失败都只记录日志、不影响打开本身：
+/// 启动后在后台线程中稍等窗口出现，再按应用名找到编辑器的窗口并用 yabai 移到目标桌面。
+/// yabai 是唯一支持的移动方式：System Events 的辅助功能接口只能调整窗口的位置与大小，
+/// 没有把窗口分配到其他桌面的操作，用 osascript 无法实现。yabai 移动窗口需要在
+/// “系统设置 > 隐私与安全性 > 辅助功能”中为其授权；未安装 yabai 或无法确定编辑器的
+/// 应用名时照常在当前桌面打开。
+#[cfg(target_os = "macos")]
+fn launch_on_space(
+    space: u32,
+    app_name: Option<String>,
+    launch: impl FnOnce() -> Result<OpenOutcome, String>,
+) -> Result<OpenOutcome, String> {
+    if space == 0 {
+        return Err("桌面编号从 1 开始".to_string());
     }
+    let Some(yabai) = find_in_path("yabai") else {
+        log::warn!("未安装 yabai，无法把窗口移到桌面 {}", space);
+        return launch();
+    };
+    let outcome = launch()?;
+    let Some(app) = space_window_app_name(&outcome, app_name.as_deref()) else {
+        log::warn!("无法确定编辑器的应用名，未移动窗口到桌面 {}", space);
+        return Ok(outcome);
+    };
+    std::thread::spawn(move || {
+        std::thread::sleep(SPACE_WINDOW_DELAY);
+        if let Err(err) = move_app_window_with_yabai(&yabai, &app, space) {
+            log::warn!("移动 {} 的窗口到桌面 {} 失败: {}", app, space, err);
+        }
+    });
+    Ok(outcome)
 }
 
-#[cfg(target_os = "windows")]
-fn find_windows_vscode() -> Option<PathBuf> {
-    find_windows_path(
-        &["ProgramFiles", "ProgramFiles(x86)", "LOCALAPPDATA"],
-        &[
-            PathBuf::from("Microsoft VS Code\\Code.exe"),
-            PathBuf::from("Programs\\Microsoft VS Code\\Code.exe"),
-        ],
-    )
-    .or_else(|| find_in_path("code").map(PathBuf::from))
+#[cfg(not(target_os = "macos"))]
+fn launch_on_space(
+    _space: u32,
+    _app_name: Option<String>,
+    launch: impl FnOnce() -> Result<OpenOutcome, String>,
+) -> Result<OpenOutcome, String> {
+    launch()
 }
 
-#[cfg(target_os = "windows")]
-fn find_windows_vscode_insiders() -> Option<PathBuf> {
-    find_windows_path(
-        &["ProgramFiles", "ProgramFiles(x86)", "LOCALAPPDATA"],
-        &[
-            PathBuf::from("Microsoft VS Code Insiders\\Code - Insiders.exe"),
-            PathBuf::from("Programs\\Microsoft VS Code Insiders\\Code - Insiders.exe"),
-        ],
-    )
-    .or_else(|| find_in_path("code-insiders").map(PathBuf::from))
+// 编辑器窗口所属的应用名：优先取 `open -a <应用>` 实际使用的应用（可能是 .app 路径），
+// 其次取参数中的应用名。
+#[cfg(any(target_os = "macos", test))]
+fn space_window_app_name(outcome: &OpenOutcome, app_name: Option<&str>) -> Option<String> {
+    let launched = outcome
+        .args
+        .windows(2)
+        .find(|pair| pair[0] == "-a")
+        .map(|pair| pair[1].as_str());
+    let name = launched.or(app_name)?;
+    let name = Path::new(name.trim_end_matches('/'))
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(name);
+    Some(clean_macos_app_name(name)).filter(|name| !name.is_empty())
 }
 
-#[cfg(target_os = "windows")]
-fn find_windows_path(env_keys: &[&str], suffixes: &[PathBuf]) -> Option<PathBuf> {
-    for key in env_keys {
-        if let Ok(root) = std::env::var(key) {
-            let root_path = PathBuf::from(root);
-            for suffix in suffixes {
-                let candidate = root_path.join(suffix);
-                if candidate.is_file() {
-                    return Some(candidate);
-                }
-            }
-        }
+#[cfg(target_os = "macos")]
+fn move_app_window_with_yabai(yabai: &str, app: &str, space: u32) -> Result<(), String> {
+    let output = Command::new(yabai)
+        .args(["-m", "query", "--windows"])
+        .output()
+        .map_err(|err| format!("无法运行 yabai: {err}"))?;
+    if !output.status.success() {
+        return Err("无法获取窗口列表".to_string());
     }
-    None
+    let window_id = find_yabai_app_window(&String::from_utf8_lossy(&output.stdout), app)
+        .ok_or("未找到编辑器窗口")?;
+    let status = Command::new(yabai)
+        .args([
+            "-m",
+            "window",
+            &window_id.to_string(),
+            "--space",
+            &space.to_string(),
+        ])
+        .status()
+        .map_err(|err| format!("无法运行 yabai: {err}"))?;
+    if !status.success() {
+        return Err(format!("yabai 执行失败: {status}"));
+    }
+    Ok(())
 }
 
-#[cfg(target_os = "windows")]
-fn find_jetbrains_toolbox_exe(product_code: &str, exe_name: &str) -> Option<PathBuf> {
-    let local = std::env::var("LOCALAPPDATA").ok()?;
-    let base = PathBuf::from(local)
-        .join("JetBrains")
-        .join("Toolbox")
-        .join("apps")
-        .join(product_code)
-        .join("ch-0");
-    if !base.is_dir() {
-        return None;
+// `yabai -m query --windows` 输出所有窗口的 JSON 数组，从中找出属于该应用的窗口：
+// 优先取获得焦点的窗口，否则取 ID 最大（最近创建）的窗口。
+#[cfg(any(target_os = "macos", test))]
+fn find_yabai_app_window(output: &str, app: &str) -> Option<u64> {
+    let windows: Vec<serde_json::Value> = serde_json::from_str(output).ok()?;
+    windows
+        .iter()
+        .filter(|window| {
+            window
+                .get("app")
+                .and_then(|name| name.as_str())
+                .is_some_and(|name| name.eq_ignore_ascii_case(app))
+        })
+        .filter_map(|window| {
+            let id = window.get("id")?.as_u64()?;
+            let focused = window
+                .get("has-focus")
+                .and_then(|focused| focused.as_bool())
+                .unwrap_or(false);
+            Some((focused, id))
+        })
+        .max()
+        .map(|(_, id)| id)
+}
+
+const VSCODE_INTERPRETER_SETTING: &str = "python.defaultInterpreterPath";
+
+const VSCODE_THEME_SETTING: &str = "workbench.colorTheme";
+
+// 在工作区设置中写入一项配置：没有 settings.json 时借助初始化文件新建；已有时按 JSONC
+// 解析，未设置该项则在开头的 `{` 之后插入，保留其余内容（包括注释）。已设置为其他值或
+// 文件无法解析时不做修改，返回提示。
+fn inject_vscode_setting(dir: &Path, key: &str, value: &str) -> Result<Option<String>, String> {
+    let relative = ".vscode/settings.json".to_string();
+    let content =
+        serde_json::to_string_pretty(&serde_json::json!({ key: value })).unwrap_or_default();
+    let result = write_bootstrap_files(dir, &[(relative.clone(), content)])?;
+    if result.skipped.is_empty() {
+        return Ok(None);
     }
-    let mut builds: Vec<PathBuf> = Vec::new();
-    if let Ok(entries) = fs::read_dir(base) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_dir() {
-                let candidate = path.join("bin").join(exe_name);
-                if candidate.is_file() {
-                    builds.push(path);
-                }
-            }
-        }
+    let root = fs::canonicalize(dir).map_err(|err| format!("读取项目路径失败: {err}"))?;
+    let target = fs::canonicalize(dir.join(&relative))
+        .map_err(|err| format!("读取 {relative} 失败: {err}"))?;
+    if !target.starts_with(&root) {
+        return Err(format!("初始化文件路径越界: {relative}"));
     }
-    builds.sort_by(|left, right| left.file_name().cmp(&right.file_name()));
-    let latest = builds.pop()?;
-    let exe_path = latest.join("bin").join(exe_name);
-    if exe_path.is_file() {
-        Some(exe_path)
-    } else {
-        None
+    let existing =
+        fs::read_to_string(&target).map_err(|err| format!("读取 {relative} 失败: {err}"))?;
+    let stripped = strip_jsonc(&existing);
+    let Some(settings) = serde_json::from_str::<serde_json::Value>(&stripped)
+        .ok()
+        .filter(serde_json::Value::is_object)
+    else {
+        return Ok(Some(format!(
+            "项目的 .vscode/settings.json 格式无法识别，未写入 {key}"
+        )));
+    };
+    if let Some(current) = settings.get(key) {
+        return Ok((current.as_str() != Some(value)).then(|| {
+            format!("项目 .vscode/settings.json 已将 {key} 设为 {current}，未改为 {value}")
+        }));
     }
+    // 注释被替换为等长空白，stripped 中第一个 `{` 的位置即原文中对象的起点。
+    let offset = stripped.find('{').unwrap_or_default();
+    let separator = if stripped[offset + 1..].trim_start().starts_with('}') {
+        ""
+    } else {
+        ","
+    };
+    let entry = serde_json::to_string(value).unwrap_or_default();
+    let updated = format!(
+        "{}{{\n  \"{key}\": {entry}{separator}{}",
+        &existing[..offset],
+        &existing[offset + 1..]
+    );
+    fs::write(&target, updated).map_err(|err| format!("写入 {relative} 失败: {err}"))?;
+    Ok(None)
 }
 
-#[cfg(target_os = "windows")]
-fn find_jetbrains_install_exe(exe_name: &str) -> Option<PathBuf> {
-    let mut roots: Vec<PathBuf> = Vec::new();
-    if let Ok(path) = std::env::var("ProgramFiles") {
-        roots.push(PathBuf::from(path).join("JetBrains"));
-    }
-    if let Ok(path) = std::env::var("ProgramFiles(x86)") {
-        roots.push(PathBuf::from(path).join("JetBrains"));
+// 把 JSONC 转成可按 JSON 解析的文本：注释替换为等长空白（保留换行），去掉 `}`/`]`
+// 前多余的逗号。字符串内的内容原样保留，各字节位置不变。
+fn strip_jsonc(text: &str) -> String {
+    fn blank(out: &mut String, ch: char) {
+        if ch == '\n' {
+            out.push('\n');
+        } else {
+            out.push_str(&" ".repeat(ch.len_utf8()));
+        }
     }
-    if let Ok(path) = std::env::var("LOCALAPPDATA") {
-        let local_path = PathBuf::from(&path);
-        roots.push(local_path.join("JetBrains"));
-        roots.push(local_path.join("Programs").join("JetBrains"));
+
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    let mut pending_comma: Option<usize> = None;
+    while let Some(ch) = chars.next() {
+        if in_string {
+            out.push(ch);
+            if ch == '\\' {
+                if let Some(next) = chars.next() {
+                    out.push(next);
+                }
+            } else if ch == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match (ch, chars.peek()) {
+            ('/', Some('/')) => {
+                out.push(' ');
+                while let Some(&next) = chars.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+                    blank(&mut out, next);
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                out.push_str("  ");
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    blank(&mut out, next);
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            (c, _) if c.is_whitespace() => out.push(c),
+            (',', _) => {
+                pending_comma = Some(out.len());
+                out.push(',');
+            }
+            ('}' | ']', _) => {
+                if let Some(index) = pending_comma.take() {
+                    out.replace_range(index..index + 1, " ");
+                }
+                out.push(ch);
+            }
+            _ => {
+                pending_comma = None;
+                in_string = ch == '"';
+                out.push(ch);
+            }
+        }
     }
-    for root in roots {
-        if let Some(found) = find_jetbrains_in_root(&root, exe_name) {
-            return Some(found);
+    out
+}
+
+// 依次尝试以下方式，前一种成功即返回：
+// 1. code-server 预设直接在浏览器中打开实例地址；
+// 2. macOS 上按应用名（.app 包路径、精确名称、扫描到的包、模糊匹配）或 bundle ID 经 `open` 启动；
+// 3. 执行预设的命令行工具；
+// 4. 命令行工具未配置或找不到时，若编辑器支持则经 `vscode://`、`idea://` 等 URL 协议打开，
+//    此时只能传递路径与行号，其他启动选项不生效；
+// 5. 以上均不可用时返回“未能打开编辑器”。
+fn launch_editor(mut params: EditorOpenParams) -> Result<OpenOutcome, String> {
+    params.path = normalize_input_path(&params.path)?;
+    if let Some(file) = params.file.take() {
+        let goto = parse_goto_argument(&file);
+        params.line = params.line.or(goto.line);
+        params.column = params.column.or(goto.column);
+        params.file = Some(goto.path);
+    }
+    if let Some(locale) = params.locale.as_deref() {
+        validate_locale(locale)?;
+    }
+    if params.preset_kind == Some(PresetKind::CodeServer) {
+        let base_url = params
+            .command_path
+            .as_deref()
+            .ok_or("未配置 code-server 地址")?;
+        return open_url_in_browser_verbose(&code_server_url(base_url, &params.path)?);
+    }
+    let family = resolve_editor_family(&params);
+    if let Some(wrapper) = params.wrapper.as_deref() {
+        validate_wrapper(wrapper)?;
+    }
+    if params.temp_profile == Some(true) && has_explicit_profile(&params) {
+        return Err("临时配置文件不能与 --profile 参数同时使用".to_string());
+    }
+    if let Some(jdk_path) = params.jdk_path.take() {
+        if family == EditorFamily::JetBrains {
+            params.jdk_path = Some(resolve_jdk_home(&jdk_path)?);
+        } else {
+            log::info!("非 JetBrains 编辑器，忽略 JDK 设置: {}", jdk_path);
         }
     }
-    None
+    if family == EditorFamily::VsCode && params.prefer_workspace_file == Some(true) {
+        if let Some(workspace) = find_single_workspace_file(Path::new(&params.path)) {
+            log::info!("使用工作区文件 {}", workspace.display());
+            params.path = workspace.to_string_lossy().to_string();
+        }
+    }
+    let flags = editor_launch_flags(family, &params);
+    let env = editor_launch_env(family, &params);
+    if cfg!(target_os = "macos") {
+        if !env.is_empty() && (params.app_name.is_some() || params.bundle_id.is_some()) {
+            log::warn!("经 open 启动应用时无法传递环境变量，语言与 JDK 设置可能不生效");
+        }
+        if let Some(app_name) = params.app_name.as_deref() {
+            if let Some(bundle_path) = macos_bundle_path(app_name) {
+                // 指定了具体的 .app 包（例如固定某个 IDE 版本）时不做模糊匹配，以免换成其他版本。
+                if Path::new(bundle_path).is_dir() {
+                    if let Some(outcome) = open_with_macos_app("-a", bundle_path, &params, &flags)?
+                    {
+                        return Ok(outcome);
+                    }
+                } else {
+                    log::warn!("应用包不存在: {}", bundle_path);
+                }
+            } else {
+                let app_name = clean_macos_app_name(app_name);
+                let app_name = app_name.as_str();
+                if let Some(outcome) = open_with_macos_app("-a", app_name, &params, &flags)? {
+                    return Ok(outcome);
+                }
+                // 多个单词的名称偶尔让 LaunchServices 查找失败，改用扫描到的 .app 包路径重试。
+                #[cfg(target_os = "macos")]
+                if app_name.contains(' ') {
+                    if let Some(bundle) = resolve_macos_app_bundle(app_name) {
+                        log::info!("应用 {} 打开失败，改用 {}", app_name, bundle.display());
+                        let bundle = bundle.to_string_lossy();
+                        if let Some(outcome) = open_with_macos_app("-a", &bundle, &params, &flags)?
+                        {
+                            return Ok(outcome);
+                        }
+                    }
+                }
+                // 精确名称失败后再尝试模糊匹配已安装的应用，例如 "VS Code" → "Visual Studio Code"。
+                #[cfg(target_os = "macos")]
+                if let Some(resolved) = fuzzy_match_macos_app(app_name) {
+                    log::info!("应用 {} 打开失败，改用 {}", app_name, resolved);
+                    if let Some(outcome) = open_with_macos_app("-a", &resolved, &params, &flags)? {
+                        return Ok(outcome);
+                    }
+                }
+            }
+        }
+
+        if let Some(bundle_id) = params.bundle_id.as_deref() {
+            if let Some(outcome) = open_with_macos_app("-b", bundle_id, &params, &flags)? {
+                return Ok(outcome);
+            }
+        }
+    }
+
+    let url_fallback = editor_url_scheme_uri(family, &params);
+    if let Some(command_path) = params.command_path.clone() {
+        let command_missing =
+            params.wrapper.is_none() && resolve_command_path(&command_path).is_none();
+        let mut arguments = build_command_arguments(params.arguments, &params.path);
+        if params.new_window == Some(true) && is_macos_open_launcher(&command_path) {
+            arguments.insert(0, "-n".to_string());
+        }
+        if params.explicit_path_kind == Some(true) && !is_macos_open_launcher(&command_path) {
+            apply_path_kind_flag(&mut arguments, &params.path, editor_capabilities(family));
+        }
+        if let Some(file) = params.file.as_deref() {
+            if is_macos_open_launcher(&command_path) {
+                // `open` 只能把文件作为文档交给应用，无法传递行号。
+                arguments.push(file.to_string());
+            } else {
+                arguments.extend(build_editor_file_arguments(
+                    family,
+                    file,
+                    params.line,
+                    params.column,
+                ));
+            }
+        }
+        insert_editor_flags(&command_path, &mut arguments, &flags);
+        append_extra_arguments(
+            &command_path,
+            &mut arguments,
+            params.extra_arguments.as_deref(),
+            &params.path,
+        );
+        #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+        let (command_path, arguments) = if params.sandbox == Some(true) {
+            sandboxed_command(&command_path, arguments, &params.path, family)
+        } else {
+            (command_path, arguments)
+        };
+        let (command_path, arguments) = match params.wrapper.as_deref() {
+            Some(wrapper) => wrap_command(wrapper, &command_path, arguments),
+            None => (command_path, arguments),
+        };
+        let options = LaunchOptions {
+            use_login_shell: params.use_login_shell.unwrap_or(false),
+            strict_path_lookup: params.strict_path_lookup.unwrap_or(false),
+            preset_id: params.preset_id.clone(),
+            target_path: Some(params.path.clone()),
+            env,
+            priority: params.priority.unwrap_or_default(),
+            success_exit_codes: params.success_exit_codes.clone(),
+            ..LaunchOptions::default()
+        };
+        let result = run_command_with_shell_support(
+            &command_path,
+            &arguments,
+            &options,
+            "打开编辑器失败:",
+            "打开编辑器失败",
+        );
+        return match (result, url_fallback) {
+            (Err(err), Some(uri)) if command_missing => {
+                log::info!("命令 {} 不可用（{}），改用 {}", command_path, err, uri);
+                open_editor_url_scheme(&uri).map_err(|_| err)
+            }
+            (result, _) => result,
+        };
+    }
+
+    if let Some(uri) = url_fallback {
+        log::info!("未配置命令行工具，改用 {}", uri);
+        return open_editor_url_scheme(&uri);
+    }
+
+    Err("未能打开编辑器".to_string())
 }
 
-#[cfg(target_os = "windows")]
-fn find_jetbrains_in_root(root: &Path, exe_name: &str) -> Option<PathBuf> {
-    if !root.is_dir() {
+// 编辑器注册的 URL 协议链接，只包含路径与行号：
+// - VS Code：`vscode://file/<路径>[:行号]`，Insiders 与 VSCodium 使用各自的协议名；
+// - JetBrains：`idea://open?file=<路径>[&line=行号]`，协议名随产品而定（`pycharm://` 等），
+//   需要 IDE 已注册协议，通常由 Toolbox 或安装程序完成。
+// JetBrains 产品的命令名（`idea`、`pycharm` 等），依次按预设 ID 与命令名识别，默认为 `idea`。
+fn jetbrains_product(params: &EditorOpenParams) -> &'static str {
+    let find = |name: &str| {
+        JETBRAINS_COMMAND_NAMES
+            .iter()
+            .copied()
+            .find(|candidate| *candidate == name)
+    };
+    params
+        .preset_id
+        .as_deref()
+        .and_then(|id| match id {
+            "intellij-idea" => Some("idea"),
+            id => find(id),
+        })
+        .or_else(|| find(&command_stem(params.command_path.as_deref()?)))
+        .unwrap_or("idea")
+}
+
+fn editor_url_scheme_uri(family: EditorFamily, params: &EditorOpenParams) -> Option<String> {
+    if !editor_capabilities(family).use_url_scheme_fallback {
         return None;
     }
-    let entries = fs::read_dir(root).ok()?;
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.is_dir() {
-            let candidate = path.join("bin").join(exe_name);
-            if candidate.is_file() {
-                return Some(candidate);
+    let command_stem = params.command_path.as_deref().map(command_stem);
+    let target = params.file.as_deref().unwrap_or(&params.path);
+    let line = params.line.filter(|_| params.file.is_some());
+    let column = params.column.filter(|_| line.is_some());
+    match family {
+        EditorFamily::VsCode => {
+            let scheme = match (params.preset_id.as_deref(), command_stem.as_deref()) {
+                (Some("vscode-insiders"), _) | (_, Some("code-insiders" | "code - insiders")) => {
+                    "vscode-insiders"
+                }
+                (_, Some("codium")) => "vscodium",
+                _ => "vscode",
+            };
+            let mut uri = format!("{scheme}://file{}", file_uri_path(target));
+            if let Some(line) = line {
+                uri.push_str(&format!(":{line}"));
+            }
+            if let Some(column) = column {
+                uri.push_str(&format!(":{column}"));
+            }
+            Some(uri)
+        }
+        EditorFamily::JetBrains => {
+            let product = jetbrains_product(params);
+            let mut uri = format!(
+                "{product}://open?file={}",
+                percent_encode(target.as_bytes(), b"-._~/:")
+            );
+            if let Some(line) = line {
+                uri.push_str(&format!("&line={line}"));
             }
+            if let Some(column) = column {
+                uri.push_str(&format!("&column={column}"));
+            }
+            Some(uri)
         }
+        EditorFamily::Other => None,
     }
-    None
 }
 
-#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
-fn list_dev_tool_presets_linux() -> Vec<DevToolPreset> {
-    let mut presets = Vec::new();
-    if let Some(command) = find_in_path("code") {
-        presets.push(build_linux_preset("vscode", "Visual Studio Code", command));
+fn open_editor_url_scheme(uri: &str) -> Result<OpenOutcome, String> {
+    let mut outcome = open_with_default(uri)?;
+    outcome
+        .warnings
+        .push("未找到编辑器命令行工具，已通过 URL 协议打开，其他启动选项未生效".to_string());
+    Ok(outcome)
+}
+
+// 目录下恰好有一个 `*.code-workspace` 文件时返回它；没有或有多个时无法判断意图，返回 None。
+fn find_single_workspace_file(dir: &Path) -> Option<PathBuf> {
+    let mut workspaces = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("code-workspace"))
+        });
+    let workspace = workspaces.next()?;
+    workspaces.next().is_none().then_some(workspace)
+}
+
+// 通过 `open -a <应用名>` 或 `open -b <Bundle ID>` 打开，`open` 返回失败时为 None。
+fn open_with_macos_app(
+    selector: &str,
+    target: &str,
+    params: &EditorOpenParams,
+    flags: &[String],
+) -> Result<Option<OpenOutcome>, String> {
+    let mut arguments = vec![
+        selector.to_string(),
+        target.to_string(),
+        params.path.clone(),
+    ];
+    if params.new_window == Some(true) {
+        arguments.insert(0, "-n".to_string());
     }
-    if let Some(command) = find_in_path("code-insiders") {
-        presets.push(build_linux_preset(
-            "vscode-insiders",
-            "Visual Studio Code - Insiders",
-            command,
-        ));
+    arguments.extend(params.file.iter().cloned());
+    insert_editor_flags("/usr/bin/open", &mut arguments, flags);
+    append_extra_arguments(
+        "/usr/bin/open",
+        &mut arguments,
+        params.extra_arguments.as_deref(),
+        &params.path,
+    );
+    let mut command = Command::new("/usr/bin/open");
+    command.args(&arguments);
+    let status = command
+        .status()
+        .map_err(|err| format!("打开编辑器失败: {err}"))?;
+    let strategy = if selector == "-b" {
+        LaunchStrategy::MacOpenBundle
+    } else {
+        LaunchStrategy::MacOpenApp
+    };
+    Ok(status
+        .success()
+        .then(|| describe_command(strategy, &command)))
+}
+
+// 区分应用名与 .app 包的绝对路径：后者返回去掉末尾斜杠的路径，可直接交给 `open -a`。
+fn macos_bundle_path(app_name: &str) -> Option<&str> {
+    let app_name = app_name.trim();
+    let bundle_path = app_name.trim_end_matches('/');
+    (app_name.starts_with('/') && bundle_path.to_ascii_lowercase().ends_with(".app"))
+        .then_some(bundle_path)
+}
+
+/// 同时在编辑器与终端中打开项目：终端失败不会阻止编辑器打开；
+/// `require_editor` 为 true 时编辑器打开失败则不再打开终端，终端标记为跳过。
+pub fn open_workspace(
+    path: &str,
+    editor: Option<DevToolPreset>,
+    terminal: Option<TerminalOpenParams>,
+    require_editor: bool,
+) -> WorkspaceOpenResult {
+    let editor = match editor {
+        Some(preset) => {
+            LaunchStepStatus::from(open_in_editor(EditorOpenParams::from_preset(preset, path)))
+        }
+        None => LaunchStepStatus::NotRequested,
+    };
+    let editor_failed = matches!(editor, LaunchStepStatus::Failed(_));
+    let terminal = match terminal {
+        Some(_) if require_editor && editor_failed => LaunchStepStatus::Skipped,
+        Some(params) => LaunchStepStatus::from(open_in_terminal(TerminalOpenParams {
+            path: path.to_string(),
+            ..params
+        })),
+        None => LaunchStepStatus::NotRequested,
+    };
+    WorkspaceOpenResult { editor, terminal }
+}
+
+/// 把初始化文件（如 `.vscode/settings.json`）写入项目后再用预设打开项目。
+/// `bootstrap_files` 为（相对路径, 内容），已存在的文件不会被覆盖；
+/// 任一路径越出项目根目录时不写入任何文件。
+pub fn open_with_bootstrap(
+    path: &str,
+    preset: DevToolPreset,
+    bootstrap_files: &[(String, String)],
+) -> Result<BootstrapResult, String> {
+    let path = normalize_input_path(path)?;
+    let result = write_bootstrap_files(Path::new(&path), bootstrap_files)?;
+    open_in_editor(EditorOpenParams::from_preset(preset, &path))?;
+    Ok(result)
+}
+
+fn write_bootstrap_files(
+    root: &Path,
+    files: &[(String, String)],
+) -> Result<BootstrapResult, String> {
+    for (relative, _) in files {
+        let relative_path = Path::new(relative);
+        if relative.trim().is_empty()
+            || !relative_path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(format!("初始化文件路径无效: {relative}"));
+        }
     }
+    let root_canon = fs::canonicalize(root).map_err(|err| format!("读取项目路径失败: {err}"))?;
+    // 先检查全部路径再写入：目录可能是指向项目外的符号链接，取最近的已存在上级
+    // （符号链接本身也算存在）解析后判断，避免经符号链接在项目外创建目录。
+    for (relative, _) in files {
+        let target = root.join(relative);
+        let existing = target
+            .ancestors()
+            .skip(1)
+            .find(|ancestor| fs::symlink_metadata(ancestor).is_ok())
+            .unwrap_or(root);
+        let existing_canon =
+            fs::canonicalize(existing).map_err(|err| format!("读取目录失败 {relative}: {err}"))?;
+        if !existing_canon.starts_with(&root_canon) {
+            return Err(format!("初始化文件路径越界: {relative}"));
+        }
+    }
+    let mut result = BootstrapResult::default();
+    for (relative, content) in files {
+        match write_bootstrap_file(root, &root_canon, relative, content) {
+            Ok(true) => result.written.push(relative.clone()),
+            Ok(false) => result.skipped.push(relative.clone()),
+            Err(err) => {
+                // 中途失败时撤销本次已写入的文件，保持“要么全部写入、要么不写入”。
+                for written in &result.written {
+                    let _ = fs::remove_file(root.join(written));
+                }
+                return Err(err);
+            }
+        }
+    }
+    Ok(result)
+}
 
-    add_linux_preset(&mut presets, "intellij-idea", "IntelliJ IDEA", "idea");
-    add_linux_preset(&mut presets, "webstorm", "WebStorm", "webstorm");
-    add_linux_preset(&mut presets, "pycharm", "PyCharm", "pycharm");
-    add_linux_preset(&mut presets, "goland", "GoLand", "goland");
-    add_linux_preset(&mut presets, "rider", "Rider", "rider");
-    add_linux_preset(&mut presets, "clion", "CLion", "clion");
-    add_linux_preset(&mut presets, "phpstorm", "PhpStorm", "phpstorm");
-    add_linux_preset(&mut presets, "datagrip", "DataGrip", "datagrip");
+// 写入单个初始化文件，文件已存在时返回 false。
+fn write_bootstrap_file(
+    root: &Path,
+    root_canon: &Path,
+    relative: &str,
+    content: &str,
+) -> Result<bool, String> {
+    let target = root.join(relative);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|err| format!("创建目录失败 {relative}: {err}"))?;
+        // 检查之后目录仍可能被替换为符号链接，写入前再确认一次。
+        let parent_canon =
+            fs::canonicalize(parent).map_err(|err| format!("读取目录失败 {relative}: {err}"))?;
+        if !parent_canon.starts_with(root_canon) {
+            return Err(format!("初始化文件路径越界: {relative}"));
+        }
+    }
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&target);
+    match file {
+        Ok(mut file) => {
+            std::io::Write::write_all(&mut file, content.as_bytes())
+                .map_err(|err| format!("写入初始化文件失败 {relative}: {err}"))?;
+            Ok(true)
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+        Err(err) => Err(format!("写入初始化文件失败 {relative}: {err}")),
+    }
+}
 
-    presets
+/// 批量打开多个路径，返回值与 `paths` 一一对应。
+/// `combine` 为真且编辑器支持多根工作区（VS Code）时合并为一次调用，
+/// 否则逐个启动独立进程，并在每次启动之间稍作间隔。
+/// `priority` 作用于每个编辑器进程，批量打开多个大型 IDE 时可设为 [`Priority::Low`]，
+/// 避免启动期间系统失去响应。
+pub fn open_many_in_editor(
+    paths: Vec<String>,
+    preset: DevToolPreset,
+    combine: bool,
+    priority: Priority,
+) -> Vec<Result<(), String>> {
+    let capabilities = editor_capabilities(preset_editor_family(&preset));
+    let valid_paths: Vec<String> = paths
+        .iter()
+        .filter_map(|path| normalize_input_path(path).ok())
+        .collect();
+    if combine && !valid_paths.is_empty() && capabilities.supports_multi_root {
+        let arguments = build_multi_path_arguments(&preset.arguments, &valid_paths);
+        let options = LaunchOptions {
+            preset_id: Some(preset.id.clone()),
+            target_path: Some(valid_paths.join("\n")),
+            priority,
+            success_exit_codes: preset.success_exit_codes.clone(),
+            ..LaunchOptions::default()
+        };
+        let result = run_command_with_shell_support(
+            &preset.command_path,
+            &arguments,
+            &options,
+            "打开编辑器失败:",
+            "打开编辑器失败",
+        )
+        .map(|_| ());
+        return paths
+            .iter()
+            .map(|path| normalize_input_path(path).and(result.clone()))
+            .collect();
+    }
+
+    paths
+        .iter()
+        .enumerate()
+        .map(|(index, path)| {
+            let path = normalize_input_path(path)?;
+            if index > 0 {
+                std::thread::sleep(OPEN_MANY_STAGGER);
+            }
+            let arguments = build_command_arguments(Some(preset.arguments.clone()), &path);
+            let options = LaunchOptions {
+                preset_id: Some(preset.id.clone()),
+                target_path: Some(path),
+                priority,
+                success_exit_codes: preset.success_exit_codes.clone(),
+                ..LaunchOptions::default()
+            };
+            spawn_detached_with_shell_support(
+                &preset.command_path,
+                &arguments,
+                &options,
+                "打开编辑器失败:",
+            )
+            .map(|_| ())
+        })
+        .collect()
 }
 
-#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
-fn add_linux_preset(presets: &mut Vec<DevToolPreset>, id: &str, name: &str, command: &str) {
-    if let Some(command_path) = find_in_path(command) {
-        presets.push(build_linux_preset(id, name, command_path));
+/// 以多根工作区打开多个目录：VS Code 在临时目录中生成列出全部目录的 `.code-workspace`
+/// 文件并打开它，得到真正的多根工作区而非多个独立窗口；其他编辑器把所有目录作为参数传入。
+/// 路径必须都是已存在的目录，重复的路径只保留第一次出现的位置。
+pub fn open_multiroot(paths: Vec<String>, preset: DevToolPreset) -> Result<OpenOutcome, String> {
+    let folders = multiroot_folders(&paths)?;
+    if preset_editor_family(&preset) != EditorFamily::VsCode {
+        let arguments = build_multi_path_arguments(&preset.arguments, &folders);
+        let options = LaunchOptions {
+            preset_id: Some(preset.id.clone()),
+            target_path: Some(folders.join("\n")),
+            success_exit_codes: preset.success_exit_codes.clone(),
+            ..LaunchOptions::default()
+        };
+        return run_command_with_shell_support(
+            &preset.command_path,
+            &arguments,
+            &options,
+            "打开编辑器失败:",
+            "打开编辑器失败",
+        );
     }
+
+    let dir = std::env::temp_dir().join("devhaven-workspaces");
+    fs::create_dir_all(&dir).map_err(|err| format!("创建工作区目录失败: {err}"))?;
+    remove_expired_workspace_files(&dir, MULTIROOT_WORKSPACE_TTL);
+    let workspace = dir.join(format!("{}.code-workspace", uuid::Uuid::new_v4()));
+    fs::write(&workspace, multiroot_workspace_content(&folders))
+        .map_err(|err| format!("写入工作区文件失败: {err}"))?;
+    open_in_editor_verbose(EditorOpenParams::from_preset(
+        preset,
+        &workspace.to_string_lossy(),
+    ))
 }
 
-#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
-fn build_linux_preset(id: &str, name: &str, command_path: String) -> DevToolPreset {
-    DevToolPreset {
-        id: id.to_string(),
-        name: name.to_string(),
-        command_path,
-        arguments: vec!["{path}".to_string()],
+fn multiroot_folders(paths: &[String]) -> Result<Vec<String>, String> {
+    let mut folders: Vec<String> = Vec::new();
+    for path in paths {
+        let path = absolute_path_string(&normalize_input_path(path)?);
+        if !Path::new(&path).is_dir() {
+            return Err(format!("目录不存在: {path}"));
+        }
+        if !folders.contains(&path) {
+            folders.push(path);
+        }
     }
+    if folders.is_empty() {
+        return Err("未指定要打开的目录".to_string());
+    }
+    Ok(folders)
 }
 
-fn find_in_path(command: &str) -> Option<String> {
-    let path_var = std::env::var_os("PATH")?;
-    let has_extension = Path::new(command).extension().is_some();
-    for dir in std::env::split_paths(&path_var) {
-        let candidate = dir.join(command);
-        if candidate.is_file() {
-            return Some(candidate.to_string_lossy().to_string());
+fn multiroot_workspace_content(folders: &[String]) -> String {
+    let folders: Vec<serde_json::Value> = folders
+        .iter()
+        .map(|folder| serde_json::json!({ "path": folder }))
+        .collect();
+    let workspace = serde_json::json!({ "folders": folders, "settings": {} });
+    serde_json::to_string_pretty(&workspace).unwrap_or_default()
+}
+
+fn remove_expired_workspace_files(dir: &Path, ttl: Duration) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let expired = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > ttl);
+        let is_workspace = path
+            .extension()
+            .is_some_and(|extension| extension == "code-workspace");
+        if expired && is_workspace {
+            let _ = fs::remove_file(&path);
         }
-        #[cfg(target_os = "windows")]
-        {
-            if !has_extension {
-                for ext in ["exe", "cmd", "bat"] {
-                    let with_ext = dir.join(format!("{command}.{ext}"));
-                    if with_ext.is_file() {
-                        return Some(with_ext.to_string_lossy().to_string());
-                    }
-                }
+    }
+}
+
+/// 试启动预设所用的进程启动方式，测试中可替换为不启动真实进程的实现。
+pub trait CommandRunner {
+    /// 以给定参数启动命令，返回可轮询退出状态的进程。
+    fn spawn(
+        &self,
+        command_path: &str,
+        arguments: &[String],
+    ) -> std::io::Result<Box<dyn RunningProcess>>;
+}
+
+/// 由 [`CommandRunner`] 启动的进程。
+pub trait RunningProcess: Send {
+    /// 不阻塞地检查进程是否已退出。
+    fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>>;
+    /// 阻塞直到进程退出。
+    fn wait(&mut self) -> std::io::Result<ExitStatus>;
+}
+
+impl RunningProcess for Child {
+    fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>> {
+        Child::try_wait(self)
+    }
+
+    fn wait(&mut self) -> std::io::Result<ExitStatus> {
+        Child::wait(self)
+    }
+}
+
+// 通过系统进程启动命令，沿用 Shell 脚本与 PATH 解析的处理。
+struct SystemCommandRunner;
+
+impl CommandRunner for SystemCommandRunner {
+    fn spawn(
+        &self,
+        command_path: &str,
+        arguments: &[String],
+    ) -> std::io::Result<Box<dyn RunningProcess>> {
+        let (child, _) = launch_with_shell_support(
+            command_path,
+            arguments,
+            &LaunchOptions::default(),
+            Command::spawn,
+        )?;
+        Ok(Box::new(child))
+    }
+}
+
+/// 在临时目录上试启动预设，报告进程能否启动以及观察期内是否保持运行。
+/// 编辑器 CLI（如 `code`）把目录交给已运行的实例后会立即以 0 退出，同样视为启动成功。
+/// 进程退出后删除临时目录；观察期结束仍在运行的进程由后台线程等待其退出后再删除。
+pub fn test_launch_preset(preset: &DevToolPreset) -> PresetTestResult {
+    test_launch_preset_with(preset, &SystemCommandRunner)
+}
+
+fn test_launch_preset_with(preset: &DevToolPreset, runner: &dyn CommandRunner) -> PresetTestResult {
+    let dir = std::env::temp_dir()
+        .join("devhaven-preset-tests")
+        .join(uuid::Uuid::new_v4().to_string());
+    if let Err(err) = fs::create_dir_all(&dir) {
+        return PresetTestResult {
+            error: Some(format!("创建临时目录失败: {err}")),
+            ..PresetTestResult::default()
+        };
+    }
+    let arguments = build_command_arguments(Some(preset.arguments.clone()), &dir.to_string_lossy());
+    match runner.spawn(&preset.command_path, &arguments) {
+        Ok(process) => observe_test_launch(process, dir),
+        Err(err) => {
+            let _ = fs::remove_dir_all(&dir);
+            PresetTestResult {
+                error: Some(format!("无法启动 {}: {err}", preset.command_path)),
+                ..PresetTestResult::default()
             }
         }
     }
-    None
 }
 
-#[cfg(target_os = "windows")]
-fn open_with_default(path: &str) -> Result<(), String> {
-    let status = Command::new("explorer")
-        .arg(path)
-        .status()
-        .map_err(|err| format!("无法打开路径: {err}"))?;
-    if status.success() {
-        Ok(())
+// 观察期内仍未退出的进程保持运行，由后台线程回收并在其退出后删除临时目录。
+fn observe_test_launch(mut process: Box<dyn RunningProcess>, dir: PathBuf) -> PresetTestResult {
+    let exited = wait_for_exit(
+        || process.try_wait(),
+        PRESET_TEST_TIMEOUT,
+        PRESET_TEST_POLL_INTERVAL,
+    );
+    if matches!(exited, Ok(None)) {
+        std::thread::spawn(move || {
+            let _ = process.wait();
+            let _ = fs::remove_dir_all(&dir);
+        });
     } else {
-        Err("打开路径失败".to_string())
+        let _ = fs::remove_dir_all(&dir);
     }
+    preset_test_result(exited)
 }
 
-#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
-fn open_with_default(path: &str) -> Result<(), String> {
-    let status = Command::new("xdg-open")
-        .arg(path)
-        .status()
-        .map_err(|err| format!("无法打开路径: {err}"))?;
-    if status.success() {
-        Ok(())
-    } else {
-        Err("打开路径失败".to_string())
+// 把观察结果转换为试启动结论：观察期内以非零状态退出视为失败。
+fn preset_test_result(exited: std::io::Result<Option<ExitStatus>>) -> PresetTestResult {
+    match exited {
+        Ok(Some(status)) => PresetTestResult {
+            started: true,
+            stayed_open: false,
+            exit_code: status.code(),
+            error: (!status.success()).then(|| format!("进程立即退出: {status}")),
+        },
+        Ok(None) => PresetTestResult {
+            started: true,
+            stayed_open: true,
+            ..PresetTestResult::default()
+        },
+        Err(err) => PresetTestResult {
+            started: true,
+            error: Some(format!("无法获取进程状态: {err}")),
+            ..PresetTestResult::default()
+        },
     }
 }
 
-#[cfg(target_os = "windows")]
-fn open_windows_terminal(path: &str) -> Result<(), String> {
-    let wt_status = Command::new("wt.exe").arg("-d").arg(path).status();
-    if let Ok(status) = wt_status {
-        if status.success() {
-            return Ok(());
+// 按间隔轮询直到返回退出状态或超时，超时返回 `None`。
+fn wait_for_exit<T>(
+    mut poll: impl FnMut() -> Result<Option<T>, std::io::Error>,
+    timeout: Duration,
+    interval: Duration,
+) -> Result<Option<T>, std::io::Error> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = poll()? {
+            return Ok(Some(status));
         }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        std::thread::sleep(interval);
     }
+}
 
-    let escaped_path = path.replace('"', "\"\"");
-    let command = format!("Set-Location -LiteralPath \"{}\"", escaped_path);
-    let status = Command::new("powershell.exe")
-        .args(["-NoExit", "-Command", command.as_str()])
-        .status()
-        .map_err(|err| format!("无法打开终端: {err}"))?;
-    if status.success() {
-        Ok(())
+// 去除首尾空白并拒绝空路径，避免把空字符串交给系统（例如被解释为主目录）。
+fn normalize_input_path(path: &str) -> Result<String, String> {
+    let path = expand_path(path.trim());
+    if path.trim().is_empty() {
+        Err("路径不能为空".to_string())
     } else {
-        Err("终端打开失败".to_string())
+        Ok(path)
+    }
+}
+
+/// 展开路径中的 `~` 与环境变量：Unix 支持 `~`、`~user`、`$VAR` 与 `${VAR}`，
+/// Windows 支持 `%VAR%`。未定义的变量与不存在的用户保持原样而不报错，
+/// 这样后续打开失败时的错误信息里仍能看到原始写法。
+pub fn expand_path(path: &str) -> String {
+    #[cfg(target_os = "windows")]
+    let expanded = expand_windows_variables(path, |name| std::env::var(name).ok());
+    #[cfg(not(target_os = "windows"))]
+    let expanded = expand_unix_path(path, |name| std::env::var(name).ok(), user_home_dir);
+    expanded
+}
+
+#[cfg(any(not(target_os = "windows"), test))]
+fn expand_unix_path(
+    path: &str,
+    lookup_var: impl Fn(&str) -> Option<String>,
+    lookup_user_home: impl Fn(&str) -> Option<String>,
+) -> String {
+    if let Some(rest) = path.strip_prefix('~') {
+        let (user, tail) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let home = if user.is_empty() {
+            lookup_var("HOME")
+        } else {
+            lookup_user_home(user)
+        };
+        if let Some(home) = home.filter(|home| !home.is_empty()) {
+            return format!("{home}{}", expand_unix_variables(tail, &lookup_var));
+        }
+    }
+    expand_unix_variables(path, &lookup_var)
+}
+
+#[cfg(any(not(target_os = "windows"), test))]
+fn expand_unix_variables(value: &str, lookup_var: &impl Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(index) = rest.find('$') {
+        expanded.push_str(&rest[..index]);
+        let after = &rest[index + 1..];
+        let (name, consumed) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = after
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+        let valid = name
+            .chars()
+            .next()
+            .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_');
+        match lookup_var(name).filter(|_| valid) {
+            Some(value) => {
+                expanded.push_str(&value);
+                rest = &after[consumed..];
+            }
+            None => {
+                expanded.push('$');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(any(target_os = "windows", test))]
+fn expand_windows_variables(value: &str, lookup_var: impl Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('%') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let resolved = after
+            .find('%')
+            .filter(|end| *end > 0)
+            .and_then(|end| lookup_var(&after[..end]).map(|value| (value, end)));
+        match resolved {
+            Some((value, end)) => {
+                expanded.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                expanded.push('%');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+// 通过 getpwnam_r 查询其他用户的主目录。
+#[cfg(not(target_os = "windows"))]
+fn user_home_dir(user: &str) -> Option<String> {
+    use std::ffi::{CStr, CString};
+
+    let name = CString::new(user).ok()?;
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    let code = unsafe {
+        libc::getpwnam_r(
+            name.as_ptr(),
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+    if code != 0 || result.is_null() || passwd.pw_dir.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(passwd.pw_dir) }
+        .to_str()
+        .ok()
+        .map(str::to_string)
+}
+
+/// 根据文件扩展名推荐最合适的预设 ID；`path` 为目录时按 `detect_project_language`
+/// 推断的主要语言推荐。
+pub fn suggest_preset_for_path(path: &str, presets: &[DevToolPreset]) -> Option<String> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    let specialized = extension
+        .as_deref()
+        .and_then(preferred_presets_for_extension)
+        .or_else(|| {
+            let language = detect_project_language(path)?;
+            let (_, extensions, _) = LANGUAGE_SIGNALS
+                .iter()
+                .find(|(candidate, _, _)| *candidate == language)?;
+            extensions
+                .iter()
+                .find_map(|extension| preferred_presets_for_extension(extension))
+        })
+        .unwrap_or_default();
+    specialized
+        .iter()
+        .chain(GENERAL_EDITOR_PRESETS)
+        .find(|id| presets.iter().any(|preset| preset.id == **id))
+        .map(|id| id.to_string())
+}
+
+fn preferred_presets_for_extension(extension: &str) -> Option<&'static [&'static str]> {
+    PRESET_SUGGESTIONS
+        .iter()
+        .find(|(extensions, _)| extensions.contains(&extension))
+        .map(|(_, preset_ids)| *preset_ids)
+}
+
+// 语言识别信号：源文件扩展名与项目根目录中的标记文件，得分相同时靠前者优先。
+const LANGUAGE_SIGNALS: &[(Language, &[&str], &[&str])] = &[
+    (Language::Rust, &["rs"], &["Cargo.toml"]),
+    (Language::Go, &["go"], &["go.mod"]),
+    (
+        Language::Python,
+        &["py", "pyi", "ipynb"],
+        &["pyproject.toml", "requirements.txt", "setup.py", "Pipfile"],
+    ),
+    (
+        Language::Kotlin,
+        &["kt", "kts"],
+        &["build.gradle.kts", "settings.gradle.kts"],
+    ),
+    (
+        Language::Java,
+        &["java"],
+        &["pom.xml", "build.gradle", "settings.gradle"],
+    ),
+    (Language::TypeScript, &["ts", "tsx"], &["tsconfig.json"]),
+    (
+        Language::JavaScript,
+        &["js", "jsx", "mjs", "cjs", "vue"],
+        &["package.json"],
+    ),
+    (Language::Php, &["php"], &["composer.json"]),
+    (Language::CSharp, &["cs", "csproj", "sln"], &[]),
+    (
+        Language::Cpp,
+        &["cpp", "cc", "cxx", "hpp", "hh"],
+        &["CMakeLists.txt"],
+    ),
+    (Language::C, &["c", "h"], &[]),
+    (Language::Ruby, &["rb"], &["Gemfile"]),
+    (Language::Swift, &["swift"], &["Package.swift"]),
+];
+// 一个标记文件折算的源文件数量，使标记文件通常压过零散的脚本文件。
+const LANGUAGE_MARKER_WEIGHT: usize = 20;
+const LANGUAGE_SCAN_MAX_DEPTH: usize = 4;
+const LANGUAGE_SCAN_MAX_FILES: usize = 2000;
+const LANGUAGE_SCAN_IGNORED: &[&str] = &[
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    "out",
+    "vendor",
+    "venv",
+    "__pycache__",
+];
+
+/// 采样目录推断项目的主要语言：根目录中的每个标记文件（Cargo.toml、go.mod、
+/// package.json、pom.xml 等）计 20 分，每个源文件计 1 分，取得分最高的语言。
+/// 按广度优先最多扫描 4 层、2000 个文件，跳过隐藏目录、依赖与构建产物目录；
+/// `path` 不是目录或没有任何信号时返回 None。
+pub fn detect_project_language(path: &str) -> Option<Language> {
+    let path = normalize_input_path(path).ok()?;
+    let root = Path::new(&path);
+    if !root.is_dir() {
+        return None;
+    }
+    let mut scores: Vec<usize> = LANGUAGE_SIGNALS
+        .iter()
+        .map(|(_, _, markers)| {
+            markers
+                .iter()
+                .filter(|marker| root.join(marker).is_file())
+                .count()
+                * LANGUAGE_MARKER_WEIGHT
+        })
+        .collect();
+    for extension in sample_file_extensions(root) {
+        if let Some(index) = LANGUAGE_SIGNALS
+            .iter()
+            .position(|(_, extensions, _)| extensions.contains(&extension.as_str()))
+        {
+            scores[index] += 1;
+        }
+    }
+    scores
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_, score)| **score)
+        .filter(|(_, score)| **score > 0)
+        .map(|(index, _)| LANGUAGE_SIGNALS[index].0)
+}
+
+// 广度优先收集目录下文件的小写扩展名，先采样浅层文件，受深度与文件数上限约束。
+fn sample_file_extensions(root: &Path) -> Vec<String> {
+    let mut extensions = Vec::new();
+    let mut visited = 0;
+    let mut pending = std::collections::VecDeque::from([(root.to_path_buf(), 0)]);
+    while let Some((dir, level)) = pending.pop_front() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_symlink() {
+                continue;
+            }
+            let entry_path = entry.path();
+            if file_type.is_dir() {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                if level + 1 < LANGUAGE_SCAN_MAX_DEPTH
+                    && !name.starts_with('.')
+                    && !LANGUAGE_SCAN_IGNORED.contains(&name.as_ref())
+                {
+                    pending.push_back((entry_path, level + 1));
+                }
+                continue;
+            }
+            visited += 1;
+            if visited > LANGUAGE_SCAN_MAX_FILES {
+                return extensions;
+            }
+            if let Some(extension) = entry_path.extension().and_then(|ext| ext.to_str()) {
+                extensions.push(extension.to_ascii_lowercase());
+            }
+        }
+    }
+    extensions
+}
+
+type SuggestCommand = fn(&Path) -> Option<String>;
+
+// 项目标记文件到终端建议命令的映射，按优先级排列；返回 None 时继续检查后续项。
+const TERMINAL_COMMAND_SUGGESTIONS: &[(&[&str], SuggestCommand)] = &[
+    (&["package.json"], suggest_node_command),
+    (&["Cargo.toml"], |_| Some("cargo run".to_string())),
+    (&["go.mod"], |_| Some("go run .".to_string())),
+    (
+        &["pyproject.toml", "requirements.txt"],
+        suggest_python_command,
+    ),
+    (&["Makefile", "makefile", "GNUmakefile"], |_| {
+        Some("make".to_string())
+    }),
+];
+
+/// 根据项目类型推荐在终端中首先执行的命令，作为 `initial_command` 供用户一键运行。
+/// `path` 为文件时检查其所在目录。
+pub fn suggest_terminal_command(path: &str) -> Option<String> {
+    let dir = terminal_working_dir(path, None);
+    TERMINAL_COMMAND_SUGGESTIONS
+        .iter()
+        .filter(|(markers, _)| markers.iter().any(|marker| dir.join(marker).is_file()))
+        .find_map(|(_, suggest)| suggest(&dir))
+}
+
+// 依赖未安装时建议安装，否则运行 dev 或 start 脚本；包管理器按锁文件判断。
+fn suggest_node_command(dir: &Path) -> Option<String> {
+    let manager = [
+        ("pnpm-lock.yaml", "pnpm"),
+        ("yarn.lock", "yarn"),
+        ("bun.lockb", "bun"),
+    ]
+    .iter()
+    .find(|(lock_file, _)| dir.join(lock_file).is_file())
+    .map_or("npm", |(_, manager)| manager);
+    if !dir.join("node_modules").is_dir() {
+        return Some(format!("{manager} install"));
+    }
+    let package: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("package.json")).ok()?).ok()?;
+    let scripts = package.get("scripts")?;
+    ["dev", "start"]
+        .iter()
+        .find(|script| scripts.get(script).is_some())
+        .map(|script| format!("{manager} run {script}"))
+}
+
+// 已有虚拟环境时激活它，否则按锁文件安装依赖或新建虚拟环境。
+fn suggest_python_command(dir: &Path) -> Option<String> {
+    if let Some(venv) = PYTHON_VENV_DIRS.iter().find(|venv| dir.join(venv).is_dir()) {
+        return Some(if cfg!(target_os = "windows") {
+            format!(r".\{venv}\Scripts\Activate.ps1")
+        } else {
+            format!("source {venv}/bin/activate")
+        });
+    }
+    if dir.join("poetry.lock").is_file() {
+        return Some("poetry install".to_string());
+    }
+    if dir.join("uv.lock").is_file() {
+        return Some("uv sync".to_string());
+    }
+    Some("python3 -m venv .venv".to_string())
+}
+
+// 预设对应的编辑器进程名（小写），用于在进程命令行中识别编辑器实例。
+const EDITOR_PROCESS_NAMES: &[(&str, &[&str])] = &[
+    ("vscode", &["code", "visual studio code"]),
+    (
+        "vscode-insiders",
+        &[
+            "code-insiders",
+            "code - insiders",
+            "visual studio code - insiders",
+        ],
+    ),
+    (
+        "intellij-idea",
+        &["idea", "intellij idea", "intellij idea ce"],
+    ),
+    ("pycharm", &["pycharm", "pycharm ce"]),
+    ("webstorm", &["webstorm"]),
+    ("goland", &["goland"]),
+    ("rider", &["rider"]),
+    ("clion", &["clion"]),
+    ("phpstorm", &["phpstorm"]),
+    ("datagrip", &["datagrip"]),
+    ("lapce", &["lapce"]),
+    ("pulsar", &["pulsar"]),
+    ("brackets", &["brackets"]),
+];
+
+/// 根据运行中的进程推测项目是否已在指定编辑器中打开。
+/// 只读取名称匹配编辑器的进程，再检查命令行中同时包含编辑器可执行文件与项目路径的进程，
+/// 属于启发式判断；任何错误都视为未打开。
+pub fn is_project_open(path: &str, preset_id: &str) -> bool {
+    let Ok(path) = normalize_input_path(path) else {
+        return false;
+    };
+    let Some(process_names) = editor_process_names(preset_id) else {
+        return false;
+    };
+    match list_process_command_lines(process_names) {
+        Ok(command_lines) => command_lines.iter().any(|command_line| {
+            command_line_runs_editor(command_line, process_names)
+                && command_line_mentions_path(command_line, &path)
+        }),
+        Err(err) => {
+            log::warn!("读取进程列表失败: {}", err);
+            false
+        }
+    }
+}
+
+fn editor_process_names(preset_id: &str) -> Option<&'static [&'static str]> {
+    EDITOR_PROCESS_NAMES
+        .iter()
+        .find(|(id, _)| *id == preset_id)
+        .map(|(_, names)| *names)
+}
+
+// 先用 pgrep 按命令行筛出可能的编辑器进程，再只读取这些进程的命令行，避免列出所有进程。
+#[cfg(not(target_os = "windows"))]
+fn list_process_command_lines(process_names: &[&str]) -> Result<Vec<String>, String> {
+    let output = Command::new("pgrep")
+        .args(["-f", "-i", &process_name_pattern(process_names)])
+        .output()
+        .map_err(|err| format!("无法执行 pgrep: {err}"))?;
+    // pgrep 没有匹配的进程时以 1 退出。
+    if output.status.code() == Some(1) {
+        return Ok(Vec::new());
+    }
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let pids = non_empty_lines(&String::from_utf8_lossy(&output.stdout)).join(",");
+    let output = Command::new("ps")
+        .args(["-o", "command=", "-p", &pids])
+        .output()
+        .map_err(|err| format!("无法执行 ps: {err}"))?;
+    // 进程可能在两次调用之间退出，此时 ps 以非零状态退出但仍输出其余进程。
+    Ok(non_empty_lines(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[cfg(target_os = "windows")]
+fn list_process_command_lines(process_names: &[&str]) -> Result<Vec<String>, String> {
+    let script = format!(
+        "[Console]::OutputEncoding = [System.Text.Encoding]::UTF8; \
+        Get-CimInstance Win32_Process -Filter \"{}\" | ForEach-Object {{ $_.CommandLine }}",
+        process_name_wql_filter(process_names)
+    );
+    let output = powershell_command(&script, false)
+        .output()
+        .map_err(|err| format!("无法读取进程列表: {err}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(non_empty_lines(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// pgrep 的扩展正则：匹配任一进程名，名称中的正则元字符按字面处理。
+#[cfg(any(not(target_os = "windows"), test))]
+fn process_name_pattern(process_names: &[&str]) -> String {
+    process_names
+        .iter()
+        .map(|name| regex_escape(name))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+#[cfg(any(not(target_os = "windows"), test))]
+fn regex_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if "\\.^$|?*+()[]{}".contains(ch) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+// WMI 查询条件：可执行文件名以任一进程名开头（如 `Code.exe`、`idea64.exe`），WQL 比较不区分大小写。
+#[cfg(any(target_os = "windows", test))]
+fn process_name_wql_filter(process_names: &[&str]) -> String {
+    process_names
+        .iter()
+        .map(|name| {
+            let name = name
+                .replace('\'', "''")
+                .replace('[', "[[]")
+                .replace('_', "[_]")
+                .replace('%', "[%]");
+            format!("Name LIKE '{name}%'")
+        })
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+// 进程名需作为完整的路径片段出现：前面是路径分隔符、引号或行首，
+// 后面（忽略 `64` 后缀）是扩展名、分隔符、引号、空白或行尾，避免 `code` 命中 `vscode-server` 之类的名称。
+fn command_line_runs_editor(command_line: &str, process_names: &[&str]) -> bool {
+    let command_line = command_line.to_lowercase();
+    process_names.iter().any(|name| {
+        command_line.match_indices(name).any(|(index, _)| {
+            let before = command_line[..index].chars().next_back();
+            let after = command_line[index + name.len()..].trim_start_matches("64");
+            matches!(before, None | Some('/' | '\\' | '"'))
+                && (after.starts_with('.') || is_command_line_boundary(after))
+        })
+    })
+}
+
+// 路径后必须紧跟分隔符、引号、空白或行尾，避免 `/p/app` 命中 `/p/app2`。
+fn command_line_mentions_path(command_line: &str, path: &str) -> bool {
+    let path = path.trim_end_matches(['/', '\\']);
+    if path.is_empty() {
+        return false;
+    }
+    command_line
+        .match_indices(path)
+        .any(|(index, _)| is_command_line_boundary(&command_line[index + path.len()..]))
+}
+
+fn is_command_line_boundary(rest: &str) -> bool {
+    match rest.chars().next() {
+        None => true,
+        Some(ch) => ch.is_whitespace() || matches!(ch, '/' | '\\' | '"' | '\''),
+    }
+}
+
+// Linux 桌面环境的默认应用设置入口，按顺序尝试。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+const LINUX_DEFAULT_APPS_SETTINGS: &[(&str, &[&str])] = &[
+    ("gnome-control-center", &["default-apps"]),
+    ("systemsettings", &["kcm_componentchooser"]),
+    ("systemsettings5", &["kcm_componentchooser"]),
+    ("xfce4-mime-settings", &[]),
+];
+
+/// 打开系统的默认应用（文件关联）设置，便于用户修正错误的默认打开方式。
+pub fn open_default_apps_settings() -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    // 系统设置没有单独的默认应用面板，默认浏览器等选项位于“桌面与程序坞”。
+    let result =
+        open_with_default("x-apple.systempreferences:com.apple.Desktop-Settings.extension")
+            .or_else(|_| {
+                open_with_default("x-apple.systempreferences:com.apple.preference.general")
+            })
+            .map(|_| ());
+    #[cfg(target_os = "windows")]
+    let result = run_explorer("ms-settings:defaultapps").map(|_| ());
+    #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+    let result = open_linux_default_apps_settings();
+    result
+}
+
+// 依次尝试各桌面环境的设置程序，第一个能启动的即可。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn open_linux_default_apps_settings() -> Result<(), String> {
+    for (program, arguments) in LINUX_DEFAULT_APPS_SETTINGS {
+        let Some(program) = find_in_path(program) else {
+            continue;
+        };
+        let mut command = Command::new(&program);
+        command.args(*arguments);
+        match spawn_detached(&mut command) {
+            Ok(()) => return Ok(()),
+            Err(err) => log::warn!("无法启动 {}: {}", program, err),
+        }
+    }
+    Err("未找到默认应用设置，请在系统设置中手动修改".to_string())
+}
+
+/// 在默认浏览器中打开 http/https 链接。
+pub fn open_url_in_browser(url: &str) -> Result<(), String> {
+    open_url_in_browser_verbose(url).map(|_| ())
+}
+
+/// 与 [`open_url_in_browser`] 相同，成功时返回实际执行的命令。
+pub fn open_url_in_browser_verbose(url: &str) -> Result<OpenOutcome, String> {
+    let url = validate_browser_url(url)?;
+    open_with_default(url)
+}
+
+/// 编辑器配置目录相对于系统配置目录的位置。
+enum EditorConfigLocation {
+    /// 固定的子目录。
+    Fixed(&'static [&'static str]),
+    /// `JetBrains/<产品前缀><版本号>`，取版本最新的一个。
+    JetBrains(&'static [&'static str]),
+}
+
+const EDITOR_CONFIG_LOCATIONS: &[(&str, EditorConfigLocation)] = &[
+    ("vscode", EditorConfigLocation::Fixed(&["Code", "User"])),
+    (
+        "vscode-insiders",
+        EditorConfigLocation::Fixed(&["Code - Insiders", "User"]),
+    ),
+    (
+        "intellij-idea",
+        EditorConfigLocation::JetBrains(&["IntelliJIdea", "IdeaIC"]),
+    ),
+    (
+        "pycharm",
+        EditorConfigLocation::JetBrains(&["PyCharm", "PyCharmCE"]),
+    ),
+    ("webstorm", EditorConfigLocation::JetBrains(&["WebStorm"])),
+    ("goland", EditorConfigLocation::JetBrains(&["GoLand"])),
+    ("rider", EditorConfigLocation::JetBrains(&["Rider"])),
+    ("clion", EditorConfigLocation::JetBrains(&["CLion"])),
+    ("phpstorm", EditorConfigLocation::JetBrains(&["PhpStorm"])),
+    ("datagrip", EditorConfigLocation::JetBrains(&["DataGrip"])),
+];
+
+/// 在文件管理器中打开编辑器的用户配置目录。
+pub fn open_editor_config_dir(preset_id: &str) -> Result<(), String> {
+    let root = user_config_root().ok_or("无法获取用户配置目录")?;
+    let dir = resolve_editor_config_dir(preset_id, &root)?;
+    open_with_default(&dir.to_string_lossy()).map(|_| ())
+}
+
+// VS Code 内置的默认配置文件，不会出现在 storage.json 的配置文件列表中。
+const VSCODE_DEFAULT_PROFILE: &str = "Default";
+
+/// 列出 VS Code 中已创建的配置文件（Profile）名称，默认配置文件总在第一位。
+/// 名称读取自用户配置目录下的 `globalStorage/storage.json`；从未创建过配置文件时该文件
+/// 可能不存在或不含 `userDataProfiles`，此时只返回默认配置文件。
+pub fn list_vscode_profiles() -> Result<Vec<String>, String> {
+    let root = user_config_root().ok_or("无法获取用户配置目录")?;
+    let dir = resolve_editor_config_dir("vscode", &root)?;
+    let storage = dir.join("globalStorage").join("storage.json");
+    match fs::read_to_string(&storage) {
+        Ok(content) => parse_vscode_profiles(&content),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            Ok(vec![VSCODE_DEFAULT_PROFILE.to_string()])
+        }
+        Err(err) => Err(format!("读取 VS Code 配置失败: {err}")),
+    }
+}
+
+// storage.json 中的 `userDataProfiles` 形如 `[{"location": "-1a2b3c", "name": "Work"}]`。
+fn parse_vscode_profiles(content: &str) -> Result<Vec<String>, String> {
+    let storage: serde_json::Value =
+        serde_json::from_str(content).map_err(|err| format!("解析 VS Code 配置失败: {err}"))?;
+    let mut profiles = vec![VSCODE_DEFAULT_PROFILE.to_string()];
+    let entries = storage
+        .get("userDataProfiles")
+        .and_then(|value| value.as_array())
+        .into_iter()
+        .flatten();
+    for entry in entries {
+        let Some(name) = entry.get("name").and_then(|name| name.as_str()) else {
+            continue;
+        };
+        let name = name.trim();
+        if !name.is_empty() && !profiles.iter().any(|profile| profile == name) {
+            profiles.push(name.to_string());
+        }
+    }
+    Ok(profiles)
+}
+
+/// 清除 VS Code 为项目保存的工作区存储（`User/workspaceStorage/<ID>`），用于修复该目录
+/// 损坏导致的异常。必须传入 `confirm = true` 才会删除；返回被删除的目录，不存在时返回
+/// `None`。目录 ID 按 VS Code 的规则计算，删除前还会核对该目录确实位于 workspaceStorage
+/// 之下，且其中 `workspace.json` 记录的文件夹就是该项目。
+pub fn clear_workspace_storage(path: &str, confirm: bool) -> Result<Option<String>, String> {
+    if !confirm {
+        return Err("清除工作区存储前需要确认".to_string());
+    }
+    let path = normalize_input_path(path)?;
+    let metadata = fs::metadata(&path).map_err(|err| format!("读取项目目录失败: {err}"))?;
+    if !metadata.is_dir() {
+        return Err("仅支持清除文件夹的工作区存储".to_string());
+    }
+    let root = user_config_root().ok_or("无法获取用户配置目录")?;
+    let storage_root = resolve_editor_config_dir("vscode", &root)?.join("workspaceStorage");
+    let fs_path = vscode_fs_path(&path, cfg!(target_os = "windows"));
+    let id = vscode_workspace_id(&fs_path, vscode_folder_salt(&metadata).as_deref());
+    let storage_dir = storage_root.join(&id);
+    if fs::symlink_metadata(&storage_dir).is_err() {
+        return Ok(None);
+    }
+    validate_workspace_storage_dir(&storage_root, &storage_dir, &fs_path)?;
+    fs::remove_dir_all(&storage_dir).map_err(|err| format!("删除工作区存储失败: {err}"))?;
+    log::info!("已清除工作区存储 {}", storage_dir.display());
+    Ok(Some(storage_dir.to_string_lossy().to_string()))
+}
+
+// VS Code 使用 `URI.fsPath` 形式的路径计算 ID：去掉末尾分隔符，Windows 上盘符小写、
+// 分隔符为 `\`。也用于把 workspace.json 中 URI 的路径部分（如 `/c:/work`）还原。
+fn vscode_fs_path(path: &str, windows: bool) -> String {
+    if !windows {
+        let trimmed = path.trim_end_matches('/');
+        return if trimmed.is_empty() { "/" } else { trimmed }.to_string();
+    }
+    let mut path = path.replace('/', "\\");
+    let bytes = path.as_bytes();
+    if bytes.len() >= 3 && bytes[0] == b'\\' && bytes[1].is_ascii_alphabetic() && bytes[2] == b':' {
+        path.remove(0);
+    }
+    if path.as_bytes().get(1) == Some(&b':') {
+        path[..1].make_ascii_lowercase();
+    }
+    while path.ends_with('\\') && !path.ends_with(":\\") && path.len() > 1 {
+        path.pop();
+    }
+    path
+}
+
+// 本地文件夹的 ID 为 MD5(fsPath + 盐值)，盐值用于区分删除后重建的同名文件夹。
+fn vscode_workspace_id(fs_path: &str, salt: Option<&str>) -> String {
+    let mut input = fs_path.as_bytes().to_vec();
+    input.extend_from_slice(salt.unwrap_or_default().as_bytes());
+    md5_hex(&input)
+}
+
+// Linux 的创建时间不可靠，VS Code 改用 inode；其他平台使用创建时间的毫秒数。
+fn vscode_folder_salt(metadata: &fs::Metadata) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some(metadata.ino().to_string())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let created = metadata.created().ok()?;
+        let millis = created
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_millis();
+        Some(millis.to_string())
+    }
+}
+
+// 只允许删除 workspaceStorage 的直接子目录（非符号链接、名称为 32 位十六进制），
+// 且 workspace.json 中记录的文件夹必须与项目路径一致。
+fn validate_workspace_storage_dir(
+    storage_root: &Path,
+    storage_dir: &Path,
+    fs_path: &str,
+) -> Result<(), String> {
+    let is_hash_name = storage_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.len() == 32 && name.bytes().all(|byte| byte.is_ascii_hexdigit()));
+    let metadata =
+        fs::symlink_metadata(storage_dir).map_err(|err| format!("读取工作区存储失败: {err}"))?;
+    if !is_hash_name || !metadata.is_dir() {
+        return Err(format!("工作区存储目录无效: {}", storage_dir.display()));
+    }
+    let root =
+        fs::canonicalize(storage_root).map_err(|err| format!("读取工作区存储失败: {err}"))?;
+    let dir = fs::canonicalize(storage_dir).map_err(|err| format!("读取工作区存储失败: {err}"))?;
+    if dir.parent() != Some(root.as_path()) {
+        return Err(format!("工作区存储路径越界: {}", storage_dir.display()));
+    }
+    let content = fs::read_to_string(dir.join("workspace.json"))
+        .map_err(|err| format!("读取 workspace.json 失败: {err}"))?;
+    if !workspace_folder_matches(&content, fs_path, cfg!(target_os = "windows")) {
+        return Err(format!("工作区存储不属于该项目: {}", storage_dir.display()));
+    }
+    Ok(())
+}
+
+// workspace.json 形如 `{"folder": "file:///home/me/project"}`。
+fn workspace_folder_matches(content: &str, fs_path: &str, windows: bool) -> bool {
+    let Ok(workspace) = serde_json::from_str::<serde_json::Value>(content) else {
+        return false;
+    };
+    let Some(folder) = workspace
+        .get("folder")
+        .and_then(|folder| folder.as_str())
+        .and_then(|folder| folder.strip_prefix("file://"))
+    else {
+        return false;
+    };
+    let Some(slash) = folder.find('/') else {
+        return false;
+    };
+    let (host, folder_path) = folder.split_at(slash);
+    if !host.is_empty() && host != "localhost" {
+        return false;
+    }
+    let folder_path = vscode_fs_path(&percent_decode(folder_path), windows);
+    if windows {
+        folder_path.eq_ignore_ascii_case(fs_path)
+    } else {
+        folder_path == fs_path
+    }
+}
+
+// 各平台存放应用配置的目录：macOS 为 Application Support，Windows 为 %APPDATA%，
+// 其他平台为 XDG 配置目录（见 `xdg_config_dir`）。
+fn user_config_root() -> Option<PathBuf> {
+    let non_empty = |key: &str| std::env::var_os(key).filter(|value| !value.is_empty());
+    if cfg!(target_os = "macos") {
+        non_empty("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else if cfg!(target_os = "windows") {
+        non_empty("APPDATA").map(PathBuf::from)
+    } else {
+        xdg_config_dir()
+    }
+}
+
+/// `$XDG_CONFIG_HOME`，缺省为 `~/.config`。
+fn xdg_config_dir() -> Option<PathBuf> {
+    xdg_base_dir("XDG_CONFIG_HOME", ".config", |name| {
+        std::env::var(name).ok()
+    })
+}
+
+/// `$XDG_DATA_HOME`，缺省为 `~/.local/share`。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn xdg_data_dir() -> Option<PathBuf> {
+    xdg_base_dir("XDG_DATA_HOME", ".local/share", |name| {
+        std::env::var(name).ok()
+    })
+}
+
+/// `$XDG_CACHE_HOME`，缺省为 `~/.cache`。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn xdg_cache_dir() -> Option<PathBuf> {
+    xdg_base_dir("XDG_CACHE_HOME", ".cache", |name| std::env::var(name).ok())
+}
+
+// 按 XDG Base Directory 规范，变量未设置、为空或不是绝对路径时都视为无效，
+// 回退到主目录下的默认位置。
+fn xdg_base_dir(
+    key: &str,
+    home_relative: &str,
+    lookup_var: impl Fn(&str) -> Option<String>,
+) -> Option<PathBuf> {
+    lookup_var(key)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| {
+            lookup_var("HOME")
+                .filter(|home| !home.is_empty())
+                .map(|home| PathBuf::from(home).join(home_relative))
+        })
+}
+
+fn resolve_editor_config_dir(preset_id: &str, root: &Path) -> Result<PathBuf, String> {
+    let location = EDITOR_CONFIG_LOCATIONS
+        .iter()
+        .find(|(id, _)| *id == preset_id)
+        .map(|(_, location)| location)
+        .ok_or_else(|| format!("不支持打开该编辑器的配置目录: {preset_id}"))?;
+    let dir = match location {
+        EditorConfigLocation::Fixed(components) => components
+            .iter()
+            .fold(root.to_path_buf(), |dir, component| dir.join(component)),
+        EditorConfigLocation::JetBrains(prefixes) => {
+            latest_jetbrains_config_dir(&root.join("JetBrains"), prefixes)
+                .ok_or_else(|| format!("未找到配置目录: {preset_id}"))?
+        }
+    };
+    if !dir.is_dir() {
+        return Err(format!("配置目录不存在: {}", dir.display()));
+    }
+    Ok(dir)
+}
+
+// JetBrains 每个大版本一个配置目录，例如 `PyCharm2024.1`；前缀后必须紧跟版本号，
+// 以免 `PyCharm` 误匹配到 `PyCharmCE2024.1`。
+fn latest_jetbrains_config_dir(root: &Path, prefixes: &[&str]) -> Option<PathBuf> {
+    fs::read_dir(root)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let version = prefixes
+                .iter()
+                .find_map(|prefix| parse_dotted_version(name.strip_prefix(prefix)?))?;
+            Some((version, entry.path()))
+        })
+        .max_by(|(left, _), (right, _)| left.cmp(right))
+        .map(|(_, path)| path)
+}
+
+fn parse_dotted_version(value: &str) -> Option<Vec<u32>> {
+    value
+        .split('.')
+        .map(|part| part.parse::<u32>().ok())
+        .collect()
+}
+
+/// 开发工具预设提供者，可在运行时注册以扩展内置的检测逻辑。
+pub trait PresetProvider: Send + Sync {
+    /// 返回当前环境中检测到的预设。
+    fn detect(&self) -> Vec<DevToolPreset>;
+}
+
+// 内置的各平台预设检测器。
+struct BuiltinPresetProvider;
+
+impl PresetProvider for BuiltinPresetProvider {
+    fn detect(&self) -> Vec<DevToolPreset> {
+        #[cfg(target_os = "macos")]
+        let presets = list_dev_tool_presets_macos();
+        #[cfg(target_os = "windows")]
+        let presets = list_dev_tool_presets_windows();
+        #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+        let presets = list_dev_tool_presets_linux();
+        presets
+    }
+}
+
+static PRESET_PROVIDERS: OnceLock<Mutex<Vec<Box<dyn PresetProvider>>>> = OnceLock::new();
+
+// 获取预设提供者注册表，首次访问时放入内置检测器。
+fn preset_providers() -> MutexGuard<'static, Vec<Box<dyn PresetProvider>>> {
+    let providers = PRESET_PROVIDERS.get_or_init(|| {
+        Mutex::new(vec![
+            Box::new(BuiltinPresetProvider) as Box<dyn PresetProvider>
+        ])
+    });
+    providers.lock().unwrap_or_else(|poisoned| {
+        log::warn!("预设提供者注册表锁异常，继续使用现有数据");
+        poisoned.into_inner()
+    })
+}
+
+/// 注册额外的预设提供者，后续 list_dev_tool_presets 会一并调用。
+pub fn register_preset_provider(provider: Box<dyn PresetProvider>) {
+    preset_providers().push(provider);
+}
+
+/// 列出已检测到的开发工具预设。
+pub fn list_dev_tool_presets() -> Vec<DevToolPreset> {
+    // 检测期间持有注册表锁，提供者不能在 detect 中再注册新的提供者。
+    let providers = preset_providers();
+    with_path_lookup_cache(|| {
+        merge_provider_presets(providers.iter().map(|provider| provider.detect()))
+    })
+}
+
+/// 在内置检测结果之外扫描应用目录，补充声明能打开源代码的 macOS 应用。
+/// 需要逐个读取应用的 Info.plist，开销较大，仅在用户主动请求时调用；其他平台等同于
+/// [`list_dev_tool_presets`]。
+pub fn list_dev_tool_presets_with_discovery() -> Vec<DevToolPreset> {
+    let curated = list_dev_tool_presets();
+    #[cfg(target_os = "macos")]
+    let curated = merge_discovered_presets(curated, discover_macos_editors());
+    curated
+}
+
+/// 通过 `code --list-extensions` 列出已安装的 VS Code 扩展 ID。
+pub fn list_vscode_extensions() -> Result<Vec<String>, String> {
+    let cli = resolve_vscode_cli().ok_or("未找到 VS Code 命令行工具 code")?;
+    let arguments = vec!["--list-extensions".to_string()];
+    let (child, _) =
+        launch_with_shell_support(&cli, &arguments, &LaunchOptions::default(), |command| {
+            command
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+        })
+        .map_err(|err| format!("无法执行 VS Code 命令行: {err}"))?;
+    let output = read_output_with_timeout(child, VSCODE_CLI_TIMEOUT)?;
+    Ok(non_empty_lines(&output))
+}
+
+// `code` 命令行通常在 PATH 中；macOS 未安装 shell 命令时使用应用包内的脚本，
+// Windows 使用安装目录下的 `bin\code.cmd`。
+fn resolve_vscode_cli() -> Option<String> {
+    if let Some(cli) = find_in_path("code") {
+        return Some(cli);
+    }
+    #[cfg(target_os = "macos")]
+    let bundled =
+        find_macos_app("Visual Studio Code").map(|app| app.join("Contents/Resources/app/bin/code"));
+    #[cfg(target_os = "windows")]
+    let bundled =
+        find_windows_vscode().and_then(|exe| Some(exe.parent()?.join("bin").join("code.cmd")));
+    #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+    let bundled: Option<PathBuf> = None;
+    bundled
+        .filter(|cli| cli.is_file())
+        .map(|cli| cli.to_string_lossy().to_string())
+}
+
+/// 实际运行检测到的编辑器并返回其版本或帮助信息，用于排查问题时确认程序本身能正常执行：
+/// VS Code 执行 `--version`，JetBrains 执行 `--help`，其他编辑器执行 `--version`。
+/// 与读取版本号不同，这会真正启动编辑器的命令行入口；超过时限未退出时结束进程并返回错误。
+/// macOS 上经 `open -a` 启动的预设改为执行应用包内的程序。
+pub fn launch_editor_about(preset_id: &str) -> Result<EditorAboutResult, String> {
+    let preset = list_dev_tool_presets()
+        .into_iter()
+        .find(|preset| preset.id == preset_id)
+        .ok_or_else(|| format!("未找到编辑器: {preset_id}"))?;
+    let family = preset_editor_family(&preset);
+    let flag = match family {
+        EditorFamily::JetBrains => "--help",
+        EditorFamily::VsCode | EditorFamily::Other => "--version",
+    };
+    let command_path = editor_cli_path(&preset, family)?;
+    let (child, _) = launch_with_shell_support(
+        &command_path,
+        &[flag.to_string()],
+        &LaunchOptions::default(),
+        |command| {
+            command
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+        },
+    )
+    .map_err(|err| format!("无法启动 {command_path}: {err}"))?;
+    capture_output_with_timeout(child, EDITOR_ABOUT_TIMEOUT)
+}
+
+// 编辑器的命令行入口：macOS 的 VS Code 应用包使用 `bin/code` 脚本，
+// 其他应用使用 Info.plist 中声明的主程序。
+fn editor_cli_path(preset: &DevToolPreset, family: EditorFamily) -> Result<String, String> {
+    #[cfg(target_os = "macos")]
+    if is_macos_open_launcher(&preset.command_path) {
+        let bundle = resolve_preset_path(preset).ok_or("未找到应用")?;
+        let bundle = Path::new(&bundle);
+        let cli = if family == EditorFamily::VsCode {
+            bundle.join("Contents/Resources/app/bin/code")
+        } else {
+            let info = read_bundle_info(bundle).ok_or("无法读取应用信息")?;
+            let executable = info
+                .get("CFBundleExecutable")
+                .and_then(|value| value.as_str())
+                .ok_or("应用未声明主程序")?;
+            bundle.join("Contents/MacOS").join(executable)
+        };
+        return Ok(cli.to_string_lossy().to_string());
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = family;
+    Ok(preset.command_path.clone())
+}
+
+// 同时读取标准输出与标准错误，超时则结束子进程。
+fn capture_output_with_timeout(
+    mut child: Child,
+    timeout: Duration,
+) -> Result<EditorAboutResult, String> {
+    fn read_pipe(mut pipe: impl Read + Send + 'static) -> std::thread::JoinHandle<String> {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = pipe.read_to_end(&mut buffer);
+            String::from_utf8_lossy(&buffer).to_string()
+        })
+    }
+
+    let stdout = read_pipe(child.stdout.take().ok_or("无法读取命令输出")?);
+    let stderr = read_pipe(child.stderr.take().ok_or("无法读取命令输出")?);
+    let status = match wait_for_exit(|| child.try_wait(), timeout, PRESET_TEST_POLL_INTERVAL) {
+        Ok(Some(status)) => status,
+        Ok(None) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("命令执行超时".to_string());
+        }
+        Err(err) => return Err(format!("无法获取进程状态: {err}")),
+    };
+    let mut output = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !stderr.trim().is_empty() {
+        if !output.is_empty() && !output.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push_str(&stderr);
+    }
+    Ok(EditorAboutResult {
+        output,
+        exit_code: status.code(),
+    })
+}
+
+// 在后台线程读取标准输出，避免输出填满管道后子进程阻塞；超时则结束子进程。
+fn read_output_with_timeout(mut child: Child, timeout: Duration) -> Result<String, String> {
+    let mut stdout = child.stdout.take().ok_or("无法读取命令输出")?;
+    let reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).map(|_| output)
+    });
+    match wait_for_exit(|| child.try_wait(), timeout, PRESET_TEST_POLL_INTERVAL) {
+        Ok(Some(status)) if status.success() => reader
+            .join()
+            .map_err(|_| "读取命令输出失败".to_string())?
+            .map_err(|err| format!("读取命令输出失败: {err}")),
+        Ok(Some(status)) => Err(format!("命令执行失败: {status}")),
+        Ok(None) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            Err("命令执行超时".to_string())
+        }
+        Err(err) => Err(format!("无法获取进程状态: {err}")),
+    }
+}
+
+/// 供 CI 与测试使用的限时运行：总是捕获标准输出与标准错误，超过 `timeout_ms` 仍未结束时
+/// 结束整个进程树（Unix 为进程组，Windows 为作业对象），而不只是直接子进程，因为 GUI
+/// 启动器常会派生出脱离父进程的子进程。直接子进程退出后，若其派生的进程仍持有输出管道，
+/// 同样等到截止时间为止。经 `open -a` 等由系统服务代为启动的应用不在进程树内，无法结束。
+pub fn launch_with_deadline(
+    preset: &DevToolPreset,
+    path: &str,
+    timeout_ms: u64,
+) -> Result<DeadlineRunResult, String> {
+    let path = normalize_input_path(path)?;
+    let arguments = build_command_arguments(Some(preset.arguments.clone()), &path);
+    let options = LaunchOptions {
+        preset_id: Some(preset.id.clone()),
+        ..LaunchOptions::default()
+    };
+    let (mut tree, _) =
+        launch_with_shell_support(&preset.command_path, &arguments, &options, |command| {
+            command
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped());
+            ProcessTree::spawn(command)
+        })
+        .map_err(|err| format!("无法启动 {}: {err}", preset.command_path))?;
+    run_until_deadline(&mut tree, Duration::from_millis(timeout_ms))
+}
+
+fn run_until_deadline(
+    tree: &mut ProcessTree,
+    timeout: Duration,
+) -> Result<DeadlineRunResult, String> {
+    fn forward(
+        mut pipe: impl Read + Send + 'static,
+        is_stderr: bool,
+        sender: mpsc::Sender<(bool, String)>,
+    ) {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = pipe.read_to_end(&mut buffer);
+            let _ = sender.send((is_stderr, String::from_utf8_lossy(&buffer).to_string()));
+        });
+    }
+
+    let deadline = Instant::now() + timeout;
+    let (sender, receiver) = mpsc::channel();
+    forward(
+        tree.child.stdout.take().ok_or("无法读取命令输出")?,
+        false,
+        sender.clone(),
+    );
+    forward(
+        tree.child.stderr.take().ok_or("无法读取命令输出")?,
+        true,
+        sender,
+    );
+
+    let mut result = DeadlineRunResult::default();
+    let status = wait_for_exit(|| tree.child.try_wait(), timeout, PRESET_TEST_POLL_INTERVAL)
+        .map_err(|err| format!("无法获取进程状态: {err}"))?;
+    result.timed_out = status.is_none() || !receive_outputs(&receiver, &mut result, deadline);
+    if result.timed_out {
+        tree.kill();
+        receive_outputs(&receiver, &mut result, Instant::now() + DEADLINE_KILL_GRACE);
+    }
+    let status = match status {
+        Some(status) => Some(status),
+        None => tree.child.wait().ok(),
+    };
+    result.exit_code = status.and_then(|status| status.code());
+    Ok(result)
+}
+
+// 接收两个管道的全部输出，截止时间前未全部关闭时返回 false。
+fn receive_outputs(
+    receiver: &mpsc::Receiver<(bool, String)>,
+    result: &mut DeadlineRunResult,
+    deadline: Instant,
+) -> bool {
+    loop {
+        match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok((true, output)) => result.stderr = output,
+            Ok((false, output)) => result.stdout = output,
+            // 两个发送端都已结束，说明输出已全部收到。
+            Err(mpsc::RecvTimeoutError::Disconnected) => return true,
+            Err(mpsc::RecvTimeoutError::Timeout) => return false,
+        }
+    }
+}
+
+/// 连同派生进程一起管理的子进程：Unix 上以新进程组启动，Windows 上加入作业对象。
+struct ProcessTree {
+    child: Child,
+    #[cfg(target_os = "windows")]
+    job: Option<WindowsJob>,
+}
+
+impl ProcessTree {
+    #[cfg(not(target_os = "windows"))]
+    fn spawn(command: &mut Command) -> std::io::Result<Self> {
+        use std::os::unix::process::CommandExt;
+
+        command.process_group(0);
+        Ok(Self {
+            child: command.spawn()?,
+        })
+    }
+
+    #[cfg(target_os = "windows")]
+    fn spawn(command: &mut Command) -> std::io::Result<Self> {
+        let child = command.spawn()?;
+        // 启动与加入作业之间有短暂间隔，这期间派生的进程不受作业管理。
+        let job = WindowsJob::assign(&child)
+            .map_err(|err| log::warn!("无法创建作业对象: {}", err))
+            .ok();
+        Ok(Self { child, job })
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn kill(&mut self) {
+        // 子进程是新进程组的组长，组 ID 即其 PID。
+        unsafe {
+            libc::killpg(self.child.id() as libc::pid_t, libc::SIGKILL);
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    fn kill(&mut self) {
+        match &self.job {
+            Some(job) => job.terminate(),
+            None => {
+                let _ = self.child.kill();
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+#[link(name = "kernel32")]
+extern "system" {
+    fn CreateJobObjectW(
+        attributes: *const std::ffi::c_void,
+        name: *const u16,
+    ) -> *mut std::ffi::c_void;
+    fn AssignProcessToJobObject(job: *mut std::ffi::c_void, process: *mut std::ffi::c_void) -> i32;
+    fn TerminateJobObject(job: *mut std::ffi::c_void, exit_code: u32) -> i32;
+    fn CloseHandle(handle: *mut std::ffi::c_void) -> i32;
+}
+
+#[cfg(target_os = "windows")]
+struct WindowsJob(*mut std::ffi::c_void);
+
+#[cfg(target_os = "windows")]
+impl WindowsJob {
+    fn assign(child: &Child) -> std::io::Result<Self> {
+        use std::os::windows::io::AsRawHandle;
+
+        let handle = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+        if handle.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+        let job = Self(handle);
+        if unsafe { AssignProcessToJobObject(job.0, child.as_raw_handle()) } == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(job)
+    }
+
+    fn terminate(&self) {
+        unsafe {
+            TerminateJobObject(self.0, 1);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for WindowsJob {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+/// 检查可执行文件或应用包的代码签名与公证状态，供启动自定义命令前确认来源。
+/// 先用 `codesign --verify --deep --strict` 校验签名，签名有效时再用 `spctl --assess`
+/// 询问 Gatekeeper 是否已公证；非 macOS 平台返回 [`CodesignStatus::UnsupportedPlatform`]。
+pub fn check_macos_codesign(path: &str) -> Result<CodesignStatus, String> {
+    let path = normalize_input_path(path)?;
+    #[cfg(target_os = "macos")]
+    let status = assess_macos_codesign(&path)?;
+    #[cfg(not(target_os = "macos"))]
+    let status = {
+        let _ = path;
+        CodesignStatus::UnsupportedPlatform
+    };
+    Ok(status)
+}
+
+#[cfg(target_os = "macos")]
+fn assess_macos_codesign(path: &str) -> Result<CodesignStatus, String> {
+    if !Path::new(path).exists() {
+        return Err(format!("路径不存在: {path}"));
+    }
+    let codesign = Command::new("/usr/bin/codesign")
+        .args(["--verify", "--deep", "--strict"])
+        .arg(path)
+        .output()
+        .map_err(|err| format!("无法执行 codesign: {err}"))?;
+    let codesign_stderr = String::from_utf8_lossy(&codesign.stderr);
+    let spctl = if codesign.status.success() {
+        Command::new("/usr/sbin/spctl")
+            .args(["--assess", "--type", "execute", "-vv"])
+            .arg(path)
+            .output()
+            .ok()
+    } else {
+        None
+    };
+    let spctl = spctl.as_ref().map(|output| {
+        (
+            output.status.success(),
+            String::from_utf8_lossy(&output.stderr),
+        )
+    });
+    Ok(parse_codesign_status(
+        codesign.status.success(),
+        &codesign_stderr,
+        spctl
+            .as_ref()
+            .map(|(accepted, output)| (*accepted, output.as_ref())),
+    ))
+}
+
+// spctl 把结果写到 stderr，例如 `/Applications/X.app: accepted` 与
+// `source=Notarized Developer ID`；命令行工具通常因不是应用而被拒绝，此时签名本身仍然有效。
+#[cfg(any(target_os = "macos", test))]
+fn parse_codesign_status(
+    codesign_succeeded: bool,
+    codesign_stderr: &str,
+    spctl: Option<(bool, &str)>,
+) -> CodesignStatus {
+    if !codesign_succeeded {
+        return if codesign_stderr.contains("not signed at all") {
+            CodesignStatus::Unsigned
+        } else {
+            CodesignStatus::Rejected
+        };
+    }
+    match spctl {
+        Some((true, output))
+            if output.lines().any(|line| {
+                line.trim()
+                    .strip_prefix("source=")
+                    .is_some_and(|source| source.starts_with("Notarized"))
+            }) =>
+        {
+            CodesignStatus::Notarized
+        }
+        _ => CodesignStatus::Signed,
+    }
+}
+
+/// 解析预设实际会启动的程序位置，便于确认同时安装多个版本时用的是哪一个：
+/// macOS 的 `open -a` 预设返回 .app 包路径，其余返回命令的绝对路径；已无法解析时返回 None。
+pub fn resolve_preset_path(preset: &DevToolPreset) -> Option<String> {
+    #[cfg(target_os = "macos")]
+    if is_macos_open_launcher(&preset.command_path) {
+        let app_name = macos_preset_app_name(preset)?;
+        let bundle = match macos_bundle_path(app_name) {
+            Some(bundle_path) => Some(PathBuf::from(bundle_path)).filter(|path| path.is_dir()),
+            None => find_macos_app(app_name),
+        };
+        return bundle.map(|path| path.to_string_lossy().to_string());
+    }
+    resolve_command_path(&preset.command_path)
+}
+
+// 绝对路径要求文件仍然存在，裸命令名在 PATH 中查找，其他相对路径无法确定位置。
+fn resolve_command_path(command_path: &str) -> Option<String> {
+    let command_path = expand_path(command_path.trim());
+    let path = Path::new(&command_path);
+    if path.is_absolute() {
+        return path.is_file().then_some(command_path);
+    }
+    if path.components().count() != 1 {
+        return None;
+    }
+    find_in_path(&command_path)
+}
+
+/// 检查预设的命令与参数模板中的常见错误，供保存自定义预设前提示用户。
+pub fn validate_preset(preset: &DevToolPreset) -> Vec<PresetWarning> {
+    let mut warnings = Vec::new();
+    let command_path = preset.command_path.trim();
+    // code-server 预设的命令是实例地址，路径通过查询参数传入，不使用参数模板。
+    if preset.kind == PresetKind::CodeServer {
+        if command_path.is_empty() {
+            warnings.push(PresetWarning::EmptyCommandPath);
+        }
+        return warnings;
+    }
+    if command_path.is_empty() {
+        warnings.push(PresetWarning::EmptyCommandPath);
+    } else if resolve_command_path(command_path).is_none() {
+        warnings.push(PresetWarning::CommandNotFound {
+            command: command_path.to_string(),
+        });
+    }
+    // 没有参数时路径本来就作为唯一参数传入，不需要提示。
+    if !preset.arguments.is_empty()
+        && !preset
+            .arguments
+            .iter()
+            .any(|argument| expand_path_placeholder(argument, "").1)
+    {
+        warnings.push(PresetWarning::PathNotReferenced);
+    }
+    for argument in &preset.arguments {
+        warnings.extend(
+            unknown_placeholders(argument)
+                .into_iter()
+                .map(|placeholder| PresetWarning::UnknownPlaceholder { placeholder }),
+        );
+        if has_suspicious_quotes(argument) {
+            warnings.push(PresetWarning::SuspiciousQuotes {
+                argument: argument.clone(),
+            });
+        }
+    }
+    warnings
+}
+
+// 形如 `{name}` 的占位符，跳过 `{path}` 与转义的 `{{...}}`；花括号内不是标识符时
+// （例如 JSON）不视为占位符。
+fn unknown_placeholders(argument: &str) -> Vec<String> {
+    let mut placeholders = Vec::new();
+    let mut rest = argument;
+    while let Some(index) = rest.find('{') {
+        rest = &rest[index..];
+        if let Some(escaped_end) = rest.strip_prefix("{{").and_then(|after| after.find("}}")) {
+            rest = &rest[escaped_end + 4..];
+            continue;
+        }
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        let name = &rest[1..end];
+        if name != "path"
+            && !name.is_empty()
+            && name
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        {
+            placeholders.push(rest[..=end].to_string());
+        }
+        rest = &rest[1..];
+    }
+    placeholders
+}
+
+// 双引号不成对，或单/双引号包裹了整个参数；单个撇号（如 `--title=It's`）是正常文本。
+fn has_suspicious_quotes(argument: &str) -> bool {
+    let wrapped = ['"', '\''].iter().any(|&quote| {
+        argument.len() >= 2 && argument.starts_with(quote) && argument.ends_with(quote)
+    });
+    wrapped || argument.matches('"').count() % 2 == 1
+}
+
+/// 把预设导出为格式化的 JSON 数组，便于在其他机器上通过 [`import_presets`] 导入。
+pub fn export_presets(presets: &[DevToolPreset]) -> String {
+    serde_json::to_string_pretty(presets).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// 导入 [`export_presets`] 导出的预设配置，逐条检查而不是整体失败：
+/// 条目无法解析、ID 与前面的条目重复，或 [`validate_preset`] 报告命令为空、找不到命令、
+/// 未知占位符、引号可疑时拒绝该条目并说明原因；参数未引用 `{path}` 只是提示，照常导入。
+/// 内容不是 JSON 数组时返回错误。
+pub fn import_presets(json: &str) -> Result<PresetImportResult, String> {
+    let entries: Vec<serde_json::Value> =
+        serde_json::from_str(json).map_err(|err| format!("预设配置格式无效: {err}"))?;
+    let mut result = PresetImportResult {
+        presets: Vec::new(),
+        rejected: Vec::new(),
+    };
+    for (index, entry) in entries.into_iter().enumerate() {
+        let id = entry
+            .get("id")
+            .and_then(|id| id.as_str())
+            .map(str::to_string);
+        let mut rejected = RejectedPreset {
+            index,
+            id,
+            error: None,
+            warnings: Vec::new(),
+        };
+        let preset = match serde_json::from_value::<DevToolPreset>(entry) {
+            Ok(preset) => preset,
+            Err(err) => {
+                rejected.error = Some(format!("无法解析预设: {err}"));
+                result.rejected.push(rejected);
+                continue;
+            }
+        };
+        if result.presets.iter().any(|other| other.id == preset.id) {
+            rejected.error = Some(format!("预设 ID 重复: {}", preset.id));
+            result.rejected.push(rejected);
+            continue;
+        }
+        rejected.warnings = validate_preset(&preset)
+            .into_iter()
+            .filter(|warning| *warning != PresetWarning::PathNotReferenced)
+            .collect();
+        if rejected.warnings.is_empty() {
+            result.presets.push(preset);
+        } else {
+            result.rejected.push(rejected);
+        }
+    }
+    Ok(result)
+}
+
+/// 批量检查预设，供设置页的诊断表格使用：每个预设给出实际启动的程序位置、结论、
+/// 能识别时的版本号以及 [`validate_preset`] 的提示，顺序与传入一致。
+/// 各预设在独立线程中并行检查，10 秒内仍未完成的标记为超时，
+/// 不再等待，避免某个卡住的工具拖住整份报告。版本号只从不会打开窗口的来源读取：
+/// VS Code 系列执行 `--version`，macOS 应用包读取 Info.plist。
+pub fn verify_all_presets(presets: Vec<DevToolPreset>) -> Vec<PresetHealth> {
+    let deadline = Instant::now() + PRESET_HEALTH_TIMEOUT;
+    let (sender, receiver) = mpsc::channel();
+    let ids: Vec<String> = presets.iter().map(|preset| preset.id.clone()).collect();
+    for (index, preset) in presets.into_iter().enumerate() {
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            let _ = sender.send((index, verify_preset(&preset)));
+        });
+    }
+    drop(sender);
+
+    let mut reports: Vec<Option<PresetHealth>> = vec![None; ids.len()];
+    while reports.iter().any(Option::is_none) {
+        match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok((index, report)) => reports[index] = Some(report),
+            Err(_) => break,
+        }
+    }
+    reports
+        .into_iter()
+        .zip(ids)
+        .map(|(report, id)| {
+            report.unwrap_or_else(|| {
+                log::warn!("预设检查超时: {}", id);
+                PresetHealth {
+                    id,
+                    resolved_path: None,
+                    status: PresetHealthStatus::TimedOut,
+                    version: None,
+                    warnings: Vec::new(),
+                }
+            })
+        })
+        .collect()
+}
+
+fn verify_preset(preset: &DevToolPreset) -> PresetHealth {
+    let warnings = validate_preset(preset);
+    let is_command = preset.kind == PresetKind::Command;
+    let resolved_path = if is_command {
+        resolve_preset_path(preset)
+    } else {
+        None
+    };
+    let missing = is_command && resolved_path.is_none();
+    let version = resolved_path
+        .as_deref()
+        .and_then(|resolved| preset_version(preset, resolved));
+    PresetHealth {
+        id: preset.id.clone(),
+        status: preset_health_status(missing, &warnings),
+        resolved_path,
+        version,
+        warnings,
+    }
+}
+
+fn preset_health_status(missing: bool, warnings: &[PresetWarning]) -> PresetHealthStatus {
+    if missing {
+        PresetHealthStatus::Missing
+    } else if warnings.is_empty() {
+        PresetHealthStatus::Ok
+    } else {
+        PresetHealthStatus::Warning
+    }
+}
+
+fn preset_version(preset: &DevToolPreset, resolved: &str) -> Option<String> {
+    #[cfg(target_os = "macos")]
+    if is_macos_open_launcher(&preset.command_path) {
+        return read_bundle_info(Path::new(resolved))?
+            .get("CFBundleShortVersionString")?
+            .as_str()
+            .map(str::to_string);
+    }
+    if preset_editor_family(preset) != EditorFamily::VsCode {
+        return None;
+    }
+    // Windows 上 VS Code 的命令行入口是 `code.cmd`，需要经过脚本处理。
+    let (child, _) = launch_with_shell_support(
+        resolved,
+        &["--version".to_string()],
+        &LaunchOptions::default(),
+        |command| {
+            command
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+        },
+    )
+    .ok()?;
+    let result = capture_output_with_timeout(child, PRESET_HEALTH_TIMEOUT).ok()?;
+    if result.exit_code != Some(0) {
+        return None;
+    }
+    non_empty_lines(&result.output).into_iter().next()
+}
+
+// PATH 诊断时检查的常用命令行工具。
+const DIAGNOSED_TOOLS: &[&str] = &[
+    "code", "cursor", "zed", "subl", "idea", "webstorm", "pycharm", "goland", "git", "node", "npm",
+    "pnpm", "python3", "cargo", "go", "docker",
+];
+
+// 登录 shell 的 rc 文件可能输出欢迎信息，用标记截取 PATH。
+#[cfg(any(not(target_os = "windows"), test))]
+const LOGIN_PATH_MARKER: &str = "__DEVHAVEN_PATH__";
+
+#[cfg(not(target_os = "windows"))]
+const LOGIN_SHELL_PATH_TIMEOUT: Duration = Duration::from_secs(5);
+
+static LOGIN_SHELL_PATH: OnceLock<Option<String>> = OnceLock::new();
+
+/// 对比进程 PATH 与登录 shell PATH。从 Dock/Finder 启动的应用不会继承 shell 配置中的 PATH，
+/// 此时可提示用户开启“通过登录 shell 启动”。登录 shell 只探测一次。
+pub fn diagnose_path() -> PathDiagnostics {
+    let process_path = std::env::var_os("PATH").unwrap_or_default();
+    let process_dirs: Vec<PathBuf> = std::env::split_paths(&process_path).collect();
+    let login_dirs: Option<Vec<PathBuf>> = LOGIN_SHELL_PATH
+        .get_or_init(probe_login_shell_path)
+        .as_ref()
+        .map(|path| std::env::split_paths(path).collect());
+    let missing_from_process = login_dirs
+        .as_deref()
+        .map(|login_dirs| tools_missing_from_process(DIAGNOSED_TOOLS, &process_dirs, login_dirs))
+        .unwrap_or_default();
+    let to_strings = |dirs: &[PathBuf]| {
+        dirs.iter()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+    };
+    PathDiagnostics {
+        process_path: to_strings(&process_dirs),
+        login_shell_path: login_dirs.as_deref().map(to_strings),
+        missing_from_process,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn probe_login_shell_path() -> Option<String> {
+    None
+}
+
+#[cfg(not(target_os = "windows"))]
+fn probe_login_shell_path() -> Option<String> {
+    let shell = std::env::var("SHELL")
+        .ok()
+        .filter(|shell| !shell.trim().is_empty())
+        .unwrap_or_else(|| "/bin/sh".to_string());
+    let child = Command::new(&shell)
+        .arg("-lc")
+        .arg(format!("printf '{LOGIN_PATH_MARKER}%s' \"$PATH\""))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| log::warn!("无法启动登录 shell {}: {}", shell, err))
+        .ok()?;
+    let output = read_output_with_timeout(child, LOGIN_SHELL_PATH_TIMEOUT)
+        .map_err(|err| log::warn!("读取登录 shell PATH 失败: {}", err))
+        .ok()?;
+    parse_login_shell_path(&output)
+}
+
+#[cfg(any(not(target_os = "windows"), test))]
+fn parse_login_shell_path(output: &str) -> Option<String> {
+    let (_, path) = output.rsplit_once(LOGIN_PATH_MARKER)?;
+    let path = path.trim();
+    (!path.is_empty()).then(|| path.to_string())
+}
+
+fn tools_missing_from_process(
+    tools: &[&str],
+    process_dirs: &[PathBuf],
+    login_dirs: &[PathBuf],
+) -> Vec<String> {
+    let found_in = |dirs: &[PathBuf], tool: &str| dirs.iter().any(|dir| dir.join(tool).is_file());
+    tools
+        .iter()
+        .filter(|tool| found_in(login_dirs, tool) && !found_in(process_dirs, tool))
+        .map(|tool| tool.to_string())
+        .collect()
+}
+
+/// 把用户偏好的预设按 `preferred_ids` 的顺序移到最前面，其余预设保持原有顺序。
+/// 未检测到的偏好 ID 会被忽略。
+pub fn order_presets(presets: Vec<DevToolPreset>, preferred_ids: &[String]) -> Vec<DevToolPreset> {
+    let rank = |preset: &DevToolPreset| {
+        preferred_ids
+            .iter()
+            .position(|id| *id == preset.id)
+            .unwrap_or(preferred_ids.len())
+    };
+    let mut presets = presets;
+    // 稳定排序保证非偏好预设之间的相对顺序不变。
+    presets.sort_by_key(rank);
+    presets
+}
+
+// 合并多个提供者的预设，按 ID 去重，先注册者优先（内置检测器始终最先）。
+fn merge_provider_presets(
+    groups: impl IntoIterator<Item = Vec<DevToolPreset>>,
+) -> Vec<DevToolPreset> {
+    let mut seen = HashSet::new();
+    groups
+        .into_iter()
+        .flatten()
+        .filter(|preset| seen.insert(preset.id.clone()))
+        .collect()
+}
+
+// 根据预设 ID、命令名与应用名推断编辑器家族。
+fn resolve_editor_family(params: &EditorOpenParams) -> EditorFamily {
+    detect_editor_family(
+        params.preset_id.as_deref(),
+        params.command_path.as_deref(),
+        params
+            .app_name
+            .iter()
+            .chain(params.arguments.iter().flatten()),
+    )
+}
+
+fn preset_editor_family(preset: &DevToolPreset) -> EditorFamily {
+    detect_editor_family(
+        Some(&preset.id),
+        Some(&preset.command_path),
+        preset.arguments.iter(),
+    )
+}
+
+// `app_names` 包含应用名以及参数（macOS 预设通过 `open -a <应用名>` 启动）。
+fn detect_editor_family<'a>(
+    preset_id: Option<&str>,
+    command_path: Option<&str>,
+    app_names: impl Iterator<Item = &'a String>,
+) -> EditorFamily {
+    if let Some(family) = preset_id.and_then(editor_family_for_preset_id) {
+        return family;
+    }
+    if let Some(command_path) = command_path {
+        let family = editor_family_for_command(command_path);
+        if family != EditorFamily::Other {
+            return family;
+        }
+    }
+    for name in app_names {
+        let name = name.to_lowercase();
+        if VSCODE_APP_NAMES.iter().any(|known| name.contains(known)) {
+            return EditorFamily::VsCode;
+        }
+        if JETBRAINS_APP_NAMES.iter().any(|known| name.contains(known)) {
+            return EditorFamily::JetBrains;
+        }
+    }
+    EditorFamily::Other
+}
+
+fn editor_family_for_preset_id(preset_id: &str) -> Option<EditorFamily> {
+    match preset_id {
+        "vscode" | "vscode-insiders" => Some(EditorFamily::VsCode),
+        "intellij-idea" | "pycharm" | "webstorm" | "goland" | "rider" | "clion" | "phpstorm"
+        | "datagrip" => Some(EditorFamily::JetBrains),
+        _ => None,
+    }
+}
+
+fn editor_family_for_command(command_path: &str) -> EditorFamily {
+    let stem = command_stem(command_path);
+    let stem = stem.as_str();
+    if VSCODE_COMMAND_NAMES.contains(&stem) || stem == "code - insiders" {
+        EditorFamily::VsCode
+    } else if JETBRAINS_COMMAND_NAMES.contains(&stem) {
+        EditorFamily::JetBrains
+    } else {
+        EditorFamily::Other
+    }
+}
+
+// 命令的小写文件名去掉扩展名与 JetBrains 的 `64` 后缀。
+// 同时兼容 `/` 与 `\` 分隔符，预设可能来自其他平台的配置。
+fn command_stem(command_path: &str) -> String {
+    let file_name = command_path
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(command_path)
+        .to_lowercase();
+    file_name
+        .rsplit_once('.')
+        .map_or(file_name.as_str(), |(stem, _)| stem)
+        .trim_end_matches("64")
+        .to_string()
+}
+
+// 根据启动选项与编辑器能力生成额外参数，不支持的选项直接忽略。
+fn editor_launch_flags(family: EditorFamily, params: &EditorOpenParams) -> Vec<String> {
+    let capabilities = editor_capabilities(family);
+    let mut flags = Vec::new();
+    if params.safe_mode == Some(true) && capabilities.supports_safe_mode {
+        match family {
+            EditorFamily::VsCode => flags.push("--disable-extensions".to_string()),
+            // JetBrains 通过启动命令 `disableNonBundledPlugins` 禁用所有非内置插件。
+            EditorFamily::JetBrains => flags.push("disableNonBundledPlugins".to_string()),
+            EditorFamily::Other => {}
+        }
+    }
+    if let Some(locale) = params.locale.as_deref() {
+        if family == EditorFamily::VsCode && capabilities.supports_locale {
+            flags.extend(["--locale".to_string(), locale.to_string()]);
+        }
+    }
+    if params.new_window == Some(true) {
+        flags.extend(capabilities.new_window_flag.map(str::to_string));
+    }
+    if params.read_only == Some(true) {
+        flags.extend(read_only_flag(family, params.command_path.as_deref()).map(str::to_string));
+    }
+    if params.temp_profile == Some(true) {
+        flags.extend(capabilities.temp_profile_flag.map(str::to_string));
+    }
+    if params.restore_session == Some(false) {
+        if let Some(flag) = capabilities.fresh_session_flag {
+            if !flags.iter().any(|existing| existing == flag) {
+                flags.push(flag.to_string());
+            }
+        }
+    }
+    flags
+}
+
+fn validate_wrapper(wrapper: &[String]) -> Result<(), String> {
+    match wrapper.first() {
+        Some(program) if !program.trim().is_empty() => Ok(()),
+        _ => Err("包装命令不能为空".to_string()),
+    }
+}
+
+// 包装命令的第一项作为实际启动的程序，其余参数、编辑器命令与编辑器参数依次跟在后面。
+fn wrap_command(
+    wrapper: &[String],
+    command_path: &str,
+    arguments: Vec<String>,
+) -> (String, Vec<String>) {
+    let mut wrapped = wrapper[1..].to_vec();
+    wrapped.push(command_path.to_string());
+    wrapped.extend(arguments);
+    (wrapper[0].clone(), wrapped)
+}
+
+// 预设参数或本次的额外参数中已经指定了 `--profile <名称>` 或 `--profile=<名称>`。
+fn has_explicit_profile(params: &EditorOpenParams) -> bool {
+    params
+        .arguments
+        .iter()
+        .chain(params.extra_arguments.iter())
+        .flatten()
+        .any(|argument| argument == "--profile" || argument.starts_with("--profile="))
+}
+
+// JetBrains 没有指定语言的命令行参数，改由 JVM 读取的 `JAVA_TOOL_OPTIONS` 设置系统属性，
+// 保留用户已有的取值；指定 JDK 时设置该产品的 `*_JDK` 变量。
+fn editor_launch_env(family: EditorFamily, params: &EditorOpenParams) -> Vec<(String, String)> {
+    if family != EditorFamily::JetBrains {
+        return Vec::new();
+    }
+    let mut env = Vec::new();
+    if let Some(locale) = params
+        .locale
+        .as_deref()
+        .filter(|_| editor_capabilities(family).supports_locale)
+    {
+        let mut options = jvm_locale_options(locale);
+        if let Some(existing) = std::env::var("JAVA_TOOL_OPTIONS")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+        {
+            options = format!("{existing} {options}");
+        }
+        env.push(("JAVA_TOOL_OPTIONS".to_string(), options));
+    }
+    if let Some(jdk_path) = params.jdk_path.as_deref() {
+        env.push((
+            jetbrains_jdk_env_var(jetbrains_product(params)),
+            jdk_path.to_string(),
+        ));
+    }
+    env
+}
+
+// 启动脚本按产品读取 JDK 变量，WebStorm 沿用历史名称 `WEBIDE_JDK`。
+fn jetbrains_jdk_env_var(product: &str) -> String {
+    match product {
+        "webstorm" => "WEBIDE_JDK".to_string(),
+        product => format!("{}_JDK", product.to_ascii_uppercase()),
+    }
+}
+
+// 校验 JDK 目录下有 `bin/java`；macOS 上也接受 `.jdk` 包，此时使用其中的 `Contents/Home`。
+fn resolve_jdk_home(jdk_path: &str) -> Result<String, String> {
+    let jdk_path = absolute_path_string(&normalize_input_path(jdk_path)?);
+    let java = if cfg!(target_os = "windows") {
+        "java.exe"
+    } else {
+        "java"
+    };
+    let root = Path::new(&jdk_path);
+    [root.to_path_buf(), root.join("Contents").join("Home")]
+        .into_iter()
+        .find(|home| home.join("bin").join(java).is_file())
+        .map(|home| home.to_string_lossy().to_string())
+        .ok_or_else(|| format!("JDK 目录中未找到 bin/{java}: {jdk_path}"))
+}
+
+// `zh-Hans-CN` → `-Duser.language=zh -Duser.country=CN`，地区取两位字母或三位数字的子标签。
+fn jvm_locale_options(locale: &str) -> String {
+    let mut subtags = locale.split('-');
+    let language = subtags.next().unwrap_or_default().to_ascii_lowercase();
+    let mut options = format!("-Duser.language={language}");
+    if let Some(region) = subtags.find(|subtag| {
+        (subtag.len() == 2 && subtag.chars().all(|ch| ch.is_ascii_alphabetic()))
+            || (subtag.len() == 3 && subtag.chars().all(|ch| ch.is_ascii_digit()))
+    }) {
+        options.push_str(&format!(" -Duser.country={}", region.to_ascii_uppercase()));
+    }
+    options
+}
+
+// 近似 BCP 47：2～3 位字母的语言子标签，后接若干 2～8 位字母数字子标签，以 `-` 分隔。
+fn validate_locale(locale: &str) -> Result<&str, String> {
+    let mut subtags = locale.split('-');
+    let language = subtags.next().unwrap_or_default();
+    let valid_language =
+        (2..=3).contains(&language.len()) && language.chars().all(|ch| ch.is_ascii_alphabetic());
+    let valid_rest = subtags.all(|subtag| {
+        (2..=8).contains(&subtag.len()) && subtag.chars().all(|ch| ch.is_ascii_alphanumeric())
+    });
+    if valid_language && valid_rest {
+        Ok(locale)
+    } else {
+        Err(format!("无效的语言区域: {locale}"))
+    }
+}
+
+/// 路径作为独立参数出现时，按实际类型替换为 `--folder-uri <uri>` 或 `--file-uri <uri>`，
+/// 避免编辑器把目录误当作要新建的文件；路径不存在、是工作区文件或编辑器不支持时保持原样
+/// （VS Code 只有直接传入 `.code-workspace` 才会按工作区打开）。
+fn apply_path_kind_flag(arguments: &mut Vec<String>, path: &str, capabilities: EditorCapabilities) {
+    let path_ref = Path::new(path);
+    let is_workspace_file = path_ref
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("code-workspace"));
+    let flag = if is_workspace_file {
+        None
+    } else if path_ref.is_dir() {
+        capabilities.folder_flag
+    } else if path_ref.is_file() {
+        capabilities.file_flag
+    } else {
+        None
+    };
+    let Some(flag) = flag else {
+        return;
+    };
+    if let Some(index) = arguments.iter().position(|argument| argument == path) {
+        arguments.splice(index..=index, [flag.to_string(), file_uri(path)]);
+    }
+}
+
+// 把本地路径转换为 file URI，例如 `C:\a b` → `file:///C:/a%20b`，
+// UNC 路径 `\\server\share\a` → `file://server/share/a`。
+fn file_uri(path: &str) -> String {
+    let path = file_uri_path(path);
+    if path.starts_with("//") {
+        format!("file:{path}")
+    } else {
+        format!("file://{path}")
+    }
+}
+
+// 编码后的 URI 路径：本地路径为 `/C:/a` 或 `/home/a`，UNC 路径为 `//server/share/a`
+// （服务器名作为主机）；`\\?\` 长路径前缀会被去掉。
+fn file_uri_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let path = match path.strip_prefix("//?/") {
+        Some(rest) => match rest.strip_prefix("UNC/") {
+            Some(unc) => format!("//{unc}"),
+            None => rest.to_string(),
+        },
+        None => path,
+    };
+    let encoded = percent_encode(path.as_bytes(), b"-._~/:");
+    if path.starts_with('/') {
+        encoded
+    } else {
+        format!("/{encoded}")
+    }
+}
+
+// 百分号编码，字母数字与 `safe` 中的字节保持原样。
+fn percent_encode(bytes: &[u8], safe: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        if byte.is_ascii_alphanumeric() || safe.contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+// 编辑器参数放在最前面；经 `open` 启动时需要放到 `--args` 之后才会转交给应用，
+// 且只在应用尚未运行时生效。
+fn insert_editor_flags(command_path: &str, arguments: &mut Vec<String>, flags: &[String]) {
+    if flags.is_empty() {
+        return;
+    }
+    if is_macos_open_launcher(command_path) {
+        if !arguments.iter().any(|argument| argument == "--args") {
+            arguments.push("--args".to_string());
+        }
+        arguments.extend(flags.iter().cloned());
+    } else {
+        arguments.splice(0..0, flags.iter().cloned());
+    }
+}
+
+// 额外参数放在最后，展开其中的 `{path}`；经 `open` 启动时同样需要放到 `--args` 之后。
+fn append_extra_arguments(
+    command_path: &str,
+    arguments: &mut Vec<String>,
+    extra_arguments: Option<&[String]>,
+    path: &str,
+) {
+    let Some(extra_arguments) = extra_arguments.filter(|extra| !extra.is_empty()) else {
+        return;
+    };
+    if is_macos_open_launcher(command_path)
+        && !arguments.iter().any(|argument| argument == "--args")
+    {
+        arguments.push("--args".to_string());
+    }
+    arguments.extend(
+        extra_arguments
+            .iter()
+            .map(|argument| expand_path_placeholder(argument, path).0),
+    );
+}
+
+fn is_macos_open_launcher(command_path: &str) -> bool {
+    cfg!(target_os = "macos") && Path::new(command_path) == Path::new("/usr/bin/open")
+}
+
+// 生成定位文件所需的参数，追加在项目路径之后。
+// JetBrains 的 `--line`、`--column` 只作用于紧随其后的文件，因此顺序为
+// `<project> --line N [--column M] <file>`；VS Code 通过 `-g file:line[:col]` 跳转。
+// 列号只在指定了行号时生效。
+fn build_editor_file_arguments(
+    family: EditorFamily,
+    file: &str,
+    line: Option<u32>,
+    column: Option<u32>,
+) -> Vec<String> {
+    match (family, line) {
+        (EditorFamily::JetBrains, Some(line)) => {
+            let mut arguments = vec!["--line".to_string(), line.to_string()];
+            if let Some(column) = column {
+                arguments.extend(["--column".to_string(), column.to_string()]);
+            }
+            arguments.push(file.to_string());
+            arguments
+        }
+        (EditorFamily::VsCode, Some(line)) => {
+            let location = match column {
+                Some(column) => format!("{file}:{line}:{column}"),
+                None => format!("{file}:{line}"),
+            };
+            vec!["-g".to_string(), location]
+        }
+        _ => vec![file.to_string()],
+    }
+}
+
+/// 把跳转写法解析为统一的 [`GotoSpec`]，支持：
+/// - VS Code 的 `file:line[:col]`，可带 `-g` / `--goto` 前缀；
+/// - JetBrains 的 `--line N [--column M] file`；
+/// - 不带位置的普通路径。
+///
+/// 只把末尾的纯数字段当作行列号，因此 Windows 盘符（`C:\a.rs:3`）中的冒号不受影响；
+/// 去掉数字段后只剩单个盘符字母时（`C:12`）按原样视为路径。
+pub fn parse_goto_argument(arg: &str) -> GotoSpec {
+    let mut rest = arg.trim();
+    let mut flag_line = None;
+    let mut flag_column = None;
+    loop {
+        if let Some(value) = rest
+            .strip_prefix("-g ")
+            .or_else(|| rest.strip_prefix("--goto "))
+        {
+            rest = value.trim_start();
+            continue;
+        }
+        let (flag, value) = match rest.split_once(char::is_whitespace) {
+            Some((flag @ ("--line" | "--column"), value)) => (flag, value.trim_start()),
+            _ => break,
+        };
+        let (number, remaining) = value.split_once(char::is_whitespace).unwrap_or((value, ""));
+        let Ok(number) = number.parse::<u32>() else {
+            break;
+        };
+        if flag == "--line" {
+            flag_line = Some(number);
+        } else {
+            flag_column = Some(number);
+        }
+        rest = remaining.trim_start();
+    }
+    if flag_line.is_some() || flag_column.is_some() {
+        return GotoSpec {
+            path: rest.to_string(),
+            line: flag_line,
+            column: flag_column,
+        };
+    }
+
+    let mut path = rest;
+    let mut numbers = Vec::new();
+    while numbers.len() < 2 {
+        let Some((head, tail)) = path.rsplit_once(':') else {
+            break;
+        };
+        let Ok(number) = tail.parse::<u32>() else {
+            break;
+        };
+        let is_bare_drive = head.len() == 1 && head.chars().all(|ch| ch.is_ascii_alphabetic());
+        if head.is_empty() || is_bare_drive {
+            break;
+        }
+        numbers.push(number);
+        path = head;
+    }
+    // 从右往左取出，`file:line:col` 得到 [col, line]。
+    numbers.reverse();
+    GotoSpec {
+        path: path.to_string(),
+        line: numbers.first().copied(),
+        column: numbers.get(1).copied(),
+    }
+}
+
+// 仅放行 http/https 链接，避免把 file:、javascript: 等任意协议交给系统打开。
+fn validate_browser_url(url: &str) -> Result<&str, String> {
+    let url = url.trim();
+    let (scheme, rest) = url
+        .split_once("://")
+        .ok_or_else(|| format!("无效的链接: {url}"))?;
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return Err(format!("不支持的链接协议: {scheme}"));
+    }
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if host.is_empty() || url.chars().any(|ch| ch.is_whitespace() || ch.is_control()) {
+        return Err(format!("无效的链接: {url}"));
+    }
+    Ok(url)
+}
+
+// 拼出 code-server 打开目录的地址：`<base-url>/?folder=<编码后的路径>`。
+fn code_server_url(base_url: &str, folder: &str) -> Result<String, String> {
+    let base_url = validate_browser_url(base_url)?.trim_end_matches('/');
+    if base_url.contains(['?', '#']) {
+        return Err(format!("code-server 地址不能包含查询参数: {base_url}"));
+    }
+    Ok(format!(
+        "{base_url}/?folder={}",
+        percent_encode(folder.as_bytes(), b"-._~/")
+    ))
+}
+
+fn build_command_arguments(arguments: Option<Vec<String>>, path: &str) -> Vec<String> {
+    let mut resolved = Vec::new();
+    let mut inserted_path = false;
+
+    if let Some(arguments) = arguments {
+        for argument in arguments {
+            let (expanded, substituted) = expand_path_placeholder(&argument, path);
+            resolved.push(expanded);
+            inserted_path |= substituted;
+        }
+    }
+
+    if !inserted_path {
+        resolved.push(path.to_string());
+    }
+
+    resolved
+}
+
+// 把参数中的 `{path}` 替换为路径，`{{path}}` 转义为字面量 `{path}`；
+// 返回替换后的参数以及是否发生了替换。
+fn expand_path_placeholder(argument: &str, path: &str) -> (String, bool) {
+    const PLACEHOLDER: &str = "{path}";
+    const ESCAPED: &str = "{{path}}";
+    let mut expanded = String::with_capacity(argument.len());
+    let mut substituted = false;
+    let mut rest = argument;
+    while let Some(index) = rest.find('{') {
+        expanded.push_str(&rest[..index]);
+        rest = &rest[index..];
+        if let Some(after) = rest.strip_prefix(ESCAPED) {
+            expanded.push_str(PLACEHOLDER);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix(PLACEHOLDER) {
+            expanded.push_str(path);
+            substituted = true;
+            rest = after;
+        } else {
+            expanded.push('{');
+            rest = &rest[1..];
+        }
+    }
+    expanded.push_str(rest);
+    (expanded, substituted)
+}
+
+// 多路径版本的参数展开：包含 {path} 的参数按路径逐个重复，未引用时把所有路径追加到末尾。
+fn build_multi_path_arguments(arguments: &[String], paths: &[String]) -> Vec<String> {
+    let mut resolved = Vec::new();
+    let mut inserted_path = false;
+
+    for argument in arguments {
+        let (literal, has_placeholder) = expand_path_placeholder(argument, "");
+        if has_placeholder {
+            resolved.extend(
+                paths
+                    .iter()
+                    .map(|path| expand_path_placeholder(argument, path).0),
+            );
+            inserted_path = true;
+        } else {
+            resolved.push(literal);
+        }
+    }
+
+    if !inserted_path {
+        resolved.extend(paths.iter().cloned());
+    }
+
+    resolved
+}
+
+/// 启动前改写参数的钩子，例如注入代理设置。
+pub type ArgumentHook = Box<dyn Fn(&DevToolPreset, &mut Vec<String>) + Send + Sync>;
+
+static ARGUMENT_HOOKS: OnceLock<Mutex<Vec<ArgumentHook>>> = OnceLock::new();
+
+fn argument_hooks() -> MutexGuard<'static, Vec<ArgumentHook>> {
+    ARGUMENT_HOOKS
+        .get_or_init(|| Mutex::new(Vec::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| {
+            log::warn!("参数钩子注册表锁异常，继续使用现有数据");
+            poisoned.into_inner()
+        })
+}
+
+/// 注册参数改写钩子，在启动编辑器等外部命令前执行。
+/// 钩子收到的预设描述本次启动：`id` 为预设 ID（自定义命令为空），`command_path` 为
+/// 实际执行的命令，`arguments` 为改写前的参数，`name` 留空。
+/// 多个钩子按注册顺序依次执行，后注册的钩子看到的是前面钩子改写后的参数。
+/// 钩子执行期间持有注册表锁，不能在钩子内再注册新的钩子。
+pub fn register_argument_hook(hook: ArgumentHook) {
+    argument_hooks().push(hook);
+}
+
+fn apply_argument_hooks(
+    hooks: &[ArgumentHook],
+    preset: &DevToolPreset,
+    arguments: &mut Vec<String>,
+) {
+    for hook in hooks {
+        hook(preset, arguments);
+    }
+}
+
+fn run_command_with_shell_support(
+    command_path: &str,
+    arguments: &[String],
+    options: &LaunchOptions,
+    spawn_error_prefix: &str,
+    failure_message: &str,
+) -> Result<OpenOutcome, String> {
+    let arguments = hooked_arguments(command_path, arguments, options);
+    let result = spawn_command_with_shell_support(command_path, &arguments, options)
+        .map_err(|err| format!("{spawn_error_prefix} {err}"))
+        .and_then(|(status, outcome)| {
+            if exit_status_accepted(status, options.success_exit_codes.as_deref()) {
+                Ok(outcome)
+            } else {
+                Err(failure_message.to_string())
+            }
+        });
+    record_launch_result(command_path, options, &result);
+    result
+}
+
+// 与 `run_command_with_shell_support` 相同地应用参数钩子并写入启动审计日志，
+// 但在后台启动命令，不等待其退出。
+fn spawn_detached_with_shell_support(
+    command_path: &str,
+    arguments: &[String],
+    options: &LaunchOptions,
+    spawn_error_prefix: &str,
+) -> Result<OpenOutcome, String> {
+    let arguments = hooked_arguments(command_path, arguments, options);
+    let result = launch_with_shell_support(command_path, &arguments, options, spawn_detached)
+        .map(|((), outcome)| outcome)
+        .map_err(|err| format!("{spawn_error_prefix} {err}"));
+    record_launch_result(command_path, options, &result);
+    result
+}
+
+// 按注册顺序对即将执行的参数应用钩子，没有注册钩子时原样返回。
+fn hooked_arguments(
+    command_path: &str,
+    arguments: &[String],
+    options: &LaunchOptions,
+) -> Vec<String> {
+    let mut arguments = arguments.to_vec();
+    let hooks = argument_hooks();
+    if !hooks.is_empty() {
+        let preset = DevToolPreset {
+            id: options.preset_id.clone().unwrap_or_default(),
+            name: String::new(),
+            command_path: command_path.to_string(),
+            arguments: arguments.clone(),
+            success_exit_codes: options.success_exit_codes.clone(),
+            kind: PresetKind::Command,
+            install_kind: InstallKind::default(),
+        };
+        apply_argument_hooks(&hooks, &preset, &mut arguments);
+    }
+    arguments
+}
+
+fn record_launch_result(
+    command_path: &str,
+    options: &LaunchOptions,
+    result: &Result<OpenOutcome, String>,
+) {
+    launch_audit::record_launch(&LaunchAuditEntry {
+        preset_id: options.preset_id.as_deref(),
+        path: options.target_path.as_deref(),
+        command: command_path,
+        strategy: result.as_ref().ok().map(|outcome| outcome.strategy),
+        error: result.as_ref().err().map(String::as_str),
+    });
+}
+
+// 退出码为 0，或在预设声明的成功退出码之中。
+fn exit_status_accepted(status: ExitStatus, success_exit_codes: Option<&[i32]>) -> bool {
+    status.success()
+        || status
+            .code()
+            .is_some_and(|code| success_exit_codes.unwrap_or_default().contains(&code))
+}
+
+fn spawn_command_with_shell_support(
+    command_path: &str,
+    arguments: &[String],
+    options: &LaunchOptions,
+) -> Result<(ExitStatus, OpenOutcome), std::io::Error> {
+    launch_with_shell_support(command_path, arguments, options, Command::status)
+}
+
+// 执行成功后记录实际的命令与参数。
+fn launch_described<T>(
+    strategy: LaunchStrategy,
+    command: &mut Command,
+    launch: &impl Fn(&mut Command) -> Result<T, std::io::Error>,
+) -> Result<(T, OpenOutcome), std::io::Error> {
+    let value = launch(command)?;
+    Ok((value, describe_command(strategy, command)))
+}
+
+fn describe_command(strategy: LaunchStrategy, command: &Command) -> OpenOutcome {
+    OpenOutcome {
+        strategy,
+        command: command.get_program().to_string_lossy().to_string(),
+        args: command
+            .get_args()
+            .map(|argument| argument.to_string_lossy().to_string())
+            .collect(),
+        warnings: Vec::new(),
+    }
+}
+
+// 执行前按启动选项设置子进程的工作目录与环境变量。
+fn with_working_dir<'a, T>(
+    options: &'a LaunchOptions,
+    launch: impl Fn(&mut Command) -> Result<T, std::io::Error> + 'a,
+) -> impl Fn(&mut Command) -> Result<T, std::io::Error> + 'a {
+    move |command| {
+        if let Some(dir) = options.working_dir.as_deref() {
+            command.current_dir(dir);
+        }
+        command.envs(options.env.iter().map(|(key, value)| (key, value)));
+        apply_priority(command, options.priority);
+        launch(command)
+    }
+}
+
+// 构造命令并交给 `launch` 执行（等待退出或后台启动），Windows 下会处理脚本类命令。
+#[cfg(target_os = "windows")]
+fn launch_with_shell_support<T>(
+    command_path: &str,
+    arguments: &[String],
+    options: &LaunchOptions,
+    launch: impl Fn(&mut Command) -> Result<T, std::io::Error>,
+) -> Result<(T, OpenOutcome), std::io::Error> {
+    // 登录 shell 仅对 Unix 有意义。
+    let _ = options.use_login_shell;
+    let launch = with_working_dir(options, launch);
+    let command_path = resolve_launch_command(command_path, options)?;
+    let command_path = command_path.as_str();
+    if let Some(kind) = resolve_windows_command_kind(command_path) {
+        return launch_described(
+            kind.strategy(),
+            &mut windows_command(kind, command_path, arguments),
+            &launch,
+        );
+    }
+
+    match launch_described(
+        LaunchStrategy::CommandPath,
+        Command::new(command_path).args(arguments),
+        &launch,
+    ) {
+        Ok(value) => Ok(value),
+        Err(error) => {
+            // 严格模式不猜测扩展名。
+            let fallback = if options.strict_path_lookup {
+                None
+            } else {
+                resolve_windows_command_fallback(command_path, &error)
+            };
+            if let Some((kind, fallback_path)) = fallback {
+                launch_described(
+                    kind.strategy(),
+                    &mut windows_command(kind, &fallback_path, arguments),
+                    &launch,
+                )
+            } else {
+                Err(error)
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn launch_with_shell_support<T>(
+    command_path: &str,
+    arguments: &[String],
+    options: &LaunchOptions,
+    launch: impl Fn(&mut Command) -> Result<T, std::io::Error>,
+) -> Result<(T, OpenOutcome), std::io::Error> {
+    let launch = with_working_dir(options, launch);
+    let command_path = resolve_launch_command(command_path, options)?;
+    let command_path = command_path.as_str();
+    if !options.use_login_shell {
+        return launch_described(
+            LaunchStrategy::CommandPath,
+            Command::new(command_path).args(arguments),
+            &launch,
+        );
+    }
+    let shell = std::env::var("SHELL")
+        .ok()
+        .filter(|shell| !shell.trim().is_empty())
+        .unwrap_or_else(|| "/bin/sh".to_string());
+    launch_described(
+        LaunchStrategy::LoginShell,
+        Command::new(shell)
+            .arg("-lc")
+            .arg(login_shell_script(command_path, arguments)),
+        &launch,
+    )
+}
+
+#[cfg(any(not(target_os = "windows"), test))]
+fn unix_niceness(priority: Priority) -> Option<libc::c_int> {
+    match priority {
+        Priority::Low => Some(10),
+        Priority::Normal => None,
+        Priority::High => Some(-5),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn apply_priority(command: &mut Command, priority: Priority) {
+    use std::os::unix::process::CommandExt;
+
+    let Some(niceness) = unix_niceness(priority) else {
+        return;
+    };
+    // setpriority 是异步信号安全的，可以在 fork 之后、exec 之前调用。
+    unsafe {
+        command.pre_exec(move || {
+            // 提高优先级需要特权，失败时保持默认优先级继续启动。
+            let _ = libc::setpriority(libc::PRIO_PROCESS, 0, niceness);
+            Ok(())
+        });
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn apply_priority(command: &mut Command, priority: Priority) {
+    use std::os::windows::process::CommandExt;
+
+    const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+    const ABOVE_NORMAL_PRIORITY_CLASS: u32 = 0x0000_8000;
+    match priority {
+        Priority::Low => {
+            command.creation_flags(BELOW_NORMAL_PRIORITY_CLASS);
+        }
+        Priority::Normal => {}
+        Priority::High => {
+            command.creation_flags(ABOVE_NORMAL_PRIORITY_CLASS);
+        }
+    }
+}
+
+// 严格模式下只接受绝对路径或裸命令名，后者通过严格的 PATH 查找解析为绝对路径。
+fn resolve_launch_command(
+    command_path: &str,
+    options: &LaunchOptions,
+) -> Result<String, std::io::Error> {
+    let path = Path::new(command_path);
+    if !options.strict_path_lookup || path.is_absolute() {
+        return Ok(command_path.to_string());
+    }
+    if path.components().count() != 1
+        || !matches!(path.components().next(), Some(Component::Normal(_)))
+    {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("严格模式下命令必须是绝对路径或命令名: {command_path}"),
+        ));
+    }
+    find_in_path_with(command_path, true).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("未在 PATH 中找到命令: {command_path}"),
+        )
+    })
+}
+
+// 用 exec 替换 shell 进程，使退出码与原命令一致。
+#[cfg(any(not(target_os = "windows"), test))]
+fn login_shell_script(command_path: &str, arguments: &[String]) -> String {
+    let mut script = format!("exec {}", quote_posix_argument(command_path));
+    for argument in arguments {
+        script.push(' ');
+        script.push_str(&quote_posix_argument(argument));
+    }
+    script
+}
+
+// POSIX shell 单引号内没有任何转义，内部的单引号需写成 `'\''`。
+fn quote_posix_argument(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// 命令预览的目标 shell。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Shell {
+    /// POSIX sh 及兼容 shell（bash、zsh）。
+    Posix,
+    Powershell,
+    /// Windows cmd.exe。
+    Cmd,
+}
+
+impl Shell {
+    // 无需加引号的字符；PowerShell 中 `@`、`,` 有特殊含义，cmd 中 `%`、`!` 会展开变量。
+    fn is_plain(self, value: &str) -> bool {
+        let extra = match self {
+            Self::Posix => "_-./:=@+,%",
+            Self::Powershell => "_-./:=+\\",
+            Self::Cmd => "_-./:=+,@\\",
+        };
+        !value.is_empty()
+            && value
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || extra.contains(ch))
+    }
+}
+
+/// 把命令与参数拼接为可直接粘贴到目标 shell 执行的命令行，仅为需要的部分加引号。
+pub fn format_command_line(program: &str, args: &[String], shell: Shell) -> String {
+    let mut line = if shell.is_plain(program) {
+        program.to_string()
+    } else {
+        match shell {
+            Shell::Posix => quote_posix_argument(program),
+            // 加引号的程序路径在 PowerShell 中只是字符串，需要调用运算符 `&` 才会执行。
+            Shell::Powershell => format!("& {}", quote_powershell_literal(program)),
+            // Windows 路径不会包含双引号。
+            Shell::Cmd => format!("\"{program}\""),
+        }
+    };
+    for arg in args {
+        line.push(' ');
+        if shell.is_plain(arg) {
+            line.push_str(arg);
+            continue;
+        }
+        line.push_str(&match shell {
+            Shell::Posix => quote_posix_argument(arg),
+            Shell::Powershell => quote_powershell_literal(arg),
+            Shell::Cmd => quote_cmd_argument(arg),
+        });
+    }
+    line
+}
+
+// 先按 CommandLineToArgvW 的规则加双引号（引号前的反斜杠需要翻倍），再为引号本身及
+// cmd 元字符加 `^`：cmd 不会进入引号状态，`&`、`%` 等都按字面传给程序。
+fn quote_cmd_argument(value: &str) -> String {
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for ch in value.chars() {
+        match ch {
+            '\\' => {
+                backslashes += 1;
+                continue;
+            }
+            '"' => quoted.push_str(&"\\".repeat(backslashes * 2 + 1)),
+            _ => quoted.push_str(&"\\".repeat(backslashes)),
+        }
+        quoted.push(ch);
+        backslashes = 0;
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+
+    let mut escaped = String::with_capacity(quoted.len() * 2);
+    for ch in quoted.chars() {
+        if "\"^&|<>()%!".contains(ch) {
+            escaped.push('^');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+#[cfg(target_os = "windows")]
+fn resolve_windows_command_kind(command_path: &str) -> Option<WindowsCommandKind> {
+    let extension = Path::new(command_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())?;
+    match extension.as_str() {
+        "cmd" | "bat" => Some(WindowsCommandKind::Cmd),
+        "ps1" => Some(WindowsCommandKind::PowerShell),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn resolve_windows_command_fallback(
+    command_path: &str,
+    error: &std::io::Error,
+) -> Option<(WindowsCommandKind, String)> {
+    if Path::new(command_path).extension().is_some() || !should_try_windows_fallback(error) {
+        return None;
+    }
+    let base_path = Path::new(command_path);
+    for (extension, kind) in [
+        ("cmd", WindowsCommandKind::Cmd),
+        ("bat", WindowsCommandKind::Cmd),
+        ("ps1", WindowsCommandKind::PowerShell),
+        ("exe", WindowsCommandKind::Direct),
+        ("com", WindowsCommandKind::Direct),
+    ] {
+        let candidate = base_path.with_extension(extension);
+        if candidate.is_file() {
+            return Some((kind, candidate.to_string_lossy().to_string()));
+        }
+    }
+    None
+}
+
+#[cfg(target_os = "windows")]
+fn should_try_windows_fallback(error: &std::io::Error) -> bool {
+    matches!(
+        error.raw_os_error(),
+        Some(2) | Some(3) | Some(193) | Some(216)
+    )
+}
+
+#[cfg(target_os = "windows")]
+#[derive(Clone, Copy)]
+enum WindowsCommandKind {
+    Direct,
+    Cmd,
+    PowerShell,
+}
+
+#[cfg(target_os = "windows")]
+impl WindowsCommandKind {
+    fn strategy(self) -> LaunchStrategy {
+        match self {
+            Self::Direct => LaunchStrategy::CommandPath,
+            Self::Cmd => LaunchStrategy::WindowsCmd,
+            Self::PowerShell => LaunchStrategy::WindowsPowerShell,
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn windows_command(kind: WindowsCommandKind, executable: &str, arguments: &[String]) -> Command {
+    match kind {
+        WindowsCommandKind::Direct => {
+            let mut command = Command::new(executable);
+            command.args(arguments);
+            command
+        }
+        WindowsCommandKind::Cmd => {
+            let mut command = Command::new("cmd.exe");
+            command.arg("/C").arg(executable).args(arguments);
+            command
+        }
+        WindowsCommandKind::PowerShell => {
+            let mut script = format!("& {}", quote_powershell_literal(executable));
+            for argument in arguments {
+                script.push(' ');
+                script.push_str(&quote_powershell_literal(argument));
+            }
+            powershell_command(&script, false)
+        }
+    }
+}
+
+/// 统一构造 PowerShell 命令，脚本以 `-EncodedCommand` 传递以避免命令行转义问题。
+#[cfg(target_os = "windows")]
+fn powershell_command(script: &str, no_exit: bool) -> Command {
+    let mut command = Command::new("powershell.exe");
+    command.args(["-NoProfile", "-ExecutionPolicy", "Bypass"]);
+    if no_exit {
+        command.arg("-NoExit");
+    }
+    command
+        .arg("-EncodedCommand")
+        .arg(encode_powershell_command(script));
+    command
+}
+
+/// 执行 PowerShell 脚本并等待其退出，参数见 [`powershell_command`]。
+#[cfg(target_os = "windows")]
+fn run_powershell(script: &str, no_exit: bool) -> Result<ExitStatus, std::io::Error> {
+    powershell_command(script, no_exit).status()
+}
+
+// 将值包装为 PowerShell 单引号字面量，内部单引号需要成对转义。
+fn quote_powershell_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+// PowerShell 的 -EncodedCommand 要求 UTF-16LE 字节的 Base64 编码。
+#[cfg(any(target_os = "windows", test))]
+fn encode_powershell_command(script: &str) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let bytes: Vec<u8> = script.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let buffer = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let value = u32::from(buffer[0]) << 16 | u32::from(buffer[1]) << 8 | u32::from(buffer[2]);
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let offset = 18 - index * 6;
+                encoded.push(ALPHABET[((value >> offset) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// 复制文本到系统剪贴板（跨平台）。
+/// `also_primary` 为真时在 Linux 上同时写入 PRIMARY 选区以便中键粘贴，其他平台忽略。
+pub fn copy_to_clipboard(app: &AppHandle, content: &str, also_primary: bool) -> Result<(), String> {
+    if let Err(err) = app.clipboard().write_text(content.to_string()) {
+        #[cfg(target_os = "macos")]
+        {
+            let _ = err;
+            return copy_with_pbcopy(content).map_err(|err| format!("写入剪贴板失败: {err}"));
+        }
+        #[cfg(not(target_os = "macos"))]
+        {
+            return Err(format!("写入剪贴板失败: {err}"));
+        }
+    }
+    #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+    if also_primary {
+        // PRIMARY 只是附加功能，写入失败不影响剪贴板本身的结果。
+        if let Err(err) = copy_to_primary_selection(content) {
+            log::warn!("写入 PRIMARY 选区失败: {}", err);
+        }
+    }
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    let _ = also_primary;
+    Ok(())
+}
+
+// Wayland 与 X11 的选区工具依次尝试，使用第一个存在的工具。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn copy_to_primary_selection(content: &str) -> Result<(), String> {
+    let writers: [(&str, &[&str]); 3] = [
+        ("wl-copy", &["--primary"]),
+        ("xclip", &["-selection", "primary"]),
+        ("xsel", &["--primary", "--input"]),
+    ];
+    for (program, arguments) in writers {
+        let Some(command_path) = find_in_path(program) else {
+            continue;
+        };
+        return write_to_stdin(Command::new(command_path).args(arguments), content)
+            .map_err(|err| format!("{program}: {err}"));
+    }
+    Err("未找到 wl-copy、xclip 或 xsel".to_string())
+}
+
+/// 复制多个路径时的拼接选项。
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct PathListOptions {
+    /// 路径之间的分隔符，缺省为换行。
+    pub separator: Option<String>,
+    /// 未指定分隔符时使用 CRLF 换行，便于粘贴到 Windows 的记事本或批处理脚本中。
+    pub crlf: Option<bool>,
+    /// 按当前平台的 shell 规则为每个路径加引号。
+    pub quote: Option<bool>,
+}
+
+/// 把多个路径拼接后复制到剪贴板。
+pub fn copy_paths(
+    app: &AppHandle,
+    paths: &[String],
+    options: &PathListOptions,
+) -> Result<(), String> {
+    copy_to_clipboard(app, &join_path_list(paths, options), false)
+}
+
+fn join_path_list(paths: &[String], options: &PathListOptions) -> String {
+    let default_separator = if options.crlf == Some(true) {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let separator = options.separator.as_deref().unwrap_or(default_separator);
+    paths
+        .iter()
+        .map(|path| {
+            if options.quote == Some(true) {
+                shell_quote_path(path)
+            } else {
+                path.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+// cmd 与 PowerShell 都接受双引号，而 Windows 路径不可能包含双引号。
+#[cfg(target_os = "windows")]
+fn shell_quote_path(path: &str) -> String {
+    format!("\"{path}\"")
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell_quote_path(path: &str) -> String {
+    quote_posix_argument(path)
+}
+
+/// 在文件管理器中定位路径，同时把其绝对路径复制到剪贴板。
+/// 结果只取决于定位是否成功，复制失败仅记录警告。
+pub fn reveal_and_copy(app: &AppHandle, path: &str) -> Result<(), String> {
+    let path = absolute_path_string(&normalize_input_path(path)?);
+    open_in_finder(&path, false)?;
+    if let Err(err) = copy_to_clipboard(app, &path, false) {
+        log::warn!("复制路径失败 {}: {}", path, err);
+    }
+    Ok(())
+}
+
+// 相对路径基于当前工作目录补全；不解析符号链接，避免 Windows 上出现 `\\?\` 前缀。
+fn absolute_path_string(path: &str) -> String {
+    std::path::absolute(path)
+        .map(|absolute| absolute.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+/// 读取路径的修改时间、创建时间以及是否为 Git 仓库，用于按最近使用排序。
+pub fn get_path_metadata(path: &str) -> Result<PathMeta, String> {
+    let path = normalize_input_path(path)?;
+    let path = Path::new(&path);
+    let metadata = fs::metadata(path).map_err(|err| format!("读取路径失败: {err}"))?;
+    Ok(PathMeta {
+        modified_at: metadata
+            .modified()
+            .ok()
+            .and_then(system_time_to_unix_millis),
+        created_at: metadata.created().ok().and_then(system_time_to_unix_millis),
+        is_git_repo: metadata.is_dir() && path.join(".git").exists(),
+    })
+}
+
+// 进行中的目录大小统计：调用方提供的令牌 → 取消标记。
+static DIRECTORY_SIZE_SCANS: OnceLock<Mutex<HashMap<String, Arc<AtomicBool>>>> = OnceLock::new();
+
+fn directory_size_scans() -> MutexGuard<'static, HashMap<String, Arc<AtomicBool>>> {
+    DIRECTORY_SIZE_SCANS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 统计目录下所有文件的总字节数。符号链接不跟随，无法读取的子目录被跳过；
+/// `skip_heavy_dirs` 为 true 时跳过 node_modules、target 与 .git。
+/// 超过 15 秒时放弃；提供 `token` 时可通过 [`cancel_directory_size`] 提前取消。
+pub fn get_directory_size(
+    path: &str,
+    skip_heavy_dirs: bool,
+    token: Option<&str>,
+) -> Result<u64, String> {
+    let path = normalize_input_path(path)?;
+    let cancelled = Arc::new(AtomicBool::new(false));
+    if let Some(token) = token {
+        directory_size_scans().insert(token.to_string(), Arc::clone(&cancelled));
+    }
+    let result = directory_size(
+        Path::new(&path),
+        skip_heavy_dirs,
+        Instant::now() + DIRECTORY_SIZE_TIMEOUT,
+        &cancelled,
+    );
+    if let Some(token) = token {
+        directory_size_scans().remove(token);
+    }
+    result
+}
+
+/// 取消以 `token` 发起的目录大小统计，统计已结束或令牌未知时返回 false。
+pub fn cancel_directory_size(token: &str) -> bool {
+    match directory_size_scans().get(token) {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+// 用显式栈逐层展开，避免极深的目录树导致栈溢出；超过 `deadline` 或被取消时放弃。
+fn directory_size(
+    root: &Path,
+    skip_heavy_dirs: bool,
+    deadline: Instant,
+    cancelled: &AtomicBool,
+) -> Result<u64, String> {
+    let metadata = fs::symlink_metadata(root).map_err(|err| format!("读取路径失败: {err}"))?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut total = 0u64;
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err("已取消统计目录大小".to_string());
+        }
+        if Instant::now() >= deadline {
+            return Err("统计目录大小超时".to_string());
+        }
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                log::debug!("跳过无法读取的目录 {}: {}", dir.display(), err);
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                let name = entry.file_name();
+                if skip_heavy_dirs && HEAVY_DIR_NAMES.iter().any(|heavy| name == *heavy) {
+                    continue;
+                }
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                total += entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            }
+        }
+    }
+    Ok(total)
+}
+
+// 查找最近修改的子项目时默认忽略的目录。
+const RECENT_SCAN_IGNORED: &[&str] = &["node_modules", "target", ".git", "dist", "build"];
+// 默认向子项目内查找的层数。
+const RECENT_SCAN_DEFAULT_DEPTH: usize = 3;
+
+/// 查找最近修改的子项目时的选项。
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct RecentSubprojectOptions {
+    /// 在每个子项目内向下查找的层数，缺省为 3，0 表示只看子项目根目录中的文件。
+    pub depth: Option<usize>,
+    /// 跳过的目录名，缺省为 node_modules、target、.git、dist、build。
+    pub ignore: Option<Vec<String>>,
+}
+
+/// 在 monorepo 的直接子目录中找出包含最近修改文件的一个并用预设打开，返回选中的路径，
+/// 便于“从上次中断的地方继续”。按文件的修改时间判断，隐藏目录与忽略列表中的目录不参与。
+pub fn open_recent_subproject(
+    root: &str,
+    preset: DevToolPreset,
+    options: &RecentSubprojectOptions,
+) -> Result<String, String> {
+    let root = normalize_input_path(root)?;
+    let subproject = find_recent_subproject(Path::new(&root), options)?;
+    let subproject = subproject.to_string_lossy().to_string();
+    open_in_editor(EditorOpenParams::from_preset(preset, &subproject))?;
+    Ok(subproject)
+}
+
+fn find_recent_subproject(
+    root: &Path,
+    options: &RecentSubprojectOptions,
+) -> Result<PathBuf, String> {
+    let ignore: Vec<&str> = match options.ignore.as_deref() {
+        Some(ignore) => ignore.iter().map(String::as_str).collect(),
+        None => RECENT_SCAN_IGNORED.to_vec(),
+    };
+    let depth = options.depth.unwrap_or(RECENT_SCAN_DEFAULT_DEPTH);
+    let entries = fs::read_dir(root).map_err(|err| format!("读取目录失败: {err}"))?;
+    entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            !name.starts_with('.') && !ignore.contains(&name.as_str())
+        })
+        .filter_map(|entry| {
+            let modified = latest_file_modification(&entry.path(), depth, &ignore)?;
+            Some((modified, entry.path()))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+        .ok_or_else(|| "未找到包含文件的子项目".to_string())
+}
+
+// 逐层展开到 `depth` 层，跳过符号链接与忽略的目录，返回其中文件最新的修改时间。
+fn latest_file_modification(
+    dir: &Path,
+    depth: usize,
+    ignore: &[&str],
+) -> Option<std::time::SystemTime> {
+    let mut latest = None;
+    let mut pending = vec![(dir.to_path_buf(), 0)];
+    while let Some((dir, level)) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                let name = entry.file_name();
+                if level < depth && !ignore.iter().any(|ignored| name == *ignored) {
+                    pending.push((entry.path(), level + 1));
+                }
+            } else if file_type.is_file() {
+                let modified = entry.metadata().and_then(|metadata| metadata.modified());
+                if let Ok(modified) = modified {
+                    latest = latest.max(Some(modified));
+                }
+            }
+        }
+    }
+    latest
+}
+
+/// 返回路径所在卷上当前用户可用的字节数；路径尚不存在时按最近的已存在上级目录计算。
+pub fn get_free_space(path: &str) -> Result<u64, String> {
+    let path = absolute_path_string(&normalize_input_path(path)?);
+    let existing =
+        existing_ancestor(Path::new(&path)).ok_or_else(|| format!("路径不存在: {path}"))?;
+    available_space(existing).map_err(|err| format!("读取磁盘空间失败: {err}"))
+}
+
+fn existing_ancestor(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|ancestor| ancestor.exists())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn available_space(path: &Path) -> std::io::Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).map_err(std::io::Error::other)?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // 各平台 statvfs 字段的整数宽度不同。
+    #[allow(clippy::unnecessary_cast)]
+    let available = stat.f_bavail as u64 * stat.f_frsize as u64;
+    Ok(available)
+}
+
+#[cfg(target_os = "windows")]
+fn available_space(path: &Path) -> std::io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory_name: *const u16,
+            free_bytes_available_to_caller: *mut u64,
+            total_number_of_bytes: *mut u64,
+            total_number_of_free_bytes: *mut u64,
+        ) -> i32;
+    }
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut available = 0u64;
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(available)
+}
+
+/// 列出已挂载的卷：macOS 为 `/Volumes` 下的条目，Windows 为各逻辑驱动器，
+/// Linux 为 `/proc/mounts` 中的实际文件系统（排除 proc、sysfs、tmpfs 等伪文件系统）。
+pub fn list_volumes() -> Vec<VolumeInfo> {
+    #[cfg(target_os = "macos")]
+    let mounts: Vec<(String, PathBuf)> = fs::read_dir("/Volumes")
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| {
+                    (
+                        entry.file_name().to_string_lossy().to_string(),
+                        entry.path(),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    #[cfg(target_os = "windows")]
+    let mounts: Vec<(String, PathBuf)> = logical_drives()
+        .into_iter()
+        .map(|drive| {
+            (
+                drive.trim_end_matches('\\').to_string(),
+                PathBuf::from(drive),
+            )
+        })
+        .collect();
+    #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+    let mounts: Vec<(String, PathBuf)> = fs::read_to_string("/proc/mounts")
+        .map(|content| parse_proc_mounts(&content))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|mount_path| {
+            let name = Path::new(&mount_path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| mount_path.clone());
+            (name, PathBuf::from(mount_path))
+        })
+        .collect();
+
+    mounts
+        .into_iter()
+        .map(|(name, mount_path)| VolumeInfo {
+            name,
+            free_bytes: available_space(&mount_path).ok(),
+            mount_path: mount_path.to_string_lossy().to_string(),
+        })
+        .collect()
+}
+
+// 不对应实际存储的伪文件系统。
+#[cfg(any(all(not(target_os = "macos"), not(target_os = "windows")), test))]
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "ramfs",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "squashfs",
+    "sysfs",
+    "tmpfs",
+    "tracefs",
+];
+
+// `/proc/mounts` 每行为 `设备 挂载点 类型 选项 0 0`，挂载点中的空白等字符以 `\040` 形式的八进制转义。
+// 除伪文件系统外，还跳过系统目录下的挂载（如 `/run/user/1000/gvfs`、snap 的 squashfs），
+// 但保留 `/run/media` 下的可移动介质；同一挂载点重复挂载时只保留一次。
+#[cfg(any(all(not(target_os = "macos"), not(target_os = "windows")), test))]
+fn parse_proc_mounts(content: &str) -> Vec<String> {
+    let mut mounts: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(_), Some(mount_path), Some(fs_type)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if PSEUDO_FILESYSTEMS.contains(&fs_type) || fs_type.starts_with("fuse.gvfs") {
+            continue;
+        }
+        let mount_path = unescape_mount_path(mount_path);
+        let system_dir = ["/proc", "/sys", "/dev", "/run", "/snap", "/boot/efi"]
+            .iter()
+            .any(|dir| Path::new(&mount_path).starts_with(dir));
+        if system_dir && !Path::new(&mount_path).starts_with("/run/media") {
+            continue;
+        }
+        if !mounts.contains(&mount_path) {
+            mounts.push(mount_path);
+        }
+    }
+    mounts
+}
+
+#[cfg(any(all(not(target_os = "macos"), not(target_os = "windows")), test))]
+fn unescape_mount_path(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let octal = bytes
+            .get(index + 1..index + 4)
+            .filter(|_| bytes[index] == b'\\')
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match octal {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 4;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(target_os = "windows")]
+fn logical_drives() -> Vec<String> {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetLogicalDriveStringsW(buffer_length: u32, buffer: *mut u16) -> u32;
+    }
+
+    // 26 个驱动器各占 `X:\` 加结尾的 NUL，再加列表末尾的 NUL。
+    let mut buffer = [0u16; 26 * 4 + 1];
+    let length = unsafe { GetLogicalDriveStringsW(buffer.len() as u32, buffer.as_mut_ptr()) };
+    if length == 0 || length as usize > buffer.len() {
+        return Vec::new();
+    }
+    parse_drive_strings(&buffer[..length as usize])
+}
+
+// GetLogicalDriveStringsW 返回以 NUL 分隔的 `C:\` 列表。
+#[cfg(any(target_os = "windows", test))]
+fn parse_drive_strings(buffer: &[u16]) -> Vec<String> {
+    buffer
+        .split(|unit| *unit == 0)
+        .filter(|drive| !drive.is_empty())
+        .map(String::from_utf16_lossy)
+        .collect()
+}
+
+/// 判断路径是否位于云盘同步目录中，同步目录中的项目常导致编辑器的文件监听异常。
+/// 按路径前缀启发式判断，未识别时返回 None：
+/// - iCloud：`~/Library/Mobile Documents` 与 `~/Library/CloudStorage/iCloud*`；
+/// - OneDrive：`%OneDrive%` 等环境变量指向的目录与 `~/Library/CloudStorage/OneDrive*`；
+/// - Dropbox：`info.json` 中记录的个人与企业目录，读取不到时为 `~/Dropbox`。
+pub fn detect_cloud_sync(path: &str) -> Option<CloudProvider> {
+    let path = absolute_path_string(&normalize_input_path(path).ok()?);
+    cloud_provider_for(Path::new(&path), &cloud_sync_roots())
+}
+
+fn cloud_provider_for(path: &Path, roots: &[(CloudProvider, PathBuf)]) -> Option<CloudProvider> {
+    roots
+        .iter()
+        .find(|(_, root)| path.starts_with(root))
+        .map(|(provider, _)| *provider)
+}
+
+fn cloud_sync_roots() -> Vec<(CloudProvider, PathBuf)> {
+    let non_empty = |key: &str| std::env::var_os(key).filter(|value| !value.is_empty());
+    let home = non_empty("HOME")
+        .or_else(|| non_empty("USERPROFILE"))
+        .map(PathBuf::from);
+    let mut roots = Vec::new();
+    if let Some(home) = home.as_deref() {
+        if cfg!(target_os = "macos") {
+            roots.push((CloudProvider::ICloud, home.join("Library/Mobile Documents")));
+            // 新版 OneDrive、Dropbox 与部分 iCloud 目录通过 File Provider 挂载在 CloudStorage 下。
+            let cloud_storage = home.join("Library/CloudStorage");
+            for entry in fs::read_dir(&cloud_storage).into_iter().flatten().flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let provider = if name.starts_with("iCloud") {
+                    CloudProvider::ICloud
+                } else if name.starts_with("OneDrive") {
+                    CloudProvider::OneDrive
+                } else if name.starts_with("Dropbox") {
+                    CloudProvider::Dropbox
+                } else {
+                    continue;
+                };
+                roots.push((provider, entry.path()));
+            }
+        }
+    }
+    for key in ["OneDrive", "OneDriveCommercial", "OneDriveConsumer"] {
+        if let Some(dir) = non_empty(key) {
+            roots.push((CloudProvider::OneDrive, PathBuf::from(dir)));
+        }
+    }
+    // Dropbox 把同步目录记录在 info.json 中，Windows 位于 %APPDATA% 或 %LOCALAPPDATA%。
+    let dropbox_info = [
+        home.as_ref().map(|home| home.join(".dropbox")),
+        non_empty("APPDATA").map(|dir| PathBuf::from(dir).join("Dropbox")),
+        non_empty("LOCALAPPDATA").map(|dir| PathBuf::from(dir).join("Dropbox")),
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|dir| fs::read_to_string(dir.join("info.json")).ok());
+    match dropbox_info.map(|content| parse_dropbox_info(&content)) {
+        Some(dirs) if !dirs.is_empty() => {
+            roots.extend(dirs.into_iter().map(|dir| (CloudProvider::Dropbox, dir)));
+        }
+        _ => roots.extend(
+            home.as_ref()
+                .map(|home| (CloudProvider::Dropbox, home.join("Dropbox"))),
+        ),
+    }
+    roots
+}
+
+// info.json 形如 `{"personal": {"path": "/Users/me/Dropbox"}, "business": {"path": "…"}}`。
+fn parse_dropbox_info(content: &str) -> Vec<PathBuf> {
+    let Ok(info) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    let Some(accounts) = info.as_object() else {
+        return Vec::new();
+    };
+    accounts
+        .values()
+        .filter_map(|account| account.get("path")?.as_str())
+        .filter(|path| !path.trim().is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// 把文件或目录移到系统回收站（废纸篓），可在系统中恢复。
+pub fn move_to_trash(path: &str) -> Result<(), String> {
+    let path = absolute_path_string(&normalize_input_path(path)?);
+    fs::symlink_metadata(&path).map_err(|err| format!("路径不存在: {err}"))?;
+
+    #[cfg(target_os = "macos")]
+    let result = move_to_macos_trash(&path);
+    #[cfg(target_os = "windows")]
+    let result = move_to_recycle_bin(&path);
+    #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+    let result = move_to_freedesktop_trash(Path::new(&path));
+    result
+}
+
+#[cfg(target_os = "macos")]
+fn move_to_macos_trash(path: &str) -> Result<(), String> {
+    let script = format!(
+        "tell application \"Finder\" to delete POSIX file \"{}\"",
+        escape_applescript_string(path)
+    );
+    run_osascript(&script, "无法移到废纸篓:", "移到废纸篓失败").map(|_| ())
+}
+
+// 通过 Shell 的 SHFileOperationW 删除并允许撤销，即移入回收站；不弹出确认、进度或错误对话框。
+#[cfg(target_os = "windows")]
+fn move_to_recycle_bin(path: &str) -> Result<(), String> {
+    use std::os::windows::ffi::OsStrExt;
+
+    // 32 位 Windows 的 shellapi.h 按 1 字节对齐该结构。
+    #[repr(C)]
+    #[cfg_attr(target_pointer_width = "32", repr(packed(1)))]
+    struct ShFileOpStructW {
+        hwnd: *mut std::ffi::c_void,
+        func: u32,
+        from: *const u16,
+        to: *const u16,
+        flags: u16,
+        any_operations_aborted: i32,
+        name_mappings: *mut std::ffi::c_void,
+        progress_title: *const u16,
+    }
+
+    #[link(name = "shell32")]
+    extern "system" {
+        fn SHFileOperationW(operation: *mut ShFileOpStructW) -> i32;
+    }
+
+    const FO_DELETE: u32 = 0x3;
+    const FOF_SILENT: u16 = 0x4;
+    const FOF_NOCONFIRMATION: u16 = 0x10;
+    const FOF_ALLOWUNDO: u16 = 0x40;
+    const FOF_NOERRORUI: u16 = 0x400;
+
+    // pFrom 是以两个 NUL 结尾的路径列表。
+    let from: Vec<u16> = std::ffi::OsStr::new(path)
+        .encode_wide()
+        .chain([0, 0])
+        .collect();
+    let mut operation = ShFileOpStructW {
+        hwnd: std::ptr::null_mut(),
+        func: FO_DELETE,
+        from: from.as_ptr(),
+        to: std::ptr::null(),
+        flags: FOF_ALLOWUNDO | FOF_NOCONFIRMATION | FOF_NOERRORUI | FOF_SILENT,
+        any_operations_aborted: 0,
+        name_mappings: std::ptr::null_mut(),
+        progress_title: std::ptr::null(),
+    };
+    let code = unsafe { SHFileOperationW(&mut operation) };
+    if code != 0 {
+        return Err(format!("移到回收站失败: 错误码 {code:#x}"));
+    }
+    if operation.any_operations_aborted != 0 {
+        return Err("移到回收站的操作已中止".to_string());
+    }
+    Ok(())
+}
+
+// 按 freedesktop 回收站规范处理：同一文件系统时移入用户主回收站，
+// 跨文件系统时移入所在挂载点下的 `.Trash-<uid>`。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn move_to_freedesktop_trash(path: &Path) -> Result<(), String> {
+    let home_trash = home_trash_dir().ok_or("无法确定回收站目录")?;
+    let result = match trash_into(path, &home_trash, None) {
+        Err(err) if err.raw_os_error() == Some(libc::EXDEV) => {
+            let topdir = mount_point(path).map_err(|err| format!("无法确定挂载点: {err}"))?;
+            // SAFETY: getuid 总是成功且没有副作用。
+            let uid = unsafe { libc::getuid() };
+            let trash = topdir.join(format!(".Trash-{uid}"));
+            trash_into(path, &trash, Some(&topdir))
+        }
+        result => result,
+    };
+    result.map_err(|err| format!("移到回收站失败: {err}"))
+}
+
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn home_trash_dir() -> Option<PathBuf> {
+    xdg_data_dir().map(|dir| dir.join("Trash"))
+}
+
+// 向上查找与目标处于同一设备的最高层目录，即目标所在的挂载点。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn mount_point(path: &Path) -> std::io::Result<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut current = path.parent().unwrap_or(path);
+    let device = fs::metadata(current)?.dev();
+    while let Some(parent) = current.parent() {
+        if fs::metadata(parent)?.dev() != device {
+            break;
+        }
+        current = parent;
+    }
+    Ok(current.to_path_buf())
+}
+
+// 先以独占方式创建 `.trashinfo`，占住名称后再移动文件；重名时依次尝试 `name.2`、`name.3`。
+// `topdir` 不为空时 Path 记录相对挂载点的路径。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn trash_into(path: &Path, trash_dir: &Path, topdir: Option<&Path>) -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::DirBuilderExt;
+
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    for dir in [&files_dir, &info_dir] {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+    }
+    let name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::other("无法移动根目录"))?
+        .to_string_lossy()
+        .to_string();
+    let original = topdir
+        .and_then(|topdir| path.strip_prefix(topdir).ok())
+        .unwrap_or(path);
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode(original.as_os_str().as_bytes(), b"-._~/"),
+        chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+    );
+
+    for attempt in 1.. {
+        let entry_name = if attempt == 1 {
+            name.clone()
+        } else {
+            format!("{name}.{attempt}")
+        };
+        let info_path = info_dir.join(format!("{entry_name}.trashinfo"));
+        let mut info_file = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        };
+        let target = files_dir.join(&entry_name);
+        if fs::symlink_metadata(&target).is_ok() {
+            drop(info_file);
+            let _ = fs::remove_file(&info_path);
+            continue;
+        }
+        if let Err(err) = info_file
+            .write_all(info.as_bytes())
+            .and_then(|()| fs::rename(path, &target))
+        {
+            let _ = fs::remove_file(&info_path);
+            return Err(err);
+        }
+        return Ok(());
+    }
+    unreachable!("回收站条目名称已耗尽")
+}
+
+/// 读取剪贴板中的文件列表（例如在 Finder/资源管理器中复制的文件）。
+/// 系统剪贴板没有文件引用时，退回到把文本中的每一行视为路径，仅保留实际存在的路径。
+pub fn read_clipboard_paths(app: &AppHandle) -> Result<Vec<String>, String> {
+    match read_native_clipboard_paths() {
+        Ok(paths) if !paths.is_empty() => return Ok(paths),
+        Ok(_) => {}
+        Err(err) => log::warn!("读取剪贴板文件列表失败: {}", err),
+    }
+    let text = app
+        .clipboard()
+        .read_text()
+        .map_err(|err| format!("读取剪贴板失败: {err}"))?;
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && Path::new(line).exists())
+        .map(str::to_string)
+        .collect())
+}
+
+// macOS 通过 JXA 读取 NSFilenamesPboardType。
+#[cfg(target_os = "macos")]
+fn read_native_clipboard_paths() -> Result<Vec<String>, String> {
+    let script = "ObjC.import('AppKit');\n\
+        const list = $.NSPasteboard.generalPasteboard.propertyListForType('NSFilenamesPboardType');\n\
+        (ObjC.deepUnwrap(list) || []).join('\\n');";
+    let output = Command::new("/usr/bin/osascript")
+        .args(["-l", "JavaScript", "-e", script])
+        .output()
+        .map_err(|err| format!("无法读取剪贴板: {err}"))?;
+    if !output.status.success() {
+        return Err(
+            describe_osascript_error(&String::from_utf8_lossy(&output.stderr))
+                .unwrap_or_else(|| "无法读取剪贴板".to_string()),
+        );
+    }
+    Ok(non_empty_lines(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// Windows 的 CF_HDROP 通过 PowerShell 的 FileDropList 读取，输出强制使用 UTF-8。
+#[cfg(target_os = "windows")]
+fn read_native_clipboard_paths() -> Result<Vec<String>, String> {
+    let script = "[Console]::OutputEncoding = [System.Text.Encoding]::UTF8; \
+        Get-Clipboard -Format FileDropList | ForEach-Object { $_.FullName }";
+    let output = powershell_command(script, false)
+        .output()
+        .map_err(|err| format!("无法读取剪贴板: {err}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(non_empty_lines(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// Linux 文件管理器以 text/uri-list 形式提供复制的文件，Wayland 与 X11 分别尝试。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn read_native_clipboard_paths() -> Result<Vec<String>, String> {
+    let readers: [(&str, &[&str]); 2] = [
+        ("wl-paste", &["--no-newline", "--type", "text/uri-list"]),
+        (
+            "xclip",
+            &["-selection", "clipboard", "-t", "text/uri-list", "-o"],
+        ),
+    ];
+    for (program, arguments) in readers {
+        let Some(command_path) = find_in_path(program) else {
+            continue;
+        };
+        if let Ok(output) = Command::new(command_path).args(arguments).output() {
+            if output.status.success() {
+                return Ok(parse_uri_list(&String::from_utf8_lossy(&output.stdout)));
+            }
+        }
+    }
+    Ok(Vec::new())
+}
+
+fn non_empty_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// 解析 text/uri-list：忽略注释与非 file:// 的条目，并还原百分号编码。
+#[cfg(any(all(not(target_os = "macos"), not(target_os = "windows")), test))]
+fn parse_uri_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.strip_prefix("file://"))
+        .filter_map(|rest| {
+            // file://host/path 中的主机名只接受空或 localhost。
+            let (host, path) = rest.split_at(rest.find('/')?);
+            (host.is_empty() || host == "localhost").then(|| percent_decode(path))
+        })
+        .collect()
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' && index + 2 < bytes.len() {
+            let high = (bytes[index + 1] as char).to_digit(16);
+            let low = (bytes[index + 2] as char).to_digit(16);
+            if let (Some(high), Some(low)) = (high, low) {
+                decoded.push((high * 16 + low) as u8);
+                index += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(target_os = "macos")]
+fn copy_with_pbcopy(content: &str) -> Result<(), std::io::Error> {
+    write_to_stdin(&mut Command::new("/usr/bin/pbcopy"), content)
+}
+
+// 把内容写入命令的标准输入并等待其退出。
+#[cfg(not(target_os = "windows"))]
+fn write_to_stdin(command: &mut Command, content: &str) -> Result<(), std::io::Error> {
+    let mut child = command.stdin(Stdio::piped()).spawn()?;
+    if let Some(stdin) = child.stdin.as_mut() {
+        stdin.write_all(content.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other("写入剪贴板失败"))
+    }
+}
+
+// 使用系统默认方式打开路径。
+#[cfg(target_os = "macos")]
+fn open_with_default(path: &str) -> Result<OpenOutcome, String> {
+    let mut command = Command::new("/usr/bin/open");
+    command.arg(path);
+    let status = command
+        .status()
+        .map_err(|err| format!("无法打开路径: {err}"))?;
+    if status.success() {
+        Ok(describe_command(LaunchStrategy::SystemOpen, &command))
+    } else {
+        Err("打开路径失败".to_string())
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn list_dev_tool_presets_macos() -> Vec<DevToolPreset> {
+    let mut presets = Vec::new();
+    push_macos_app(
+        &mut presets,
+        "vscode",
+        "Visual Studio Code",
+        "Visual Studio Code",
+    );
+    push_macos_app(
+        &mut presets,
+        "vscode-insiders",
+        "Visual Studio Code - Insiders",
+        "Visual Studio Code - Insiders",
+    );
+
+    if !push_macos_app(
+        &mut presets,
+        "intellij-idea",
+        "IntelliJ IDEA",
+        "IntelliJ IDEA",
+    ) {
+        push_macos_app(
+            &mut presets,
+            "intellij-idea",
+            "IntelliJ IDEA Community",
+            "IntelliJ IDEA CE",
+        );
+    }
+
+    if !push_macos_app(&mut presets, "pycharm", "PyCharm", "PyCharm") {
+        push_macos_app(&mut presets, "pycharm", "PyCharm Community", "PyCharm CE");
+    }
+
+    push_macos_app(&mut presets, "webstorm", "WebStorm", "WebStorm");
+    push_macos_app(&mut presets, "goland", "GoLand", "GoLand");
+    push_macos_app(&mut presets, "rider", "Rider", "Rider");
+    push_macos_app(&mut presets, "clion", "CLion", "CLion");
+    push_macos_app(&mut presets, "phpstorm", "PhpStorm", "PhpStorm");
+    push_macos_app(&mut presets, "datagrip", "DataGrip", "DataGrip");
+
+    push_macos_app(&mut presets, "lapce", "Lapce", "Lapce");
+    push_macos_app(&mut presets, "pulsar", "Pulsar", "Pulsar");
+    push_macos_app(&mut presets, "brackets", "Brackets", "Brackets");
+
+    presets
+}
+
+// 在 /Applications 中查找应用包。
+#[cfg(target_os = "macos")]
+fn find_macos_app(app_name: &str) -> Option<PathBuf> {
+    let bundle_name = format!("{app_name}.app");
+    first_existing(
+        macos_application_dirs()
+            .into_iter()
+            .map(|dir| dir.join(&bundle_name)),
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn macos_application_dirs() -> Vec<PathBuf> {
+    vec![PathBuf::from("/Applications")]
+}
+
+// 列出应用目录中所有 .app 的名称（不含扩展名）。
+#[cfg(target_os = "macos")]
+fn list_macos_app_names() -> Vec<String> {
+    macos_application_dirs()
+        .into_iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_suffix(".app"))
+                .map(str::to_string)
+        })
+        .collect()
+}
+
+/// 扫描 /Applications 与 ~/Applications，返回 Info.plist 的 CFBundleDocumentTypes
+/// 中声明可处理 `public.source-code` 的应用。
+#[cfg(target_os = "macos")]
+pub fn discover_macos_editors() -> Vec<DevToolPreset> {
+    let mut presets: Vec<DevToolPreset> = macos_application_dirs()
+        .into_iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.path())
+        .filter(|bundle| bundle.extension().is_some_and(|ext| ext == "app"))
+        .filter(|bundle| {
+            read_bundle_info(bundle).is_some_and(|info| declares_source_code_documents(&info))
+        })
+        .filter_map(|bundle| {
+            let app_name = bundle.file_stem()?.to_str()?;
+            Some(DevToolPreset {
+                id: discovered_preset_id(app_name),
+                name: app_name.to_string(),
+                command_path: "/usr/bin/open".to_string(),
+                arguments: vec!["-a".to_string(), app_name.to_string(), "{path}".to_string()],
+                success_exit_codes: None,
+                kind: PresetKind::Command,
+                install_kind: install_kind_from_path(&bundle.to_string_lossy())
+                    .unwrap_or(InstallKind::AppBundle),
+            })
+        })
+        .collect();
+    presets.sort_by(|left, right| left.name.cmp(&right.name));
+    presets
+}
+
+// Info.plist 可能是二进制格式，交给 plutil 转成 JSON 再解析。
+#[cfg(target_os = "macos")]
+fn read_bundle_info(bundle: &Path) -> Option<serde_json::Value> {
+    let output = Command::new("/usr/bin/plutil")
+        .args(["-convert", "json", "-o", "-"])
+        .arg(bundle.join("Contents").join("Info.plist"))
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn declares_source_code_documents(info: &serde_json::Value) -> bool {
+    info["CFBundleDocumentTypes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|document_type| document_type["LSItemContentTypes"].as_array())
+        .flatten()
+        .any(|content_type| content_type.as_str() == Some("public.source-code"))
+}
+
+// 自动发现的预设 ID 加上前缀，避免与内置预设冲突，例如 "Sublime Text" → "app-sublime-text"。
+#[cfg(any(target_os = "macos", test))]
+fn discovered_preset_id(app_name: &str) -> String {
+    let slug = app_name
+        .split(|ch: char| !ch.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-");
+    format!("app-{slug}")
+}
+
+// 内置预设优先：ID 相同或指向同一应用的自动发现结果被丢弃。
+#[cfg(any(target_os = "macos", test))]
+fn merge_discovered_presets(
+    curated: Vec<DevToolPreset>,
+    discovered: Vec<DevToolPreset>,
+) -> Vec<DevToolPreset> {
+    let curated_apps: HashSet<String> = curated
+        .iter()
+        .filter_map(macos_preset_app_name)
+        .map(normalize_app_name)
+        .collect();
+    let discovered = discovered
+        .into_iter()
+        .filter(|preset| {
+            macos_preset_app_name(preset)
+                .is_none_or(|app_name| !curated_apps.contains(&normalize_app_name(app_name)))
+        })
+        .collect();
+    merge_provider_presets([curated, discovered])
+}
+
+// 取出 `open -a <应用名>` 形式预设中的应用名。
+#[cfg(any(target_os = "macos", test))]
+fn macos_preset_app_name(preset: &DevToolPreset) -> Option<&str> {
+    let index = preset
+        .arguments
+        .iter()
+        .position(|argument| argument == "-a")?;
+    preset.arguments.get(index + 1).map(String::as_str)
+}
+
+#[cfg(target_os = "macos")]
+fn fuzzy_match_macos_app(app_name: &str) -> Option<String> {
+    fuzzy_app_candidates(app_name, &list_macos_app_names())
+        .into_iter()
+        .find(|candidate| candidate != app_name)
+}
+
+// 按匹配程度排序候选应用名：名称相同 > 前缀 > 包含 > 按顺序包含全部字符，
+// 同一级别中名称越短越靠前。比较前统一转小写并去除空格与标点。
+#[cfg(any(target_os = "macos", test))]
+fn fuzzy_app_candidates(query: &str, app_names: &[String]) -> Vec<String> {
+    let query = normalize_app_name(query);
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let mut ranked: Vec<(u8, usize, &String)> = app_names
+        .iter()
+        .filter_map(|name| {
+            let normalized = normalize_app_name(name);
+            let rank = if normalized == query {
+                0
+            } else if normalized.starts_with(&query) {
+                1
+            } else if normalized.contains(&query) {
+                2
+            } else if is_subsequence(&query, &normalized) {
+                3
+            } else {
+                return None;
+            };
+            Some((rank, normalized.len(), name))
+        })
+        .collect();
+    ranked.sort();
+    ranked
+        .into_iter()
+        .map(|(_, _, name)| name.clone())
+        .collect()
+}
+
+// 去掉首尾空白与 `.app` 后缀，并把连续的空白（含制表符、不换行空格）合并为一个空格。
+fn clean_macos_app_name(name: &str) -> String {
+    let name = name.trim();
+    let name = match name.len().checked_sub(".app".len()) {
+        Some(split)
+            if name.is_char_boundary(split) && name[split..].eq_ignore_ascii_case(".app") =>
+        {
+            &name[..split]
+        }
+        _ => name,
+    };
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// 在应用目录中定位名称对应的 .app 包；名称与包名只有空白或标点不同时也视为同一应用。
+#[cfg(target_os = "macos")]
+fn resolve_macos_app_bundle(app_name: &str) -> Option<PathBuf> {
+    find_macos_app(app_name).or_else(|| {
+        let app_names = list_macos_app_names();
+        find_macos_app(same_app_name(app_name, &app_names)?)
+    })
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn same_app_name<'a>(app_name: &str, app_names: &'a [String]) -> Option<&'a str> {
+    let query = normalize_app_name(app_name);
+    if query.is_empty() {
+        return None;
+    }
+    app_names
+        .iter()
+        .find(|name| normalize_app_name(name) == query)
+        .map(String::as_str)
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn normalize_app_name(name: &str) -> String {
+    name.chars()
+        .filter(|ch| ch.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle
+        .chars()
+        .all(|ch| haystack.any(|candidate| candidate == ch))
+}
+
+#[cfg(target_os = "macos")]
+fn push_macos_app(
+    presets: &mut Vec<DevToolPreset>,
+    id: &str,
+    display_name: &str,
+    app_name: &str,
+) -> bool {
+    let Some(bundle) = find_macos_app(app_name) else {
+        return false;
+    };
+    presets.push(DevToolPreset {
+        id: id.to_string(),
+        name: display_name.to_string(),
+        command_path: "/usr/bin/open".to_string(),
+        arguments: vec!["-a".to_string(), app_name.to_string(), "{path}".to_string()],
+        success_exit_codes: None,
+        kind: PresetKind::Command,
+        install_kind: install_kind_from_path(&bundle.to_string_lossy())
+            .unwrap_or(InstallKind::AppBundle),
+    });
+    true
+}
+
+#[cfg(target_os = "windows")]
+fn list_dev_tool_presets_windows() -> Vec<DevToolPreset> {
+    let mut presets = Vec::new();
+    // 查询开始菜单应用需要启动 PowerShell，仅在常规位置都找不到时才查询一次。
+    let start_apps = std::cell::OnceCell::new();
+    let find_store_alias = |package_name: &str, alias: &str| {
+        find_store_app_alias(start_apps.get_or_init(list_start_apps), package_name, alias)
+    };
+
+    if let Some(path) =
+        find_windows_vscode().or_else(|| find_store_alias("Microsoft.VisualStudioCode", "code.exe"))
+    {
+        presets.push(build_windows_preset(
+            "vscode",
+            "Visual Studio Code",
+            path,
+            InstallKind::Standalone,
+        ));
+    }
+    if let Some(path) = find_windows_vscode_insiders()
+        .or_else(|| find_store_alias("Microsoft.VisualStudioCodeInsiders", "code-insiders.exe"))
+    {
+        presets.push(build_windows_preset(
+            "vscode-insiders",
+            "Visual Studio Code - Insiders",
+            path,
+            InstallKind::Standalone,
+        ));
+    }
+
+    if let Some(path) = find_jetbrains_toolbox_exe("IDEA-U", "idea64.exe")
+        .or_else(|| find_jetbrains_toolbox_exe("IDEA-C", "idea64.exe"))
+        .or_else(|| find_jetbrains_install_exe("idea64.exe"))
+        .or_else(|| find_jetbrains_package_exe("idea64.exe"))
+    {
+        let name = if path.to_string_lossy().to_lowercase().contains("idea-c") {
+            "IntelliJ IDEA Community"
+        } else {
+            "IntelliJ IDEA"
+        };
+        presets.push(build_windows_preset(
+            "intellij-idea",
+            name,
+            path,
+            InstallKind::Standalone,
+        ));
+    }
+
+    if let Some(path) = find_jetbrains_toolbox_exe("PyCharm-P", "pycharm64.exe")
+        .or_else(|| find_jetbrains_toolbox_exe("PyCharm-C", "pycharm64.exe"))
+        .or_else(|| find_jetbrains_install_exe("pycharm64.exe"))
+        .or_else(|| find_jetbrains_package_exe("pycharm64.exe"))
+    {
+        let name = if path.to_string_lossy().to_lowercase().contains("pycharm-c") {
+            "PyCharm Community"
+        } else {
+            "PyCharm"
+        };
+        presets.push(build_windows_preset(
+            "pycharm",
+            name,
+            path,
+            InstallKind::Standalone,
+        ));
+    }
+
+    add_jetbrains_windows_preset(
+        &mut presets,
+        "webstorm",
+        "WebStorm",
+        "WebStorm",
+        "webstorm64.exe",
+    );
+    add_jetbrains_windows_preset(&mut presets, "goland", "GoLand", "GoLand", "goland64.exe");
+    add_jetbrains_windows_preset(&mut presets, "rider", "Rider", "Rider", "rider64.exe");
+    add_jetbrains_windows_preset(&mut presets, "clion", "CLion", "CLion", "clion64.exe");
+    add_jetbrains_windows_preset(
+        &mut presets,
+        "phpstorm",
+        "PhpStorm",
+        "PhpStorm",
+        "phpstorm64.exe",
+    );
+    add_jetbrains_windows_preset(
+        &mut presets,
+        "datagrip",
+        "DataGrip",
+        "DataGrip",
+        "datagrip64.exe",
+    );
+
+    add_windows_preset(
+        &mut presets,
+        "lapce",
+        "Lapce",
+        &["LOCALAPPDATA", "ProgramFiles"],
+        &["Programs\\Lapce\\lapce.exe", "Lapce\\lapce.exe"],
+        "lapce",
+    );
+    add_windows_preset(
+        &mut presets,
+        "pulsar",
+        "Pulsar",
+        &["LOCALAPPDATA", "ProgramFiles"],
+        &["Programs\\Pulsar\\Pulsar.exe", "Pulsar\\Pulsar.exe"],
+        "pulsar",
+    );
+    add_windows_preset(
+        &mut presets,
+        "brackets",
+        "Brackets",
+        &["ProgramFiles(x86)", "ProgramFiles"],
+        &["Brackets\\Brackets.exe"],
+        "brackets",
+    );
+
+    presets
+}
+
+// 在常见安装目录中查找，找不到时再查 PATH 中的命令行工具。
+#[cfg(target_os = "windows")]
+fn add_windows_preset(
+    presets: &mut Vec<DevToolPreset>,
+    id: &str,
+    name: &str,
+    env_keys: &[&str],
+    suffixes: &[&str],
+    command: &str,
+) {
+    let suffixes: Vec<PathBuf> = suffixes.iter().map(PathBuf::from).collect();
+    let found = find_windows_path(env_keys, &suffixes)
+        .map(|path| (path, InstallKind::Standalone))
+        .or_else(|| find_in_path(command).map(|path| (PathBuf::from(path), InstallKind::PathShim)));
+    if let Some((path, fallback_kind)) = found {
+        presets.push(build_windows_preset(id, name, path, fallback_kind));
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn add_jetbrains_windows_preset(
+    presets: &mut Vec<DevToolPreset>,
+    id: &str,
+    name: &str,
+    toolbox_code: &str,
+    exe_name: &str,
+) {
+    if let Some(path) = find_jetbrains_toolbox_exe(toolbox_code, exe_name)
+        .or_else(|| find_jetbrains_install_exe(exe_name))
+        .or_else(|| find_jetbrains_package_exe(exe_name))
+    {
+        presets.push(build_windows_preset(
+            id,
+            name,
+            path,
+            InstallKind::Standalone,
+        ));
+    }
+}
+
+// 安装来源优先按路径特征（Toolbox、Scoop、应用商店等）判断，判断不出时使用 `fallback_kind`。
+#[cfg(target_os = "windows")]
+fn build_windows_preset(
+    id: &str,
+    name: &str,
+    command_path: PathBuf,
+    fallback_kind: InstallKind,
+) -> DevToolPreset {
+    let command_path = command_path.to_string_lossy().to_string();
+    DevToolPreset {
+        id: id.to_string(),
+        name: name.to_string(),
+        kind: PresetKind::Command,
+        install_kind: install_kind_from_path(&command_path).unwrap_or(fallback_kind),
+        command_path,
+        arguments: vec!["{path}".to_string()],
+        success_exit_codes: None,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn find_windows_vscode() -> Option<PathBuf> {
+    find_windows_path(
+        &["ProgramFiles", "ProgramFiles(x86)", "LOCALAPPDATA"],
+        &[
+            PathBuf::from("Microsoft VS Code\\Code.exe"),
+            PathBuf::from("Programs\\Microsoft VS Code\\Code.exe"),
+        ],
+    )
+    .or_else(|| find_scoop_app(&["vscode"], Path::new("Code.exe")))
+    .or_else(|| find_chocolatey_shim("code"))
+    .or_else(|| find_in_path("code").map(PathBuf::from))
+}
+
+#[cfg(target_os = "windows")]
+fn find_windows_vscode_insiders() -> Option<PathBuf> {
+    find_windows_path(
+        &["ProgramFiles", "ProgramFiles(x86)", "LOCALAPPDATA"],
+        &[
+            PathBuf::from("Microsoft VS Code Insiders\\Code - Insiders.exe"),
+            PathBuf::from("Programs\\Microsoft VS Code Insiders\\Code - Insiders.exe"),
+        ],
+    )
+    .or_else(|| find_scoop_app(&["vscode-insiders"], Path::new("Code - Insiders.exe")))
+    .or_else(|| find_chocolatey_shim("code-insiders"))
+    .or_else(|| find_in_path("code-insiders").map(PathBuf::from))
+}
+
+#[cfg(target_os = "windows")]
+fn find_windows_path(env_keys: &[&str], suffixes: &[PathBuf]) -> Option<PathBuf> {
+    env_keys
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .flat_map(|root| {
+            suffixes
+                .iter()
+                .map(move |suffix| Path::new(&root).join(suffix))
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+// Scoop 的应用目录：用户级 %SCOOP%（默认 %USERPROFILE%\scoop）与全局 %SCOOP_GLOBAL%（默认 %ProgramData%\scoop）。
+#[cfg(target_os = "windows")]
+fn scoop_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if let Ok(root) = std::env::var("SCOOP") {
+        roots.push(PathBuf::from(root));
+    } else if let Ok(profile) = std::env::var("USERPROFILE") {
+        roots.push(PathBuf::from(profile).join("scoop"));
+    }
+    if let Ok(root) = std::env::var("SCOOP_GLOBAL") {
+        roots.push(PathBuf::from(root));
+    } else if let Ok(data) = std::env::var("ProgramData") {
+        roots.push(PathBuf::from(data).join("scoop"));
+    }
+    roots
+}
+
+// Scoop 把当前版本链接到 `apps\<应用>\current`。
+#[cfg(target_os = "windows")]
+fn find_scoop_app(app_names: &[&str], relative_path: &Path) -> Option<PathBuf> {
+    let candidates = scoop_roots().into_iter().flat_map(|root| {
+        app_names.iter().map(move |app| {
+            root.join("apps")
+                .join(app)
+                .join("current")
+                .join(relative_path)
+        })
+    });
+    first_existing(candidates)
+}
+
+/// 开始菜单中的应用（`Get-StartApps` 的输出）。
+#[cfg(any(target_os = "windows", test))]
+#[derive(Debug, PartialEq, serde::Deserialize)]
+struct StartApp {
+    #[serde(rename = "Name")]
+    name: String,
+    /// MSIX 应用为 `<PackageFamilyName>!<应用 ID>`，传统程序为路径或其他标识。
+    #[serde(rename = "AppID")]
+    app_id: String,
+}
+
+#[cfg(target_os = "windows")]
+const START_APPS_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[cfg(target_os = "windows")]
+fn list_start_apps() -> Vec<StartApp> {
+    let script = "[Console]::OutputEncoding = [Text.Encoding]::UTF8; \
+                  Get-StartApps | Select-Object Name, AppID | ConvertTo-Json -Compress";
+    let child = powershell_command(script, false)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    match child
+        .map_err(|err| err.to_string())
+        .and_then(|child| read_output_with_timeout(child, START_APPS_TIMEOUT))
+    {
+        Ok(output) => parse_start_apps(&output),
+        Err(err) => {
+            log::warn!("查询开始菜单应用失败: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+// 只有一个应用时 ConvertTo-Json 输出单个对象而不是数组。
+#[cfg(any(target_os = "windows", test))]
+fn parse_start_apps(output: &str) -> Vec<StartApp> {
+    let output = output.trim();
+    serde_json::from_str::<Vec<StartApp>>(output)
+        .or_else(|_| serde_json::from_str::<StartApp>(output).map(|app| vec![app]))
+        .unwrap_or_default()
+}
+
+// 按包名（PackageFamilyName 中发布者哈希之前的部分）查找 MSIX 应用。
+#[cfg(any(target_os = "windows", test))]
+fn find_msix_app<'a>(apps: &'a [StartApp], package_name: &str) -> Option<&'a StartApp> {
+    apps.iter().find(|app| {
+        app.app_id
+            .split_once('!')
+            .and_then(|(family_name, _)| family_name.rsplit_once('_'))
+            .is_some_and(|(name, _)| name.eq_ignore_ascii_case(package_name))
+    })
+}
+
+// Microsoft Store 安装的 MSIX 应用没有可直接执行的 exe，只能通过
+// %LOCALAPPDATA%\Microsoft\WindowsApps 下的应用执行别名（可传入参数）启动。
+// 该目录也存放着未安装应用的商店跳转桩（如 python.exe），因此先确认开始菜单中有对应的包。
+// 别名是重解析点，is_file 无法识别，改用 symlink_metadata 判断是否存在。
+#[cfg(target_os = "windows")]
+fn find_store_app_alias(apps: &[StartApp], package_name: &str, alias: &str) -> Option<PathBuf> {
+    let app = find_msix_app(apps, package_name)?;
+    let alias_path = Path::new(&std::env::var("LOCALAPPDATA").ok()?)
+        .join("Microsoft")
+        .join("WindowsApps")
+        .join(alias);
+    if fs::symlink_metadata(&alias_path).is_ok() {
+        return Some(alias_path);
+    }
+    log::warn!(
+        "{} 已从 Microsoft Store 安装（{}），但应用执行别名 {} 已关闭，无法传入路径启动",
+        app.name,
+        app.app_id,
+        alias
+    );
+    None
+}
+
+// Chocolatey 在 %ChocolateyInstall%\bin 下生成命令 shim，该目录不一定在 PATH 中。
+#[cfg(target_os = "windows")]
+fn find_chocolatey_shim(command: &str) -> Option<PathBuf> {
+    let root = std::env::var("ChocolateyInstall")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("ProgramData").map(|data| Path::new(&data).join("chocolatey")))
+        .ok()?;
+    first_existing([root.join("bin").join(format!("{command}.exe"))])
+}
+
+// 通过 Scoop（extras 仓库，例如 idea / idea-ultimate、pycharm / pycharm-professional）
+// 或 Chocolatey shim 安装的 JetBrains IDE。
+#[cfg(target_os = "windows")]
+fn find_jetbrains_package_exe(exe_name: &str) -> Option<PathBuf> {
+    let base = exe_name.trim_end_matches(".exe").trim_end_matches("64");
+    let app_names = [
+        format!("{base}-ultimate"),
+        format!("{base}-professional"),
+        base.to_string(),
+    ];
+    let app_names: Vec<&str> = app_names.iter().map(String::as_str).collect();
+    find_scoop_app(&app_names, &Path::new("IDE").join("bin").join(exe_name))
+        .or_else(|| find_chocolatey_shim(base))
+}
+
+#[cfg(target_os = "windows")]
+fn find_jetbrains_toolbox_exe(product_code: &str, exe_name: &str) -> Option<PathBuf> {
+    let local = std::env::var("LOCALAPPDATA").ok()?;
+    let base = PathBuf::from(local)
+        .join("JetBrains")
+        .join("Toolbox")
+        .join("apps")
+        .join(product_code)
+        .join("ch-0");
+    if !base.is_dir() {
+        return None;
+    }
+    let mut builds: Vec<PathBuf> = Vec::new();
+    if let Ok(entries) = fs::read_dir(base) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                let candidate = path.join("bin").join(exe_name);
+                if candidate.is_file() {
+                    builds.push(path);
+                }
+            }
+        }
+    }
+    builds.sort_by(|left, right| left.file_name().cmp(&right.file_name()));
+    let latest = builds.pop()?;
+    let exe_path = latest.join("bin").join(exe_name);
+    if exe_path.is_file() {
+        Some(exe_path)
+    } else {
+        None
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn find_jetbrains_install_exe(exe_name: &str) -> Option<PathBuf> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    if let Ok(path) = std::env::var("ProgramFiles") {
+        roots.push(PathBuf::from(path).join("JetBrains"));
+    }
+    if let Ok(path) = std::env::var("ProgramFiles(x86)") {
+        roots.push(PathBuf::from(path).join("JetBrains"));
+    }
+    if let Ok(path) = std::env::var("LOCALAPPDATA") {
+        let local_path = PathBuf::from(&path);
+        roots.push(local_path.join("JetBrains"));
+        roots.push(local_path.join("Programs").join("JetBrains"));
+    }
+    for root in roots {
+        if let Some(found) = find_jetbrains_in_root(&root, exe_name) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+#[cfg(target_os = "windows")]
+fn find_jetbrains_in_root(root: &Path, exe_name: &str) -> Option<PathBuf> {
+    if !root.is_dir() {
+        return None;
+    }
+    let entries = fs::read_dir(root).ok()?;
+    entries
+        .flatten()
+        .map(|entry| entry.path().join("bin").join(exe_name))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn list_dev_tool_presets_linux() -> Vec<DevToolPreset> {
+    let mut presets = Vec::new();
+    let (stable, insiders) = classify_vscode_shims(
+        find_in_path("code"),
+        find_in_path("code-insiders"),
+        vscode_reports_insiders,
+    );
+    if let Some(command) = stable {
+        presets.push(build_linux_preset(
+            "vscode",
+            "Visual Studio Code",
+            command,
+            InstallKind::PathShim,
+        ));
+    }
+    if let Some(command) = insiders {
+        presets.push(build_linux_preset(
+            "vscode-insiders",
+            "Visual Studio Code - Insiders",
+            command,
+            InstallKind::PathShim,
+        ));
+    }
+
+    add_linux_preset(&mut presets, "intellij-idea", "IntelliJ IDEA", "idea");
+    add_linux_preset(&mut presets, "webstorm", "WebStorm", "webstorm");
+    add_linux_preset(&mut presets, "pycharm", "PyCharm", "pycharm");
+    add_linux_preset(&mut presets, "goland", "GoLand", "goland");
+    add_linux_preset(&mut presets, "rider", "Rider", "rider");
+    add_linux_preset(&mut presets, "clion", "CLion", "clion");
+    add_linux_preset(&mut presets, "phpstorm", "PhpStorm", "phpstorm");
+    add_linux_preset(&mut presets, "datagrip", "DataGrip", "datagrip");
+
+    add_linux_preset(&mut presets, "lapce", "Lapce", "lapce");
+    add_linux_preset(&mut presets, "pulsar", "Pulsar", "pulsar");
+    add_linux_preset(&mut presets, "brackets", "Brackets", "brackets");
+
+    presets
+}
+
+// 部分系统上 `code` 实际指向 Insiders，按 `--version` 的输出重新归类两个命令；
+// 无法判断时沿用命令名。两个命令归为同一类时保留先找到的一个。
+#[cfg(any(all(not(target_os = "macos"), not(target_os = "windows")), test))]
+fn classify_vscode_shims(
+    code: Option<String>,
+    code_insiders: Option<String>,
+    reports_insiders: impl Fn(&str) -> Option<bool>,
+) -> (Option<String>, Option<String>) {
+    let mut stable = None;
+    let mut insiders = None;
+    for (command, named_insiders) in [(code, false), (code_insiders, true)] {
+        let Some(command) = command else {
+            continue;
+        };
+        let slot = if reports_insiders(&command).unwrap_or(named_insiders) {
+            &mut insiders
+        } else {
+            &mut stable
+        };
+        if slot.is_none() {
+            *slot = Some(command);
+        } else {
+            log::warn!("忽略重复的 VS Code 命令: {}", command);
+        }
+    }
+    (stable, insiders)
+}
+
+// `--version` 的检测结果按命令缓存，命令文件的修改时间变化（如升级）后重新检测。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+type VsCodeChannelCache = HashMap<String, (Option<std::time::SystemTime>, Option<bool>)>;
+
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+static VSCODE_CHANNELS: OnceLock<Mutex<VsCodeChannelCache>> = OnceLock::new();
+
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn vscode_channel_cache() -> MutexGuard<'static, VsCodeChannelCache> {
+    VSCODE_CHANNELS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// 执行 `<command> --version`，首行版本号带 `-insider` 后缀即为 Insiders；执行失败时返回 None。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn vscode_reports_insiders(command: &str) -> Option<bool> {
+    let modified = fs::metadata(command)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    if let Some((cached_modified, channel)) = vscode_channel_cache().get(command) {
+        if *cached_modified == modified {
+            return *channel;
+        }
+    }
+    let channel = Command::new(command)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| is_insiders_version_output(&String::from_utf8_lossy(&output.stdout)));
+    vscode_channel_cache().insert(command.to_string(), (modified, channel));
+    channel
+}
+
+#[cfg(any(all(not(target_os = "macos"), not(target_os = "windows")), test))]
+fn is_insiders_version_output(output: &str) -> Option<bool> {
+    let version = output.lines().next()?.trim();
+    if version.is_empty() {
+        return None;
+    }
+    Some(version.ends_with("-insider"))
+}
+
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn add_linux_preset(presets: &mut Vec<DevToolPreset>, id: &str, name: &str, command: &str) {
+    if let Some(command_path) = find_in_path(command) {
+        presets.push(build_linux_preset(
+            id,
+            name,
+            command_path,
+            InstallKind::PathShim,
+        ));
+    }
+}
+
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn build_linux_preset(
+    id: &str,
+    name: &str,
+    command_path: String,
+    fallback_kind: InstallKind,
+) -> DevToolPreset {
+    DevToolPreset {
+        id: id.to_string(),
+        name: name.to_string(),
+        kind: PresetKind::Command,
+        install_kind: install_kind_from_path(&command_path).unwrap_or(fallback_kind),
+        command_path,
+        arguments: vec!["{path}".to_string()],
+        success_exit_codes: None,
+    }
+}
+
+/// 根据路径中的特征目录推断安装来源，无法判断时返回 `None`，由调用方按发现方式决定。
+fn install_kind_from_path(path: &str) -> Option<InstallKind> {
+    let normalized = path.replace('\\', "/").to_lowercase();
+    let kind = if normalized.contains("/jetbrains/toolbox/")
+        || normalized.contains("/jetbrains toolbox/")
+    {
+        InstallKind::JetBrainsToolbox
+    } else if normalized.contains("/windowsapps/") {
+        InstallKind::MicrosoftStore
+    } else if normalized.contains("/scoop/") {
+        InstallKind::Scoop
+    } else if normalized.contains("/chocolatey/bin/") {
+        InstallKind::PathShim
+    } else if normalized.starts_with("/snap/") {
+        InstallKind::Snap
+    } else if normalized.contains("/flatpak/exports/") {
+        InstallKind::Flatpak
+    } else if normalized.ends_with(".app") || normalized.contains(".app/") {
+        InstallKind::AppBundle
+    } else {
+        return None;
+    };
+    Some(kind)
+}
+
+/// 按顺序返回第一个存在的候选路径。
+#[cfg(any(target_os = "macos", target_os = "windows", test))]
+fn first_existing(candidates: impl IntoIterator<Item = PathBuf>) -> Option<PathBuf> {
+    candidates.into_iter().find(|candidate| candidate.exists())
+}
+
+fn find_in_path(command: &str) -> Option<String> {
+    find_in_path_with(command, false)
+}
+
+type PathLookupCache = HashMap<(String, bool), Option<String>>;
+
+thread_local! {
+    // 单次扫描内的 PATH 查找结果，键为（命令名, 是否严格模式），带扩展名的命令名单独缓存。
+    // 为 None 时不缓存，只在 with_path_lookup_cache 期间启用；按线程隔离，并发扫描互不影响。
+    static PATH_LOOKUP_CACHE: RefCell<Option<PathLookupCache>> =
+        const { RefCell::new(None) };
+}
+
+// 预设扫描会多次查找同一命令（如 Linux 的 VS Code 归类与各 IDE 的候选位置），
+// 每次都要重新拆分 PATH 并逐个目录 stat。实测 PATH 含 20 个目录时，10 个未安装的命令
+// 各查找 10 次由约 1.0 ms 降到约 0.15 ms。扫描结束后丢弃缓存，避免安装新工具后结果过期。
+fn with_path_lookup_cache<T>(scan: impl FnOnce() -> T) -> T {
+    struct RestoreCache(Option<PathLookupCache>);
+    impl Drop for RestoreCache {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            PATH_LOOKUP_CACHE.with(|cache| *cache.borrow_mut() = previous);
+        }
+    }
+    let _restore =
+        RestoreCache(PATH_LOOKUP_CACHE.with(|cache| cache.borrow_mut().replace(HashMap::new())));
+    scan()
+}
+
+fn cached_path_lookup(
+    command: &str,
+    strict: bool,
+    lookup: impl FnOnce() -> Option<String>,
+) -> Option<String> {
+    let key = (command.to_string(), strict);
+    if let Some(cached) = PATH_LOOKUP_CACHE.with(|cache| {
+        cache
+            .borrow()
+            .as_ref()
+            .and_then(|map| map.get(&key).cloned())
+    }) {
+        return cached;
+    }
+    let found = lookup();
+    PATH_LOOKUP_CACHE.with(|cache| {
+        if let Some(map) = cache.borrow_mut().as_mut() {
+            map.insert(key, found.clone());
+        }
+    });
+    found
+}
+
+// 默认模式存在 PATH 劫持风险：PATH 中的 `.`、空项或相对目录会相对当前工作目录解析，
+// Windows 上还会为无扩展名的命令补全 exe/cmd/bat，于是项目目录里的 `code.bat`
+// 可能被当作编辑器执行。`strict` 为真时跳过非绝对路径的 PATH 目录且只按原名查找，
+// 用于启动用户提供的命令名。
+fn find_in_path_with(command: &str, strict: bool) -> Option<String> {
+    cached_path_lookup(command, strict, || search_path(command, strict))
+}
+
+fn search_path(command: &str, strict: bool) -> Option<String> {
+    let path_var = std::env::var_os("PATH")?;
+    for dir in std::env::split_paths(&path_var) {
+        if strict && !dir.is_absolute() {
+            continue;
+        }
+        let candidate = dir.join(command);
+        if candidate.is_file() {
+            return Some(candidate.to_string_lossy().to_string());
+        }
+        #[cfg(target_os = "windows")]
+        {
+            if !strict && Path::new(command).extension().is_none() {
+                for ext in ["exe", "cmd", "bat"] {
+                    let with_ext = dir.join(format!("{command}.{ext}"));
+                    if with_ext.is_file() {
+                        return Some(with_ext.to_string_lossy().to_string());
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(target_os = "windows")]
+fn open_with_default(path: &str) -> Result<OpenOutcome, String> {
+    run_explorer(&explorer_open_argument(path))
+}
+
+// explorer 自行解析命令行，不遵循常规的参数转义规则，因此需要以原始参数传入。
+// 另外 explorer 成功打开窗口时也经常返回退出码 1，只能以进程能否启动判断结果。
+#[cfg(target_os = "windows")]
+fn run_explorer(raw_argument: &str) -> Result<OpenOutcome, String> {
+    use std::os::windows::process::CommandExt;
+
+    Command::new("explorer")
+        .raw_arg(raw_argument)
+        .status()
+        .map(|_| OpenOutcome {
+            strategy: LaunchStrategy::Explorer,
+            command: "explorer".to_string(),
+            args: vec![raw_argument.to_string()],
+            warnings: Vec::new(),
+        })
+        .map_err(|err| format!("无法打开路径: {err}"))
+}
+
+// explorer 会把未加引号的逗号当作参数分隔符，空格同理，所以普通打开要整体加引号。
+#[cfg(any(target_os = "windows", test))]
+fn explorer_open_argument(path: &str) -> String {
+    format!("\"{}\"", normalize_explorer_path(path))
+}
+
+// /select 只认 `/select,"路径"` 的形式，整体加引号（`"/select,路径"`）会被忽略并打开“文档”目录。
+#[cfg(any(target_os = "windows", test))]
+fn explorer_select_argument(path: &str) -> String {
+    format!("/select,\"{}\"", normalize_explorer_path(path))
+}
+
+// Windows 路径不可能包含双引号，直接去除以免提前闭合引号；
+// explorer 也不识别正斜杠分隔的本地路径，链接则保持原样。
+// 超过 MAX_PATH（按 UTF-16 字符计）的路径需要扩展前缀才能被系统接受。
+#[cfg(any(target_os = "windows", test))]
+fn normalize_explorer_path(path: &str) -> String {
+    let path = path.replace('"', "");
+    if path.contains("://") {
+        return path;
+    }
+    let path = path.replace('/', "\\");
+    if path.encode_utf16().count() >= WINDOWS_MAX_PATH {
+        to_windows_extended_path(&path)
+    } else {
+        path
+    }
+}
+
+#[cfg(any(target_os = "windows", test))]
+const WINDOWS_MAX_PATH: usize = 260;
+
+/// 把绝对路径转换为 `\\?\` 扩展形式以绕过 MAX_PATH 限制：
+/// 盘符路径加 `\\?\` 前缀，UNC 路径 `\\server\share` 转为 `\\?\UNC\server\share`；
+/// 已带前缀的路径与相对路径保持原样（扩展形式不允许相对路径）。
+#[cfg(any(target_os = "windows", test))]
+fn to_windows_extended_path(path: &str) -> String {
+    let path = path.replace('/', "\\");
+    if path.starts_with("\\\\?\\") || path.starts_with("\\\\.\\") {
+        return path;
+    }
+    if let Some(unc) = path.strip_prefix("\\\\") {
+        return format!("\\\\?\\UNC\\{unc}");
+    }
+    let bytes = path.as_bytes();
+    if bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\' {
+        return format!("\\\\?\\{path}");
+    }
+    path
+}
+
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn open_with_default(path: &str) -> Result<OpenOutcome, String> {
+    let mut command = Command::new("xdg-open");
+    command.arg(path);
+    let status = command
+        .status()
+        .map_err(|err| format!("无法打开路径: {err}"))?;
+    if status.success() {
+        Ok(describe_command(LaunchStrategy::SystemOpen, &command))
+    } else {
+        Err("打开路径失败".to_string())
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn open_macos_terminal(
+    path: &str,
+    reuse_window: bool,
+    program: Option<&[String]>,
+) -> Result<OpenOutcome, String> {
+    run_osascript(
+        &macos_terminal_script(path, reuse_window, program),
+        "无法打开终端:",
+        "终端打开失败",
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn run_osascript(
+    script: &str,
+    spawn_error_prefix: &str,
+    failure_message: &str,
+) -> Result<OpenOutcome, String> {
+    let mut command = Command::new("/usr/bin/osascript");
+    command.arg("-e").arg(script);
+    let output = command
+        .output()
+        .map_err(|err| format!("{spawn_error_prefix} {err}"))?;
+    if output.status.success() {
+        return Ok(describe_command(LaunchStrategy::Osascript, &command));
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if let Some(app) = automation_denied_app(&stderr) {
+        return Err(automation_permission_error(&app));
+    }
+    match describe_osascript_error(&stderr) {
+        Some(detail) => Err(format!("{failure_message}: {detail}")),
+        None => Err(failure_message.to_string()),
+    }
+}
+
+// 缺少 macOS 自动化权限时错误信息的固定前缀，完整格式为 `未获得自动化权限: <应用名>（…）`，
+// 由 [`classify_system_error`] 识别为 [`SystemError::AutomationPermissionDenied`]。
+const AUTOMATION_PERMISSION_DENIED: &str = "未获得自动化权限";
+
+/// 识别命令返回的错误信息中界面需要单独处理的类型，其他错误返回 None。
+pub fn classify_system_error(message: &str) -> Option<SystemError> {
+    let rest = message
+        .strip_prefix(AUTOMATION_PERMISSION_DENIED)?
+        .strip_prefix(": ")?;
+    let app = rest.split('（').next().unwrap_or(rest).trim();
+    (!app.is_empty()).then(|| SystemError::AutomationPermissionDenied {
+        app: app.to_string(),
+    })
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn automation_permission_error(app: &str) -> String {
+    format!(
+        "{AUTOMATION_PERMISSION_DENIED}: {app}（请在“系统设置 > 隐私与安全性 > 自动化”中允许本应用控制 {app}）"
+    )
+}
+
+// 被拒绝时 osascript 输出 `36:120: execution error: Not authorized to send Apple events to
+// Terminal. (-1743)`（errAEEventNotPermitted），从中取出目标应用名。
+#[cfg(any(target_os = "macos", test))]
+fn automation_denied_app(stderr: &str) -> Option<String> {
+    const NOT_AUTHORIZED: &str = "Not authorized to send Apple events to ";
+    if let Some((_, rest)) = stderr.split_once(NOT_AUTHORIZED) {
+        let app = rest.split(" (-1743)").next().unwrap_or(rest);
+        let app = app
+            .lines()
+            .next()
+            .unwrap_or(app)
+            .trim()
+            .trim_end_matches('.');
+        if !app.is_empty() {
+            return Some(app.to_string());
+        }
+    }
+    stderr.contains("(-1743)").then(|| "目标程序".to_string())
+}
+
+// osascript 的错误形如 `0:10: execution error: <描述> (<错误码>)`：去掉脚本位置前缀，
+// 并为常见的权限错误附上处理方法。
+#[cfg(any(target_os = "macos", test))]
+fn describe_osascript_error(stderr: &str) -> Option<String> {
+    let message = stderr.trim();
+    if message.is_empty() {
+        return None;
+    }
+    let message = message
+        .split_once("execution error: ")
+        .map_or(message, |(_, rest)| rest)
+        .trim();
+    let hint = if message.ends_with("(-1719)") || message.ends_with("(-25211)") {
+        Some("请在“系统设置 > 隐私与安全性 > 辅助功能”中允许本应用")
+    } else if message.ends_with("(-600)") {
+        Some("目标程序未运行或启动失败")
+    } else {
+        None
+    };
+    Some(match hint {
+        Some(hint) => format!("{message}（{hint}）"),
+        None => message.to_string(),
+    })
+}
+
+// 复用窗口时在最前面的 Terminal 窗口中执行 cd，没有窗口时由 `do script` 新建。
+#[cfg(any(target_os = "macos", test))]
+fn macos_terminal_script(path: &str, reuse_window: bool, program: Option<&[String]>) -> String {
+    let cd_command = escape_applescript_string(&terminal_startup_command(path, program));
+    if reuse_window {
+        format!(
+            "tell application \"Terminal\"\n    if (count of windows) = 0 then\n        do script \"{cd_command}\"\n    else\n        do script \"{cd_command}\" in front window\n    end if\n    activate\nend tell"
+        )
+    } else {
+        format!(
+            "tell application \"Terminal\"\n    do script \"{cd_command}\"\n    activate\nend tell"
+        )
+    }
+}
+
+#[cfg(any(target_os = "macos", test))]
+const ITERM_BUNDLE_ID: &str = "com.googlecode.iterm2";
+
+// 系统默认终端是否为 iTerm2：在 iTerm2 中选择“Make iTerm2 Default Term”后，
+// LaunchServices 中 `public.unix-executable` 的 shell 处理程序即为 iTerm2。
+#[cfg(target_os = "macos")]
+fn macos_default_terminal_is_iterm() -> bool {
+    let Some(home) = std::env::var_os("HOME") else {
+        return false;
+    };
+    let plist = Path::new(&home)
+        .join("Library/Preferences/com.apple.LaunchServices/com.apple.launchservices.secure.plist");
+    let Ok(output) = Command::new("/usr/bin/plutil")
+        .args(["-extract", "LSHandlers", "json", "-o", "-"])
+        .arg(&plist)
+        .output()
+    else {
+        return false;
+    };
+    output.status.success() && shell_handler_is_iterm(&String::from_utf8_lossy(&output.stdout))
+}
+
+// `handlers` 为 LSHandlers 数组的 JSON。
+#[cfg(any(target_os = "macos", test))]
+fn shell_handler_is_iterm(handlers: &str) -> bool {
+    serde_json::from_str::<Vec<serde_json::Value>>(handlers).is_ok_and(|handlers| {
+        handlers.iter().any(|handler| {
+            handler
+                .get("LSHandlerContentType")
+                .and_then(|value| value.as_str())
+                == Some("public.unix-executable")
+                && handler
+                    .get("LSHandlerRoleShell")
+                    .and_then(|value| value.as_str())
+                    .is_some_and(|id| id.eq_ignore_ascii_case(ITERM_BUNDLE_ID))
+        })
+    })
+}
+
+// 用户配置的终端是否为 iTerm2：直接指向 iTerm.app，或是 `open -a iTerm`、
+// `open -b com.googlecode.iterm2`。
+#[cfg(any(target_os = "macos", test))]
+fn is_iterm_command(command_path: &str, arguments: &[String]) -> bool {
+    let is_iterm_name = |name: &str| {
+        let name = Path::new(name.trim_end_matches('/'))
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(name);
+        matches!(
+            clean_macos_app_name(name).to_ascii_lowercase().as_str(),
+            "iterm" | "iterm2"
+        )
+    };
+    if is_iterm_name(command_path) {
+        return true;
+    }
+    Path::new(command_path)
+        .file_name()
+        .is_some_and(|name| name == "open")
+        && arguments.windows(2).any(|pair| match pair[0].as_str() {
+            "-a" => is_iterm_name(&pair[1]),
+            "-b" => pair[1].eq_ignore_ascii_case(ITERM_BUNDLE_ID),
+            _ => false,
+        })
+}
+
+// iTerm2 在当前窗口新建标签页；没有窗口时新建窗口。
+#[cfg(target_os = "macos")]
+fn open_iterm_tab(path: &str, program: Option<&[String]>) -> Result<OpenOutcome, String> {
+    let cd_command = escape_applescript_string(&terminal_startup_command(path, program));
+    let script = format!(
+        "tell application \"iTerm\"\n    if (count of windows) = 0 then\n        create window with default profile\n    else\n        tell current window to create tab with default profile\n    end if\n    tell current session of current window to write text \"{cd_command}\"\n    activate\nend tell"
+    );
+    run_osascript(&script, "无法打开 iTerm:", "iTerm 打开失败")
+}
+
+// 在新标签页中打开目标所在目录并选中目标；标签页需要等 Finder 创建完成后再切换目录。
+#[cfg(any(target_os = "macos", test))]
+fn finder_tab_script(path: &str) -> String {
+    let path = escape_applescript_string(path);
+    format!(
+        "set targetItem to (POSIX file \"{path}\") as alias\ntell application \"Finder\"\n    activate\n    if (count of Finder windows) = 0 then\n        reveal targetItem\n    else\n        tell application \"System Events\" to keystroke \"t\" using command down\n        delay 0.3\n        set target of front Finder window to container of targetItem\n        select targetItem\n    end if\nend tell"
+    )
+}
+
+// 转义 AppleScript 字符串字面量中的反斜杠与双引号。
+#[cfg(any(target_os = "macos", test))]
+fn escape_applescript_string(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(target_os = "windows")]
+fn open_windows_terminal(
+    path: &str,
+    reuse_window: bool,
+    program: Option<&[String]>,
+) -> Result<OpenOutcome, String> {
+    let mut wt = Command::new("wt.exe");
+    // wt 把选项之后的参数当作新标签页要运行的命令行。
+    wt.args(windows_terminal_arguments(path, reuse_window))
+        .args(program.into_iter().flatten());
+    let wt_failure = match wt.status() {
+        Ok(status) if status.success() => {
+            return Ok(describe_command(LaunchStrategy::WindowsTerminal, &wt))
+        }
+        Ok(status) => format!("Windows Terminal 启动失败: {status}"),
+        Err(err) => format!("无法启动 Windows Terminal: {err}"),
+    };
+    log::warn!("{}，改用 PowerShell 打开终端", wt_failure);
+
+    let mut script = format!(
+        "Set-Location -LiteralPath {}",
+        quote_powershell_literal(path)
+    );
+    if let Some(program) = program {
+        script.push_str("; &");
+        for argument in program {
+            script.push(' ');
+            script.push_str(&quote_powershell_literal(argument));
+        }
+    }
+    let status = run_powershell(&script, true)
+        .map_err(|err| format!("无法打开终端: {err}（{wt_failure}）"))?;
+    if status.success() {
+        Ok(describe_command(
+            LaunchStrategy::WindowsPowerShell,
+            &powershell_command(&script, true),
+        ))
+    } else {
+        Err(format!("终端打开失败（{wt_failure}）"))
+    }
+}
+
+// `-w 0` 指向最近使用的窗口，不存在时 wt 会新建窗口。
+#[cfg(any(target_os = "windows", test))]
+fn windows_terminal_arguments(path: &str, reuse_window: bool) -> Vec<String> {
+    let mut arguments = Vec::new();
+    if reuse_window {
+        arguments.extend(["-w".to_string(), "0".to_string()]);
+    }
+    arguments.extend(["-d".to_string(), path.to_string()]);
+    arguments
+}
+
+// Linux 终端按 $TERMINAL → x-terminal-emulator → 常见终端的顺序选择。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn open_linux_terminal(path: &str, program: Option<&[String]>) -> Result<OpenOutcome, String> {
+    let terminal = resolve_linux_terminal().ok_or_else(|| "未找到可用的终端".to_string())?;
+    let name = linux_terminal_name(&terminal);
+    let mut command = Command::new(&terminal);
+    command
+        .args(linux_terminal_directory_arguments(&name, path, || {
+            probe_terminal_flags(&terminal)
+        }))
+        .current_dir(path);
+    if let Some(program) = program {
+        command.args(linux_terminal_exec_arguments(&name, program));
+    }
+    spawn_detached(&mut command)
+        .map(|()| describe_command(LaunchStrategy::LinuxTerminal, &command))
+        .map_err(|err| format!("无法打开终端: {err}"))
+}
+
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn resolve_linux_terminal() -> Option<String> {
+    if let Some(terminal) = std::env::var("TERMINAL")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+    {
+        if Path::new(&terminal).is_absolute() && Path::new(&terminal).is_file() {
+            return Some(terminal);
+        }
+        if let Some(found) = find_in_path(&terminal) {
+            return Some(found);
+        }
+    }
+    ["x-terminal-emulator", "gnome-terminal", "konsole", "xterm"]
+        .into_iter()
+        .find_map(find_in_path)
+}
+
+// x-terminal-emulator 通常是 Debian alternatives 的软链接，需解析到真实终端才能确定参数。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn linux_terminal_name(terminal: &str) -> String {
+    let resolved = fs::canonicalize(terminal).unwrap_or_else(|_| PathBuf::from(terminal));
+    resolved
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(terminal)
+        .trim_end_matches(".wrapper")
+        .to_string()
+}
+
+// 指定工作目录的参数：（参数, 是否以 `=` 连接路径）。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+type DirectoryFlag = (&'static str, bool);
+
+// 各终端指定工作目录的参数，按优先顺序排列，较旧版本的终端可能只支持后面的写法。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+const LINUX_TERMINAL_DIRECTORY_FLAGS: &[(&[&str], &[DirectoryFlag])] = &[
+    (
+        &[
+            "gnome-terminal",
+            "xfce4-terminal",
+            "mate-terminal",
+            "tilix",
+            "foot",
+        ],
+        &[("--working-directory", true), ("--working-dir", true)],
+    ),
+    (&["konsole"], &[("--workdir", false)]),
+    (&["kitty"], &[("--directory", false)]),
+    (&["alacritty"], &[("--working-directory", false)]),
+];
+
+// 有多种写法时调用 `probe` 探测，取帮助中列出的第一个参数，探测不到时使用首选写法；
+// 只有一种写法时直接使用，不运行终端。未知终端依赖进程的 current_dir。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn linux_terminal_directory_arguments(
+    name: &str,
+    path: &str,
+    probe: impl FnOnce() -> TerminalFlagSet,
+) -> Vec<String> {
+    if name == "wezterm" {
+        return vec!["start".to_string(), "--cwd".to_string(), path.to_string()];
+    }
+    let Some((_, candidates)) = LINUX_TERMINAL_DIRECTORY_FLAGS
+        .iter()
+        .find(|(names, _)| names.contains(&name))
+    else {
+        return Vec::new();
+    };
+    let (flag, joined) = if candidates.len() > 1 {
+        let flags = probe();
+        candidates
+            .iter()
+            .find(|(flag, _)| flags.supports(flag))
+            .unwrap_or(&candidates[0])
+    } else {
+        &candidates[0]
+    };
+    if *joined {
+        vec![format!("{flag}={path}")]
+    } else {
+        vec![flag.to_string(), path.to_string()]
+    }
+}
+
+// 等待终端输出帮助信息的时长。
+const TERMINAL_HELP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 终端帮助信息中列出的长参数，见 [`probe_terminal_flags`]。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TerminalFlagSet {
+    long_options: HashSet<String>,
+}
+
+impl TerminalFlagSet {
+    /// 帮助信息中是否列出了该长参数，例如 `--working-directory`。
+    pub fn supports(&self, flag: &str) -> bool {
+        self.long_options.contains(flag)
+    }
+}
+
+static TERMINAL_FLAGS: OnceLock<Mutex<HashMap<String, TerminalFlagSet>>> = OnceLock::new();
+
+fn terminal_flags_cache() -> MutexGuard<'static, HashMap<String, TerminalFlagSet>> {
+    TERMINAL_FLAGS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| {
+            log::warn!("终端参数缓存锁异常，继续使用现有数据");
+            poisoned.into_inner()
+        })
+}
+
+/// 运行 `<终端> --help` 收集其支持的长参数，结果按命令缓存。
+/// gnome-terminal 等只在 `--help-all` 中列出全部参数，帮助中提到它时一并读取。
+/// 尽力而为：终端无法启动、超时或不支持 `--help` 时返回空集合。
+pub fn probe_terminal_flags(command_path: &str) -> TerminalFlagSet {
+    if let Some(flags) = terminal_flags_cache().get(command_path) {
+        return flags.clone();
+    }
+    let mut help = read_terminal_help(command_path, "--help").unwrap_or_default();
+    if help.contains("--help-all") {
+        help.push_str(&read_terminal_help(command_path, "--help-all").unwrap_or_default());
+    }
+    let flags = parse_help_long_options(&help);
+    terminal_flags_cache().insert(command_path.to_string(), flags.clone());
+    flags
+}
+
+fn read_terminal_help(command_path: &str, flag: &str) -> Option<String> {
+    let child = Command::new(command_path)
+        .arg(flag)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    read_output_with_timeout(child, TERMINAL_HELP_TIMEOUT).ok()
+}
+
+// 帮助信息中的长参数形如 `--working-directory=DIRNAME` 或 `--workdir <dir>`，
+// 只取 `--` 后由字母、数字与连字符组成的名称。
+fn parse_help_long_options(help: &str) -> TerminalFlagSet {
+    let long_options = help
+        .split(|ch: char| ch.is_whitespace() || matches!(ch, ',' | '[' | '(' | '"' | '\''))
+        .filter_map(|token| token.strip_prefix("--"))
+        .map(|name| {
+            name.split(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '-'))
+                .next()
+                .unwrap_or_default()
+        })
+        .filter(|name| {
+            name.chars()
+                .next()
+                .is_some_and(|ch| ch.is_ascii_alphanumeric())
+        })
+        .map(|name| format!("--{name}"))
+        .collect();
+    TerminalFlagSet { long_options }
+}
+
+// 各终端指定启动程序的参数不同，未知终端按 xterm 的 `-e` 处理。
+#[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+fn linux_terminal_exec_arguments(name: &str, program: &[String]) -> Vec<String> {
+    let prefix: &[&str] = match name {
+        "gnome-terminal" | "wezterm" => &["--"],
+        "xfce4-terminal" | "mate-terminal" => &["-x"],
+        "kitty" | "foot" => &[],
+        _ => &["-e"],
+    };
+    prefix
+        .iter()
+        .map(|value| value.to_string())
+        .chain(program.iter().cloned())
+        .collect()
+}
+
+/// 启动进程后不等待其退出，由后台线程回收子进程。
+fn spawn_detached(command: &mut Command) -> Result<(), std::io::Error> {
+    let mut child = command.spawn()?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_existing_returns_first_present_candidate_in_order() {
+        let root = std::env::temp_dir().join(format!("devhaven-system-{}", uuid::Uuid::new_v4()));
+        let missing = root.join("missing");
+        let dir = root.join("present-dir");
+        let file = root.join("present-file");
+        fs::create_dir_all(&dir).expect("create present dir");
+        fs::write(&file, "").expect("create present file");
+
+        assert_eq!(
+            first_existing([missing.clone(), file.clone(), dir.clone()]),
+            Some(file.clone())
+        );
+        assert_eq!(
+            first_existing([missing.clone(), dir.clone(), file]),
+            Some(dir)
+        );
+        assert_eq!(first_existing([missing]), None);
+        assert_eq!(first_existing(Vec::new()), None);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn find_single_workspace_file_requires_exactly_one() {
+        let root = std::env::temp_dir().join(format!("devhaven-system-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(root.join("nested.code-workspace")).expect("create dir");
+        fs::write(root.join("README.md"), "").expect("create readme");
+        assert_eq!(find_single_workspace_file(&root), None);
+
+        let workspace = root.join("app.code-workspace");
+        fs::write(&workspace, "{}").expect("create workspace");
+        assert_eq!(find_single_workspace_file(&root), Some(workspace));
+
+        fs::write(root.join("other.code-workspace"), "{}").expect("create workspace");
+        assert_eq!(find_single_workspace_file(&root), None);
+        assert_eq!(find_single_workspace_file(&root.join("missing")), None);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    fn editor_params(command_path: Option<&str>, arguments: &[&str]) -> EditorOpenParams {
+        EditorOpenParams {
+            path: "/work/project".to_string(),
+            command_path: command_path.map(str::to_string),
+            arguments: Some(arguments.iter().map(|value| value.to_string()).collect()),
+            ..EditorOpenParams::default()
+        }
+    }
+
+    #[test]
+    fn normalize_input_path_rejects_only_blank_paths() {
+        assert!(normalize_input_path("").is_err());
+        assert!(normalize_input_path(" \t\n").is_err());
+        assert_eq!(
+            normalize_input_path(" /work/project "),
+            Ok("/work/project".to_string())
+        );
+
+        let blank_editor = editor_params(Some("/usr/bin/code"), &["{path}"]);
+        assert!(open_in_editor(EditorOpenParams {
+            path: " ".to_string(),
+            ..blank_editor
+        })
+        .is_err());
+        assert!(open_in_finder("", false).is_err());
+        assert_eq!(
+            open_many_in_editor(
+                vec![" ".to_string()],
+                preset("code"),
+                true,
+                Priority::Normal
+            ),
+            vec![Err("路径不能为空".to_string())]
+        );
+    }
+
+    fn test_var(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/me".to_string()),
+            "USERPROFILE" => Some("C:\\Users\\me".to_string()),
+            "A_1" => Some("x".to_string()),
+            _ => None,
+        }
+    }
+
+    fn test_user_home(user: &str) -> Option<String> {
+        (user == "alice").then(|| "/home/alice".to_string())
+    }
+
+    #[test]
+    fn xdg_base_dir_honors_overrides_and_falls_back_to_home() {
+        let vars = |overrides: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                overrides
+                    .iter()
+                    .chain([("HOME", "/home/me")].iter())
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let overridden = vars(&[
+            ("XDG_CONFIG_HOME", "/xdg/config"),
+            ("XDG_DATA_HOME", "/xdg/data"),
+            ("XDG_CACHE_HOME", "/xdg/cache"),
+        ]);
+        assert_eq!(
+            xdg_base_dir("XDG_CONFIG_HOME", ".config", overridden),
+            Some(PathBuf::from("/xdg/config"))
+        );
+        assert_eq!(
+            xdg_base_dir("XDG_DATA_HOME", ".local/share", overridden),
+            Some(PathBuf::from("/xdg/data"))
+        );
+        assert_eq!(
+            xdg_base_dir("XDG_CACHE_HOME", ".cache", overridden),
+            Some(PathBuf::from("/xdg/cache"))
+        );
+
+        // 空值与相对路径按规范视为未设置。
+        let invalid = vars(&[("XDG_CONFIG_HOME", ""), ("XDG_DATA_HOME", "relative/data")]);
+        assert_eq!(
+            xdg_base_dir("XDG_CONFIG_HOME", ".config", invalid),
+            Some(PathBuf::from("/home/me/.config"))
+        );
+        assert_eq!(
+            xdg_base_dir("XDG_DATA_HOME", ".local/share", invalid),
+            Some(PathBuf::from("/home/me/.local/share"))
+        );
+        assert_eq!(
+            xdg_base_dir("XDG_CACHE_HOME", ".cache", invalid),
+            Some(PathBuf::from("/home/me/.cache"))
+        );
+        assert_eq!(xdg_base_dir("XDG_CACHE_HOME", ".cache", |_| None), None);
+    }
+
+    #[test]
+    fn expand_unix_path_expands_tilde_and_variables() {
+        let expand = |path| expand_unix_path(path, test_var, test_user_home);
+        assert_eq!(expand("~"), "/home/me");
+        assert_eq!(expand("~/projects"), "/home/me/projects");
+        assert_eq!(expand("~alice/src"), "/home/alice/src");
+        assert_eq!(expand("~bob/src"), "~bob/src");
+        assert_eq!(expand("$HOME/x"), "/home/me/x");
+        assert_eq!(expand("${HOME}x/$A_1"), "/home/mex/x");
+        assert_eq!(expand("/p/$UNKNOWN/${NOPE}/$"), "/p/$UNKNOWN/${NOPE}/$");
+        assert_eq!(expand("/p/${HOME"), "/p/${HOME");
+        assert_eq!(expand("/p/a~b"), "/p/a~b");
+    }
+
+    #[test]
+    fn expand_windows_variables_expands_percent_names() {
+        assert_eq!(
+            expand_windows_variables("%USERPROFILE%\\x", test_var),
+            "C:\\Users\\me\\x"
+        );
+        assert_eq!(
+            expand_windows_variables("C:\\%UNKNOWN%\\100%", test_var),
+            "C:\\%UNKNOWN%\\100%"
+        );
+        assert_eq!(
+            expand_windows_variables("%%USERPROFILE%", test_var),
+            "%C:\\Users\\me"
+        );
+    }
+
+    #[test]
+    fn absolute_path_string_resolves_relative_paths() {
+        let cwd = std::env::current_dir().expect("current dir");
+        assert_eq!(
+            absolute_path_string("src"),
+            cwd.join("src").to_string_lossy()
+        );
+        let absolute = cwd.join("a b").to_string_lossy().to_string();
+        assert_eq!(absolute_path_string(&absolute), absolute);
+    }
+
+    #[test]
+    fn editor_launch_flags_add_safe_mode_only_for_capable_editors() {
+        let mut code = editor_params(Some("/usr/bin/code"), &["{path}"]);
+        code.safe_mode = Some(true);
+        let mut vim = editor_params(Some("/usr/bin/vim"), &["{path}"]);
+        vim.safe_mode = Some(true);
+
+        let flags = editor_launch_flags(resolve_editor_family(&code), &code);
+        assert_eq!(flags, ["--disable-extensions"]);
+        assert!(editor_launch_flags(resolve_editor_family(&vim), &vim).is_empty());
+
+        let mut arguments = vec!["/p".to_string()];
+        insert_editor_flags("/usr/bin/code", &mut arguments, &flags);
+        assert_eq!(arguments, ["--disable-extensions", "/p"]);
+    }
+
+    #[test]
+    fn locale_maps_to_vscode_flag_and_jvm_options() {
+        let mut code = editor_params(Some("/usr/bin/code"), &["{path}"]);
+        code.locale = Some("zh-CN".to_string());
+        assert_eq!(
+            editor_launch_flags(EditorFamily::VsCode, &code),
+            ["--locale", "zh-CN"]
+        );
+        assert!(editor_launch_env(EditorFamily::VsCode, &code).is_empty());
+        assert!(editor_launch_flags(EditorFamily::JetBrains, &code).is_empty());
+        assert!(editor_launch_flags(EditorFamily::Other, &code).is_empty());
+
+        assert_eq!(
+            jvm_locale_options("zh-Hans-cn"),
+            "-Duser.language=zh -Duser.country=CN"
+        );
+        assert_eq!(jvm_locale_options("de"), "-Duser.language=de");
+    }
+
+    #[test]
+    fn jdk_path_sets_product_specific_env_for_jetbrains() {
+        let root = std::env::temp_dir().join(format!("devhaven-jdk-{}", uuid::Uuid::new_v4()));
+        let bundle_home = root.join("temurin-21.jdk").join("Contents").join("Home");
+        fs::create_dir_all(bundle_home.join("bin")).expect("create jdk");
+        fs::write(bundle_home.join("bin").join("java"), "").expect("write java");
+        let bundle = root.join("temurin-21.jdk").to_string_lossy().to_string();
+        let home = bundle_home.to_string_lossy().to_string();
+
+        assert_eq!(resolve_jdk_home(&bundle), Ok(home.clone()));
+        assert_eq!(resolve_jdk_home(&home), Ok(home.clone()));
+        assert!(resolve_jdk_home(&root.to_string_lossy()).is_err());
+
+        let mut pycharm = editor_params(Some("/opt/pycharm/bin/pycharm.sh"), &["{path}"]);
+        pycharm.preset_id = Some("pycharm".to_string());
+        pycharm.jdk_path = Some(home.clone());
+        assert_eq!(
+            editor_launch_env(EditorFamily::JetBrains, &pycharm),
+            [("PYCHARM_JDK".to_string(), home.clone())]
+        );
+        assert!(editor_launch_env(EditorFamily::VsCode, &pycharm).is_empty());
+        assert_eq!(jetbrains_jdk_env_var("idea"), "IDEA_JDK");
+        assert_eq!(jetbrains_jdk_env_var("webstorm"), "WEBIDE_JDK");
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn temp_profile_flag_precedes_path_and_rejects_explicit_profile() {
+        let mut params = editor_params(Some("/usr/bin/code"), &["{path}"]);
+        params.temp_profile = Some(true);
+        let flags = editor_launch_flags(EditorFamily::VsCode, &params);
+        assert_eq!(flags, ["--profile-temp"]);
+        assert!(editor_launch_flags(EditorFamily::JetBrains, &params).is_empty());
+        let mut arguments = build_command_arguments(params.arguments.clone(), "/p");
+        insert_editor_flags("/usr/bin/code", &mut arguments, &flags);
+        assert_eq!(arguments, ["--profile-temp", "/p"]);
+        assert!(!has_explicit_profile(&params));
+
+        params.extra_arguments = Some(vec!["--profile=Work".to_string()]);
+        assert!(has_explicit_profile(&params));
+        params.extra_arguments = None;
+        params.arguments = Some(vec!["--profile".to_string(), "Work".to_string()]);
+        assert!(has_explicit_profile(&params));
+        params.arguments = Some(vec!["--profile-temp".to_string()]);
+        assert!(!has_explicit_profile(&params));
+    }
+
+    #[test]
+    fn inject_vscode_setting_inserts_without_overriding() {
+        let root = std::env::temp_dir().join(format!("devhaven-system-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("create root");
+        let settings = root.join(".vscode/settings.json");
+        let read = || fs::read_to_string(&settings).expect("read");
+
+        assert_eq!(
+            inject_vscode_setting(&root, VSCODE_INTERPRETER_SETTING, "/p/.venv/bin/python")
+                .expect("inject"),
+            None
+        );
+        let written: serde_json::Value = serde_json::from_str(&read()).expect("json");
+        assert_eq!(written[VSCODE_INTERPRETER_SETTING], "/p/.venv/bin/python");
+        assert_eq!(
+            inject_vscode_setting(&root, VSCODE_INTERPRETER_SETTING, "/p/.venv/bin/python")
+                .expect("inject"),
+            None
+        );
+        let warning = inject_vscode_setting(&root, VSCODE_INTERPRETER_SETTING, "/other/python")
+            .expect("inject")
+            .expect("warning");
+        assert!(warning.contains("/p/.venv/bin/python") && warning.contains("/other/python"));
+        let kept: serde_json::Value = serde_json::from_str(&read()).expect("json");
+        assert_eq!(kept[VSCODE_INTERPRETER_SETTING], "/p/.venv/bin/python");
+
+        fs::write(
+            &settings,
+            "{\n  // 格式化\n  \"editor.formatOnSave\": true\n}",
+        )
+        .expect("write");
+        assert_eq!(
+            inject_vscode_setting(&root, VSCODE_THEME_SETTING, "Dark+").expect("inject"),
+            None
+        );
+        assert_eq!(
+            read(),
+            "{\n  \"workbench.colorTheme\": \"Dark+\",\n  // 格式化\n  \"editor.formatOnSave\": true\n}"
+        );
+
+        fs::write(&settings, "{}").expect("write");
+        inject_vscode_setting(&root, VSCODE_THEME_SETTING, "Dark+").expect("inject");
+        let inserted: serde_json::Value = serde_json::from_str(&read()).expect("json");
+        assert_eq!(inserted[VSCODE_THEME_SETTING], "Dark+");
+
+        // 被注释掉的配置不算已设置；注释与末尾逗号都能识别。
+        fs::write(
+            &settings,
+            "// 主题\n{\n  // \"workbench.colorTheme\": \"Light\",\n  /* 字体 */ \"editor.fontSize\": 14,\n}",
+        )
+        .expect("write");
+        assert_eq!(
+            inject_vscode_setting(&root, VSCODE_THEME_SETTING, "Dark+").expect("inject"),
+            None
+        );
+        let updated = read();
+        assert!(updated.starts_with("// 主题\n{\n  \"workbench.colorTheme\": \"Dark+\","));
+        let parsed: serde_json::Value =
+            serde_json::from_str(&strip_jsonc(&updated)).expect("jsonc");
+        assert_eq!(parsed[VSCODE_THEME_SETTING], "Dark+");
+        assert_eq!(parsed["editor.fontSize"], 14);
+
+        fs::write(&settings, "[]").expect("write");
+        assert!(inject_vscode_setting(&root, VSCODE_THEME_SETTING, "Dark+")
+            .expect("inject")
+            .is_some());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn strip_jsonc_keeps_strings_and_offsets() {
+        let text = "{\"url\": \"http://a/*b*/\", // 说明\n \"list\": [1, 2,],}";
+        let stripped = strip_jsonc(text);
+        assert_eq!(stripped.len(), text.len());
+        let parsed: serde_json::Value = serde_json::from_str(&stripped).expect("json");
+        assert_eq!(parsed["url"], "http://a/*b*/");
+        assert_eq!(parsed["list"], serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn wrap_command_prefixes_container_entry() {
+        let wrapper: Vec<String> = ["distrobox", "enter", "dev", "--"]
+            .iter()
+            .map(|part| part.to_string())
+            .collect();
+        assert!(validate_wrapper(&wrapper).is_ok());
+        assert_eq!(
+            wrap_command(&wrapper, "code", vec!["/p".to_string()]),
+            (
+                "distrobox".to_string(),
+                vec![
+                    "enter".to_string(),
+                    "dev".to_string(),
+                    "--".to_string(),
+                    "code".to_string(),
+                    "/p".to_string()
+                ]
+            )
+        );
+        assert!(validate_wrapper(&[]).is_err());
+        assert!(validate_wrapper(&[" ".to_string(), "run".to_string()]).is_err());
+    }
+
+    #[test]
+    fn new_window_flag_is_added_only_for_vscode() {
+        let mut params = editor_params(Some("/usr/bin/code"), &["{path}"]);
+        params.new_window = Some(true);
+        assert_eq!(
+            editor_launch_flags(EditorFamily::VsCode, &params),
+            ["--new-window"]
+        );
+        assert!(editor_launch_flags(EditorFamily::JetBrains, &params).is_empty());
+        assert!(editor_launch_flags(EditorFamily::Other, &params).is_empty());
+
+        params.new_window = None;
+        assert!(editor_launch_flags(EditorFamily::VsCode, &params).is_empty());
+    }
+
+    #[test]
+    fn read_only_flag_depends_on_editor() {
+        let mut vim = editor_params(Some("/usr/bin/nvim"), &["{path}"]);
+        vim.read_only = Some(true);
+        assert_eq!(
+            editor_launch_flags(resolve_editor_family(&vim), &vim),
+            ["-R"]
+        );
+
+        let mut code = editor_params(Some("/usr/bin/code"), &["{path}"]);
+        code.read_only = Some(true);
+        assert!(editor_launch_flags(resolve_editor_family(&code), &code).is_empty());
+
+        let preset = |command_path: &str| DevToolPreset {
+            id: "custom".to_string(),
+            name: String::new(),
+            command_path: command_path.to_string(),
+            arguments: vec!["{path}".to_string()],
+            success_exit_codes: None,
+            kind: PresetKind::Command,
+            install_kind: InstallKind::default(),
+        };
+        assert!(supports_read_only(&preset("/usr/bin/vim")));
+        assert!(supports_read_only(&preset("C:\\Tools\\nano.exe")));
+        assert!(!supports_read_only(&preset("/usr/bin/code")));
+        assert!(!supports_read_only(&preset("/usr/bin/subl")));
+    }
+
+    #[test]
+    fn restore_session_false_opens_a_fresh_vscode_window() {
+        let mut params = editor_params(Some("/usr/bin/code"), &["{path}"]);
+        params.restore_session = Some(false);
+        assert_eq!(
+            editor_launch_flags(EditorFamily::VsCode, &params),
+            ["--new-window"]
+        );
+        assert!(editor_launch_flags(EditorFamily::JetBrains, &params).is_empty());
+
+        params.new_window = Some(true);
+        assert_eq!(
+            editor_launch_flags(EditorFamily::VsCode, &params),
+            ["--new-window"]
+        );
+
+        params.new_window = None;
+        params.restore_session = Some(true);
+        assert!(editor_launch_flags(EditorFamily::VsCode, &params).is_empty());
+    }
+
+    #[test]
+    fn validate_locale_accepts_bcp47_like_tags() {
+        for locale in ["en", "zh-CN", "zh-Hans-CN", "es-419", "pt-br"] {
+            assert!(validate_locale(locale).is_ok(), "{locale}");
+        }
+        for locale in ["", "e", "english", "zh_CN", "zh-", "en-US;rm", "--locale"] {
+            assert!(validate_locale(locale).is_err(), "{locale}");
+        }
+    }
+
+    #[test]
+    fn append_extra_arguments_expands_path_after_preset_arguments() {
+        let mut arguments = build_command_arguments(Some(vec!["-n".to_string()]), "/p");
+        let extra = vec!["--wait".to_string(), "--folder-uri={path}".to_string()];
+        append_extra_arguments("/usr/bin/code", &mut arguments, Some(&extra), "/p");
+        assert_eq!(arguments, ["-n", "/p", "--wait", "--folder-uri=/p"]);
+
+        let mut unchanged = vec!["/p".to_string()];
+        append_extra_arguments("/usr/bin/code", &mut unchanged, Some(&[]), "/p");
+        append_extra_arguments("/usr/bin/code", &mut unchanged, None, "/p");
+        assert_eq!(unchanged, ["/p"]);
+    }
+
+    #[test]
+    fn apply_path_kind_flag_marks_folders_and_files_for_vscode() {
+        let root = std::env::temp_dir().join(format!("devhaven-system-{}", uuid::Uuid::new_v4()));
+        let file = root.join("main.rs");
+        fs::create_dir_all(&root).expect("create dir");
+        fs::write(&file, "").expect("create file");
+        let root_path = root.to_string_lossy().to_string();
+        let file_path = file.to_string_lossy().to_string();
+        let vscode = editor_capabilities(EditorFamily::VsCode);
+
+        let mut arguments = vec!["-n".to_string(), root_path.clone()];
+        apply_path_kind_flag(&mut arguments, &root_path, vscode);
+        assert_eq!(arguments, ["-n", "--folder-uri", &file_uri(&root_path)]);
+
+        let mut arguments = vec![file_path.clone()];
+        apply_path_kind_flag(&mut arguments, &file_path, vscode);
+        assert_eq!(arguments, ["--file-uri", &file_uri(&file_path)]);
+
+        let mut arguments = vec![root_path.clone()];
+        apply_path_kind_flag(
+            &mut arguments,
+            &root_path,
+            editor_capabilities(EditorFamily::JetBrains),
+        );
+        assert_eq!(arguments, [root_path.as_str()]);
+
+        let workspace = root.join("app.code-workspace");
+        fs::write(&workspace, "{}").expect("create workspace");
+        let workspace_path = workspace.to_string_lossy().to_string();
+        let mut arguments = vec![workspace_path.clone()];
+        apply_path_kind_flag(&mut arguments, &workspace_path, vscode);
+        assert_eq!(arguments, [workspace_path.as_str()]);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn file_uri_encodes_paths() {
+        assert_eq!(file_uri("/home/me/my app"), "file:///home/me/my%20app");
+        assert_eq!(file_uri("C:\\work\\a#b"), "file:///C:/work/a%23b");
+        assert_eq!(file_uri("/tmp/中"), "file:///tmp/%E4%B8%AD");
+        assert_eq!(
+            file_uri("\\\\server\\share\\a b"),
+            "file://server/share/a%20b"
+        );
+        assert_eq!(file_uri("\\\\?\\UNC\\server\\share"), "file://server/share");
+        assert_eq!(file_uri("\\\\?\\C:\\work"), "file:///C:/work");
+    }
+
+    #[test]
+    fn install_kind_from_path_recognizes_install_locations() {
+        let cases = [
+            (
+                "C:\\Users\\dev\\AppData\\Local\\JetBrains\\Toolbox\\apps\\IDEA-U\\ch-0\\241\\bin\\idea64.exe",
+                Some(InstallKind::JetBrainsToolbox),
+            ),
+            (
+                "/Users/dev/Applications/JetBrains Toolbox/IntelliJ IDEA Ultimate.app",
+                Some(InstallKind::JetBrainsToolbox),
+            ),
+            (
+                "C:\\Users\\dev\\scoop\\apps\\vscode\\current\\Code.exe",
+                Some(InstallKind::Scoop),
+            ),
+            (
+                "C:\\Users\\dev\\AppData\\Local\\Microsoft\\WindowsApps\\code.exe",
+                Some(InstallKind::MicrosoftStore),
+            ),
+            (
+                "C:\\ProgramData\\chocolatey\\bin\\code.exe",
+                Some(InstallKind::PathShim),
+            ),
+            ("/snap/bin/code", Some(InstallKind::Snap)),
+            (
+                "/var/lib/flatpak/exports/bin/com.visualstudio.code",
+                Some(InstallKind::Flatpak),
+            ),
+            ("/Applications/Zed.app", Some(InstallKind::AppBundle)),
+            ("/usr/bin/code", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(install_kind_from_path(path), expected, "{path}");
+        }
+    }
+
+    #[test]
+    fn classify_vscode_shims_swaps_crossed_commands() {
+        let crossed = |command: &str| Some(command == "/usr/bin/code");
+        assert_eq!(
+            classify_vscode_shims(
+                Some("/usr/bin/code".to_string()),
+                Some("/usr/bin/code-insiders".to_string()),
+                crossed,
+            ),
+            (
+                Some("/usr/bin/code-insiders".to_string()),
+                Some("/usr/bin/code".to_string())
+            )
+        );
+        assert_eq!(
+            classify_vscode_shims(Some("/usr/bin/code".to_string()), None, crossed),
+            (None, Some("/usr/bin/code".to_string()))
+        );
+        assert_eq!(
+            classify_vscode_shims(
+                Some("/usr/bin/code".to_string()),
+                Some("/usr/bin/code-insiders".to_string()),
+                |_| None,
+            ),
+            (
+                Some("/usr/bin/code".to_string()),
+                Some("/usr/bin/code-insiders".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn is_insiders_version_output_reads_first_line() {
+        assert_eq!(
+            is_insiders_version_output("1.96.0-insider\nabc123\nx64\n"),
+            Some(true)
+        );
+        assert_eq!(
+            is_insiders_version_output("1.95.3\nabc123\nx64\n"),
+            Some(false)
+        );
+        assert_eq!(is_insiders_version_output(""), None);
+    }
+
+    #[test]
+    fn vscode_workspace_id_hashes_fs_path_with_salt() {
+        assert_eq!(
+            vscode_workspace_id("/work/app", Some("42")),
+            md5_hex(b"/work/app42")
+        );
+        assert_eq!(
+            vscode_workspace_id("/work/app", None),
+            md5_hex(b"/work/app")
+        );
+
+        assert_eq!(vscode_fs_path("/work/app/", false), "/work/app");
+        assert_eq!(vscode_fs_path("/", false), "/");
+        assert_eq!(vscode_fs_path("C:/Work/App/", true), "c:\\Work\\App");
+        assert_eq!(vscode_fs_path("/C:/Work/App", true), "c:\\Work\\App");
+        assert_eq!(vscode_fs_path("D:\\", true), "d:\\");
+    }
+
+    #[test]
+    fn workspace_folder_matches_decodes_folder_uri() {
+        let unix = r#"{"folder":"file:///work/my%20app"}"#;
+        assert!(workspace_folder_matches(unix, "/work/my app", false));
+        assert!(!workspace_folder_matches(unix, "/work/other", false));
+        assert!(!workspace_folder_matches(
+            r#"{"folder":"vscode-remote://ssh-remote+host/work/my%20app"}"#,
+            "/work/my app",
+            false
+        ));
+        assert!(!workspace_folder_matches(
+            r#"{"workspace":"file:///work/a.code-workspace"}"#,
+            "/work/a.code-workspace",
+            false
+        ));
+        assert!(workspace_folder_matches(
+            r#"{"folder":"file:///c%3A/Work/App"}"#,
+            "c:\\work\\app",
+            true
+        ));
+    }
+
+    #[test]
+    fn validate_workspace_storage_dir_requires_matching_child() {
+        let root = std::env::temp_dir().join(format!("devhaven-storage-{}", uuid::Uuid::new_v4()));
+        let storage_root = root.join("workspaceStorage");
+        let id = vscode_workspace_id("/work/app", Some("1"));
+        let storage_dir = storage_root.join(&id);
+        fs::create_dir_all(&storage_dir).expect("create storage dir");
+        fs::write(
+            storage_dir.join("workspace.json"),
+            r#"{"folder":"file:///work/app"}"#,
+        )
+        .expect("write workspace.json");
+
+        assert!(validate_workspace_storage_dir(&storage_root, &storage_dir, "/work/app").is_ok());
+        assert!(
+            validate_workspace_storage_dir(&storage_root, &storage_dir, "/work/other").is_err()
+        );
+        assert!(validate_workspace_storage_dir(&root, &storage_dir, "/work/app").is_err());
+        let not_hash = storage_root.join("not-a-hash");
+        fs::create_dir_all(&not_hash).expect("create dir");
+        assert!(validate_workspace_storage_dir(&storage_root, &not_hash, "/work/app").is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn resolve_editor_config_dir_picks_latest_jetbrains_version() {
+        let root = std::env::temp_dir().join(format!("devhaven-config-{}", uuid::Uuid::new_v4()));
+        for dir in [
+            "Code/User",
+            "JetBrains/PyCharm2023.3",
+            "JetBrains/PyCharm2024.1",
+            "JetBrains/PyCharmCE2025.1",
+            "JetBrains/GoLand2024.1.backup",
+        ] {
+            fs::create_dir_all(root.join(dir)).expect("create dir");
+        }
+
+        assert_eq!(
+            resolve_editor_config_dir("vscode", &root),
+            Ok(root.join("Code").join("User"))
+        );
+        assert_eq!(
+            resolve_editor_config_dir("pycharm", &root),
+            Ok(root.join("JetBrains/PyCharmCE2025.1"))
+        );
+        assert!(resolve_editor_config_dir("goland", &root).is_err());
+        assert!(resolve_editor_config_dir("vscode-insiders", &root).is_err());
+        assert!(resolve_editor_config_dir("unknown", &root).is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn resolve_editor_family_detects_known_editors() {
+        let idea = editor_params(Some("C:\\JetBrains\\bin\\idea64.exe"), &["{path}"]);
+        let code = editor_params(Some("/usr/bin/code"), &["{path}"]);
+        let mac_idea = editor_params(Some("/usr/bin/open"), &["-a", "IntelliJ IDEA", "{path}"]);
+        let unknown = editor_params(Some("/usr/bin/vim"), &["{path}"]);
+        let mut by_id = editor_params(Some("/opt/custom/launcher"), &[]);
+        by_id.preset_id = Some("pycharm".to_string());
+
+        assert_eq!(resolve_editor_family(&idea), EditorFamily::JetBrains);
+        assert_eq!(resolve_editor_family(&code), EditorFamily::VsCode);
+        assert_eq!(resolve_editor_family(&mac_idea), EditorFamily::JetBrains);
+        assert_eq!(resolve_editor_family(&unknown), EditorFamily::Other);
+        assert_eq!(resolve_editor_family(&by_id), EditorFamily::JetBrains);
+    }
+
+    #[test]
+    fn editor_url_scheme_uri_encodes_path_and_line() {
+        let mut params = EditorOpenParams {
+            path: "/p/my app".to_string(),
+            preset_id: Some("vscode".to_string()),
+            ..EditorOpenParams::default()
+        };
+        assert_eq!(
+            editor_url_scheme_uri(EditorFamily::VsCode, &params).as_deref(),
+            Some("vscode://file/p/my%20app")
+        );
+
+        params.file = Some("/p/my app/main.rs".to_string());
+        params.line = Some(7);
+        params.preset_id = Some("vscode-insiders".to_string());
+        assert_eq!(
+            editor_url_scheme_uri(EditorFamily::VsCode, &params).as_deref(),
+            Some("vscode-insiders://file/p/my%20app/main.rs:7")
+        );
+
+        params.preset_id = None;
+        params.command_path = Some("/usr/local/bin/pycharm".to_string());
+        assert_eq!(
+            editor_url_scheme_uri(EditorFamily::JetBrains, &params).as_deref(),
+            Some("pycharm://open?file=/p/my%20app/main.rs&line=7")
+        );
+
+        params.command_path = Some("vim".to_string());
+        assert_eq!(editor_url_scheme_uri(EditorFamily::Other, &params), None);
+    }
+
+    #[test]
+    fn parse_vscode_profiles_keeps_default_first() {
+        let content = r#"{
+            "theme": "vs-dark",
+            "userDataProfiles": [
+                {"location": "-1a2b3c", "name": "Work", "icon": "briefcase"},
+                {"location": "-4d5e6f", "name": " Rust "},
+                {"location": "-7a8b9c", "name": ""},
+                {"location": "-0d1e2f", "name": "Work"}
+            ]
+        }"#;
+        assert_eq!(
+            parse_vscode_profiles(content).expect("parse"),
+            ["Default", "Work", "Rust"]
+        );
+        assert_eq!(
+            parse_vscode_profiles(r#"{"theme": "vs-dark"}"#).expect("parse"),
+            ["Default"]
+        );
+        assert!(parse_vscode_profiles("{").is_err());
+    }
+
+    #[test]
+    fn volume_mount_point_detects_external_volumes() {
+        assert_eq!(
+            volume_mount_point(Path::new("/Volumes/NAS/projects/app")),
+            Some(PathBuf::from("/Volumes/NAS"))
+        );
+        assert_eq!(
+            volume_mount_point(Path::new("/Volumes/My Disk")),
+            Some(PathBuf::from("/Volumes/My Disk"))
+        );
+        assert_eq!(volume_mount_point(Path::new("/Volumes")), None);
+        assert_eq!(volume_mount_point(Path::new("/Users/me/Volumes/x")), None);
+        assert_eq!(volume_mount_point(Path::new("Volumes/x")), None);
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn apply_priority_sets_child_niceness() {
+        assert_eq!(unix_niceness(Priority::Normal), None);
+        assert_eq!(unix_niceness(Priority::High), Some(-5));
+
+        let mut command = Command::new("/bin/sh");
+        command.args(["-c", "nice"]);
+        apply_priority(&mut command, Priority::Low);
+        let output = command.output().expect("run nice");
+        let niceness: i32 = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .expect("parse niceness");
+        assert!(niceness >= 10, "niceness = {niceness}");
+    }
+
+    #[test]
+    fn parse_proc_mounts_keeps_real_filesystems() {
+        let content = "\
+/dev/nvme0n1p2 / ext4 rw,relatime 0 0
+proc /proc proc rw,nosuid 0 0
+sysfs /sys sysfs rw,nosuid 0 0
+tmpfs /run tmpfs rw,nosuid 0 0
+/dev/nvme0n1p1 /boot/efi vfat rw 0 0
+/dev/loop3 /snap/core/1 squashfs ro 0 0
+/dev/sdb1 /run/media/me/My\\040Disk exfat rw 0 0
+gvfsd-fuse /run/user/1000/gvfs fuse.gvfsd-fuse rw 0 0
+nas:/export /mnt/nas nfs4 rw 0 0
+/dev/nvme0n1p3 /home ext4 rw 0 0
+/dev/nvme0n1p3 /home ext4 rw 0 0
+";
+        assert_eq!(
+            parse_proc_mounts(content),
+            ["/", "/run/media/me/My Disk", "/mnt/nas", "/home"]
+        );
+    }
+
+    #[test]
+    fn cloud_provider_for_matches_sync_roots() {
+        let roots = vec![
+            (
+                CloudProvider::ICloud,
+                PathBuf::from("/Users/me/Library/Mobile Documents"),
+            ),
+            (CloudProvider::Dropbox, PathBuf::from("/Users/me/Dropbox")),
+        ];
+        assert_eq!(
+            cloud_provider_for(
+                Path::new("/Users/me/Library/Mobile Documents/com~apple~CloudDocs/app"),
+                &roots
+            ),
+            Some(CloudProvider::ICloud)
+        );
+        assert_eq!(
+            cloud_provider_for(Path::new("/Users/me/Dropbox/work/app"), &roots),
+            Some(CloudProvider::Dropbox)
+        );
+        assert_eq!(
+            cloud_provider_for(Path::new("/Users/me/Dropbox2/app"), &roots),
+            None
+        );
+
+        let info = r#"{"personal": {"path": "/Users/me/Dropbox", "host": 1},
+            "business": {"path": "/Users/me/Dropbox (Acme)"}}"#;
+        let mut dirs = parse_dropbox_info(info);
+        dirs.sort();
+        assert_eq!(
+            dirs,
+            [
+                PathBuf::from("/Users/me/Dropbox"),
+                PathBuf::from("/Users/me/Dropbox (Acme)")
+            ]
+        );
+        assert!(parse_dropbox_info("not json").is_empty());
+    }
+
+    #[test]
+    fn parse_drive_strings_splits_nul_separated_list() {
+        let buffer: Vec<u16> = "C:\\\0D:\\\0\0".encode_utf16().collect();
+        assert_eq!(parse_drive_strings(&buffer), ["C:\\", "D:\\"]);
+        assert!(parse_drive_strings(&[0]).is_empty());
+    }
+
+    #[test]
+    fn automation_denied_app_detects_apple_event_permission_errors() {
+        assert_eq!(
+            automation_denied_app(
+                "36:120: execution error: Not authorized to send Apple events to Terminal. (-1743)\n"
+            )
+            .as_deref(),
+            Some("Terminal")
+        );
+        assert_eq!(
+            automation_denied_app(
+                "execution error: Not authorized to send Apple events to System Events. (-1743)"
+            )
+            .as_deref(),
+            Some("System Events")
+        );
+        assert_eq!(
+            automation_denied_app("execution error: 未获得授权。 (-1743)").as_deref(),
+            Some("目标程序")
+        );
+        assert_eq!(
+            automation_denied_app(
+                "execution error: Terminal got an error: Application isn't running. (-600)"
+            ),
+            None
+        );
+        assert_eq!(
+            classify_system_error(&automation_permission_error("iTerm")),
+            Some(SystemError::AutomationPermissionDenied {
+                app: "iTerm".to_string()
+            })
+        );
+        assert_eq!(classify_system_error("终端打开失败: 找不到应用"), None);
+    }
+
+    #[test]
+    fn describe_osascript_error_adds_permission_hints() {
+        assert!(describe_osascript_error(
+            "0:10: execution error: System Events got an error: osascript is not allowed assistive access. (-1719)"
+        )
+        .expect("detail")
+        .contains("辅助功能"));
+        assert_eq!(
+            describe_osascript_error("12:30: syntax error: Expected end of line. (-2741)")
+                .as_deref(),
+            Some("12:30: syntax error: Expected end of line. (-2741)")
+        );
+        assert_eq!(describe_osascript_error(" \n"), None);
+    }
+
+    #[test]
+    fn build_editor_file_arguments_orders_jetbrains_line_before_file() {
+        let mut arguments = build_command_arguments(Some(vec!["{path}".to_string()]), "/p");
+        arguments.extend(build_editor_file_arguments(
+            EditorFamily::JetBrains,
+            "/p/src/main.rs",
+            Some(12),
+            None,
+        ));
+        assert_eq!(arguments, ["/p", "--line", "12", "/p/src/main.rs"]);
+
+        assert_eq!(
+            build_editor_file_arguments(EditorFamily::JetBrains, "/p/a.rs", None, Some(4)),
+            ["/p/a.rs"]
+        );
+        assert_eq!(
+            build_editor_file_arguments(EditorFamily::JetBrains, "/p/a.rs", Some(3), Some(4)),
+            ["--line", "3", "--column", "4", "/p/a.rs"]
+        );
+        assert_eq!(
+            build_editor_file_arguments(EditorFamily::VsCode, "/p/a.rs", Some(3), None),
+            ["-g", "/p/a.rs:3"]
+        );
+        assert_eq!(
+            build_editor_file_arguments(EditorFamily::VsCode, "/p/a.rs", Some(3), Some(4)),
+            ["-g", "/p/a.rs:3:4"]
+        );
+        assert_eq!(
+            build_editor_file_arguments(EditorFamily::Other, "/p/a.rs", Some(3), None),
+            ["/p/a.rs"]
+        );
+    }
+
+    #[test]
+    fn parse_goto_argument_handles_editor_styles() {
+        let goto = |path: &str, line: Option<u32>, column: Option<u32>| GotoSpec {
+            path: path.to_string(),
+            line,
+            column,
+        };
+        assert_eq!(parse_goto_argument("/p/a.rs"), goto("/p/a.rs", None, None));
+        assert_eq!(
+            parse_goto_argument("/p/a.rs:12"),
+            goto("/p/a.rs", Some(12), None)
+        );
+        assert_eq!(
+            parse_goto_argument(" /p/a.rs:12:5 "),
+            goto("/p/a.rs", Some(12), Some(5))
+        );
+        assert_eq!(
+            parse_goto_argument("-g /p/my app.rs:3:1"),
+            goto("/p/my app.rs", Some(3), Some(1))
+        );
+        assert_eq!(
+            parse_goto_argument("--goto src/lib.rs:9"),
+            goto("src/lib.rs", Some(9), None)
+        );
+        assert_eq!(
+            parse_goto_argument("/p/a.rs:1:2:3"),
+            goto("/p/a.rs:1", Some(2), Some(3))
+        );
+        assert_eq!(
+            parse_goto_argument("/p/a.rs:main"),
+            goto("/p/a.rs:main", None, None)
+        );
+
+        assert_eq!(
+            parse_goto_argument("C:\\p\\a.rs"),
+            goto("C:\\p\\a.rs", None, None)
+        );
+        assert_eq!(
+            parse_goto_argument("C:\\p\\a.rs:7"),
+            goto("C:\\p\\a.rs", Some(7), None)
+        );
+        assert_eq!(
+            parse_goto_argument("C:\\p\\a.rs:7:2"),
+            goto("C:\\p\\a.rs", Some(7), Some(2))
+        );
+        assert_eq!(parse_goto_argument("C:12"), goto("C:12", None, None));
+
+        assert_eq!(
+            parse_goto_argument("--line 42 /p/a.rs"),
+            goto("/p/a.rs", Some(42), None)
+        );
+        assert_eq!(
+            parse_goto_argument("--line 42 --column 7 C:\\p\\my file.rs"),
+            goto("C:\\p\\my file.rs", Some(42), Some(7))
+        );
+        assert_eq!(
+            parse_goto_argument("--line x /p/a.rs"),
+            goto("--line x /p/a.rs", None, None)
+        );
+    }
+
+    #[test]
+    fn open_workspace_skips_terminal_when_required_editor_fails() {
+        let mut editor = preset("broken");
+        editor.command_path = "/definitely/missing/editor".to_string();
+        let result = open_workspace(
+            "/tmp",
+            Some(editor),
+            Some(TerminalOpenParams::default()),
+            true,
+        );
+        assert!(matches!(result.editor, LaunchStepStatus::Failed(_)));
+        assert_eq!(result.terminal, LaunchStepStatus::Skipped);
+    }
+
+    #[test]
+    fn get_path_metadata_reports_times_and_git_repo() {
+        let root = std::env::temp_dir().join(format!("devhaven-meta-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(root.join(".git")).expect("create dir");
+        let root_str = root.to_string_lossy().to_string();
+
+        let meta = get_path_metadata(&root_str).expect("read metadata");
+        assert!(meta.is_git_repo);
+        assert!(meta.modified_at.is_some_and(|millis| millis > 0));
+
+        let file = root.join("notes.md");
+        fs::write(&file, "").expect("write file");
+        let meta = get_path_metadata(&file.to_string_lossy()).expect("read metadata");
+        assert!(!meta.is_git_repo);
+        assert!(get_path_metadata(&root.join("missing").to_string_lossy()).is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn get_free_space_walks_up_to_existing_ancestor() {
+        let temp = std::env::temp_dir();
+        let missing = temp.join("devhaven-missing").join("child");
+        assert_eq!(existing_ancestor(&missing), Some(temp.as_path()));
+        assert!(get_free_space(&missing.to_string_lossy()).is_ok_and(|free| free > 0));
+    }
+
+    #[test]
+    fn directory_size_sums_files_and_skips_heavy_dirs() {
+        let root = std::env::temp_dir().join(format!("devhaven-size-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(root.join("src/deep/er")).expect("create dir");
+        fs::create_dir_all(root.join("node_modules/pkg")).expect("create dir");
+        fs::write(root.join("README.md"), "12345").expect("write file");
+        fs::write(root.join("src/deep/er/main.rs"), "123").expect("write file");
+        fs::write(root.join("node_modules/pkg/index.js"), "1234567").expect("write file");
+        let far_future = Instant::now() + Duration::from_secs(60);
+        let running = AtomicBool::new(false);
+
+        assert_eq!(directory_size(&root, false, far_future, &running), Ok(15));
+        assert_eq!(directory_size(&root, true, far_future, &running), Ok(8));
+        assert_eq!(
+            directory_size(&root.join("README.md"), true, far_future, &running),
+            Ok(5)
+        );
+        assert!(directory_size(&root, false, Instant::now(), &running).is_err());
+        assert_eq!(
+            directory_size(&root, false, far_future, &AtomicBool::new(true)),
+            Err("已取消统计目录大小".to_string())
+        );
+        assert!(!cancel_directory_size("devhaven-unknown-scan"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+    fn trash_into_writes_trash_info_and_avoids_name_clashes() {
+        let root = std::env::temp_dir().join(format!("devhaven-trash-{}", uuid::Uuid::new_v4()));
+        let trash = root.join("Trash");
+        fs::create_dir_all(root.join("work")).expect("create dir");
+        let first = root.join("work/a b.txt");
+        fs::write(&first, "1").expect("write file");
+        trash_into(&first, &trash, None).expect("trash file");
+        fs::write(&first, "2").expect("write file");
+        trash_into(&first, &trash, None).expect("trash file again");
+
+        assert!(!first.exists());
+        assert_eq!(
+            fs::read_to_string(trash.join("files/a b.txt")).expect("read"),
+            "1"
+        );
+        assert_eq!(
+            fs::read_to_string(trash.join("files/a b.txt.2")).expect("read"),
+            "2"
+        );
+        let info = fs::read_to_string(trash.join("info/a b.txt.2.trashinfo")).expect("read info");
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(info.contains(&format!("Path={}/work/a%20b.txt\n", root.display())));
+        assert!(info.contains("DeletionDate="));
+
+        let nested = root.join("work/dir");
+        fs::create_dir_all(&nested).expect("create dir");
+        trash_into(&nested, &trash, Some(&root)).expect("trash dir");
+        let info = fs::read_to_string(trash.join("info/dir.trashinfo")).expect("read info");
+        assert!(info.contains("Path=work/dir\n"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+    fn linux_terminal_directory_arguments_match_terminal_flags() {
+        assert_eq!(
+            linux_terminal_directory_arguments("gnome-terminal", "/p q", TerminalFlagSet::default),
+            ["--working-directory=/p q"]
+        );
+        let no_probe = || -> TerminalFlagSet { panic!("只有一种写法时不应探测") };
+        assert_eq!(
+            linux_terminal_directory_arguments("konsole", "/p", no_probe),
+            ["--workdir", "/p"]
+        );
+        assert!(linux_terminal_directory_arguments("xterm", "/p", no_probe).is_empty());
+
+        let legacy =
+            || parse_help_long_options("  --working-dir=DIR   Set the working directory\n");
+        assert_eq!(
+            linux_terminal_directory_arguments("gnome-terminal", "/p", legacy),
+            ["--working-dir=/p"]
+        );
+    }
+
+    #[test]
+    fn parse_help_long_options_collects_flag_names() {
+        let help = "Usage:\n  gnome-terminal [OPTION…] [-- COMMAND …]\n\n\
+            Help Options:\n  -h, --help                 Show help options\n  \
+            --help-all                 Show all help options\n\n\
+            Terminal options:\n  --working-directory=DIRNAME  Set the working directory\n  \
+            -e, --command=STRING       Execute the argument\n  [--title <title>]\n";
+        let flags = parse_help_long_options(help);
+        assert!(flags.supports("--working-directory"));
+        assert!(flags.supports("--help-all"));
+        assert!(flags.supports("--command"));
+        assert!(flags.supports("--title"));
+        assert!(!flags.supports("--"));
+        assert!(!flags.supports("--working-dir"));
+        assert_eq!(
+            parse_help_long_options("usage: xterm [-e command]"),
+            TerminalFlagSet::default()
+        );
+    }
+
+    #[test]
+    fn explorer_arguments_quote_paths_with_spaces_and_commas() {
+        assert_eq!(
+            explorer_open_argument("C:\\My Projects\\a,b"),
+            "\"C:\\My Projects\\a,b\""
+        );
+        assert_eq!(
+            explorer_select_argument("C:/My Projects/a,b/readme.md"),
+            "/select,\"C:\\My Projects\\a,b\\readme.md\""
+        );
+        assert_eq!(
+            explorer_open_argument("D:\\项目\\演示"),
+            "\"D:\\项目\\演示\""
+        );
+        assert_eq!(
+            explorer_open_argument("http://localhost:3000/a"),
+            "\"http://localhost:3000/a\""
+        );
+    }
+
+    #[test]
+    fn to_windows_extended_path_prefixes_absolute_paths() {
+        assert_eq!(
+            to_windows_extended_path("C:\\work\\project"),
+            "\\\\?\\C:\\work\\project"
+        );
+        assert_eq!(
+            to_windows_extended_path("d:/work/project"),
+            "\\\\?\\d:\\work\\project"
+        );
+        assert_eq!(
+            to_windows_extended_path("\\\\server\\share\\project"),
+            "\\\\?\\UNC\\server\\share\\project"
+        );
+        assert_eq!(
+            to_windows_extended_path("\\\\?\\C:\\work"),
+            "\\\\?\\C:\\work"
+        );
+        assert_eq!(
+            to_windows_extended_path("\\\\?\\UNC\\server\\share"),
+            "\\\\?\\UNC\\server\\share"
+        );
+        assert_eq!(to_windows_extended_path("relative\\dir"), "relative\\dir");
+    }
+
+    #[test]
+    fn explorer_arguments_extend_long_paths() {
+        let long_path = format!("C:\\{}\\readme.md", "a".repeat(260));
+        assert_eq!(
+            explorer_select_argument(&long_path),
+            format!("/select,\"\\\\?\\{long_path}\"")
+        );
+        let long_unc = format!("\\\\server\\share\\{}", "b".repeat(260));
+        assert_eq!(
+            explorer_open_argument(&long_unc),
+            format!("\"\\\\?\\UNC{}\"", &long_unc[1..])
+        );
+
+        // 按 UTF-16 计数：100 个中文字符占 300 字节，但远未达到 MAX_PATH。
+        let wide_path = format!("C:\\{}\\说明.md", "项".repeat(100));
+        assert_eq!(
+            explorer_open_argument(&wide_path),
+            format!("\"{wide_path}\"")
+        );
+    }
+
+    #[test]
+    fn macos_bundle_path_distinguishes_paths_from_names() {
+        assert_eq!(
+            macos_bundle_path("/Users/me/Applications/IntelliJ IDEA 2024.1.app/"),
+            Some("/Users/me/Applications/IntelliJ IDEA 2024.1.app")
+        );
+        assert_eq!(
+            macos_bundle_path("/Applications/Visual Studio Code.APP"),
+            Some("/Applications/Visual Studio Code.APP")
+        );
+        assert_eq!(macos_bundle_path("Visual Studio Code"), None);
+        assert_eq!(macos_bundle_path("Visual Studio Code.app"), None);
+        assert_eq!(macos_bundle_path("/usr/local/bin/code"), None);
+    }
+
+    #[test]
+    fn clean_macos_app_name_collapses_whitespace_and_suffix() {
+        assert_eq!(clean_macos_app_name("  IntelliJ\tIDEA  "), "IntelliJ IDEA");
+        assert_eq!(
+            clean_macos_app_name("Visual Studio Code\u{a0}-  Insiders.app"),
+            "Visual Studio Code - Insiders"
+        );
+        assert_eq!(clean_macos_app_name("Zed"), "Zed");
+    }
+
+    #[test]
+    fn same_app_name_ignores_spacing_and_punctuation() {
+        let app_names = vec![
+            "IntelliJ IDEA".to_string(),
+            "Visual Studio Code".to_string(),
+            "Visual Studio Code - Insiders".to_string(),
+        ];
+        assert_eq!(
+            same_app_name("Visual Studio Code – Insiders", &app_names),
+            Some("Visual Studio Code - Insiders")
+        );
+        assert_eq!(
+            same_app_name("intellij idea", &app_names),
+            Some("IntelliJ IDEA")
+        );
+        assert_eq!(same_app_name("IntelliJ IDEA CE", &app_names), None);
+    }
+
+    #[test]
+    fn fuzzy_app_candidates_rank_closest_names_first() {
+        let apps: Vec<String> = [
+            "Visual Studio Code - Insiders",
+            "Visual Studio Code",
+            "Xcode",
+            "Safari",
+        ]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+
+        assert_eq!(
+            fuzzy_app_candidates("VS Code", &apps),
+            ["Visual Studio Code", "Visual Studio Code - Insiders"]
+        );
+        assert_eq!(
+            fuzzy_app_candidates("visualstudiocode", &apps)[0],
+            "Visual Studio Code"
+        );
+        assert_eq!(fuzzy_app_candidates("xcode", &apps)[0], "Xcode");
+        assert!(fuzzy_app_candidates("Sublime Text", &apps).is_empty());
+        assert!(fuzzy_app_candidates(" - ", &apps).is_empty());
+    }
+
+    #[test]
+    fn command_line_runs_editor_matches_whole_executable_names() {
+        let vscode = editor_process_names("vscode").unwrap();
+        assert!(command_line_runs_editor(
+            "/usr/share/code/code --unity-launch /home/me/app",
+            vscode
+        ));
+        assert!(command_line_runs_editor(
+            "/Applications/Visual Studio Code.app/Contents/MacOS/Electron /p",
+            vscode
+        ));
+        assert!(command_line_runs_editor(
+            "\"C:\\Program Files\\Microsoft VS Code\\Code.exe\" C:\\p",
+            vscode
+        ));
+        assert!(!command_line_runs_editor(
+            "/usr/bin/code-insiders /home/me/app",
+            vscode
+        ));
+        assert!(!command_line_runs_editor(
+            "/usr/bin/vscode-server /p",
+            vscode
+        ));
+        assert!(command_line_runs_editor(
+            "C:\\JetBrains\\bin\\idea64.exe C:\\p",
+            editor_process_names("intellij-idea").unwrap()
+        ));
+        assert_eq!(editor_process_names("vim"), None);
+    }
+
+    #[test]
+    fn process_name_filters_match_any_editor_name() {
+        let names = ["code", "code - insiders", "c++"];
+        assert_eq!(process_name_pattern(&names), "code|code - insiders|c\\+\\+");
+        assert_eq!(
+            process_name_wql_filter(&["code", "it's_100%"]),
+            "Name LIKE 'code%' OR Name LIKE 'it''s[_]100[%]%'"
+        );
+    }
+
+    #[test]
+    fn command_line_mentions_path_requires_a_boundary() {
+        let command_line = "/usr/bin/code /home/me/app --new-window";
+        assert!(command_line_mentions_path(command_line, "/home/me/app"));
+        assert!(command_line_mentions_path(command_line, "/home/me/app/"));
+        assert!(!command_line_mentions_path(command_line, "/home/me/ap"));
+        assert!(!command_line_mentions_path(
+            "/usr/bin/code /home/me/app2",
+            "/home/me/app"
+        ));
+        assert!(!command_line_mentions_path(command_line, "/"));
+    }
+
+    #[test]
+    fn expand_path_placeholder_honours_escaped_placeholders() {
+        assert_eq!(
+            expand_path_placeholder("--root={path}", "/p"),
+            ("--root=/p".to_string(), true)
+        );
+        assert_eq!(
+            expand_path_placeholder("--template={{path}}", "/p"),
+            ("--template={path}".to_string(), false)
+        );
+        assert_eq!(
+            expand_path_placeholder("{path}:{{path}}:{path}", "/p"),
+            ("/p:{path}:/p".to_string(), true)
+        );
+        assert_eq!(
+            expand_path_placeholder("{x} {{other}}", "/p"),
+            ("{x} {{other}}".to_string(), false)
+        );
+        assert_eq!(
+            build_command_arguments(Some(vec!["--literal={{path}}".to_string()]), "/p"),
+            ["--literal={path}", "/p"]
+        );
+    }
+
+    #[test]
+    fn multiroot_folders_validates_and_deduplicates() {
+        let root = std::env::temp_dir().join(format!("devhaven-system-{}", uuid::Uuid::new_v4()));
+        let alpha = root.join("alpha");
+        let beta = root.join("beta");
+        fs::create_dir_all(&alpha).expect("create alpha");
+        fs::create_dir_all(&beta).expect("create beta");
+        let alpha = alpha.to_string_lossy().to_string();
+        let beta = beta.to_string_lossy().to_string();
+
+        let folders =
+            multiroot_folders(&[alpha.clone(), beta.clone(), alpha.clone()]).expect("folders");
+        assert_eq!(folders, [alpha.clone(), beta.clone()]);
+        let content: serde_json::Value =
+            serde_json::from_str(&multiroot_workspace_content(&folders)).expect("json");
+        assert_eq!(content["folders"][1]["path"], beta.as_str());
+
+        let missing = root.join("missing").to_string_lossy().to_string();
+        assert!(multiroot_folders(&[alpha, missing]).is_err());
+        assert!(multiroot_folders(&[]).is_err());
+
+        let workspace = root.join("old.code-workspace");
+        fs::write(&workspace, "{}").expect("write workspace");
+        remove_expired_workspace_files(&root, Duration::from_secs(3600));
+        assert!(workspace.exists());
+        remove_expired_workspace_files(&root, Duration::ZERO);
+        assert!(!workspace.exists());
+        assert!(root.join("alpha").exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn build_multi_path_arguments_expands_every_path() {
+        let paths = vec!["/a".to_string(), "/b c".to_string()];
+        assert_eq!(
+            build_multi_path_arguments(&["-n".to_string(), "{path}".to_string()], &paths),
+            ["-n", "/a", "/b c"]
+        );
+        assert_eq!(
+            build_multi_path_arguments(&["--folder={path}".to_string()], &paths),
+            ["--folder=/a", "--folder=/b c"]
+        );
+        assert_eq!(
+            build_multi_path_arguments(&["-n".to_string()], &paths),
+            ["-n", "/a", "/b c"]
+        );
+    }
+
+    fn preset(id: &str) -> DevToolPreset {
+        DevToolPreset {
+            id: id.to_string(),
+            name: id.to_string(),
+            command_path: id.to_string(),
+            arguments: vec!["{path}".to_string()],
+            success_exit_codes: None,
+            kind: PresetKind::Command,
+            install_kind: InstallKind::default(),
+        }
+    }
+
+    #[test]
+    fn merge_provider_presets_keeps_first_preset_per_id() {
+        let mut custom_vscode = preset("vscode");
+        custom_vscode.name = "Custom".to_string();
+        let merged = merge_provider_presets(vec![
+            vec![preset("vscode"), preset("idea")],
+            vec![custom_vscode, preset("proprietary-ide")],
+        ]);
+
+        let ids: Vec<&str> = merged.iter().map(|preset| preset.id.as_str()).collect();
+        assert_eq!(ids, vec!["vscode", "idea", "proprietary-ide"]);
+        assert_ne!(merged[0].name, "Custom");
+    }
+
+    fn mac_preset(id: &str, app_name: &str) -> DevToolPreset {
+        DevToolPreset {
+            id: id.to_string(),
+            name: app_name.to_string(),
+            command_path: "/usr/bin/open".to_string(),
+            arguments: vec!["-a".to_string(), app_name.to_string(), "{path}".to_string()],
+            success_exit_codes: None,
+            kind: PresetKind::Command,
+            install_kind: InstallKind::default(),
+        }
+    }
+
+    #[test]
+    fn resolve_command_path_requires_existing_command() {
+        let sh = resolve_command_path("sh").expect("sh in PATH");
+        assert!(Path::new(&sh).is_absolute());
+        assert_eq!(resolve_command_path(&sh), Some(sh.clone()));
+        assert_eq!(resolve_command_path("/definitely/missing/tool"), None);
+        assert_eq!(resolve_command_path("devhaven-missing-tool"), None);
+        assert_eq!(resolve_command_path("bin/sh"), None);
+    }
+
+    #[test]
+    fn order_presets_moves_preferred_ids_to_front() {
+        let presets = vec![
+            preset("vscode"),
+            preset("cursor"),
+            preset("idea"),
+            preset("zed"),
+        ];
+        let preferred = vec![
+            "zed".to_string(),
+            "missing".to_string(),
+            "cursor".to_string(),
+        ];
+        let ids: Vec<String> = order_presets(presets, &preferred)
+            .into_iter()
+            .map(|preset| preset.id)
+            .collect();
+        assert_eq!(ids, ["zed", "cursor", "vscode", "idea"]);
+    }
+
+    #[test]
+    fn merge_discovered_presets_prefers_curated_apps() {
+        let merged = merge_discovered_presets(
+            vec![mac_preset("vscode", "Visual Studio Code")],
+            vec![
+                mac_preset(
+                    &discovered_preset_id("Visual Studio Code"),
+                    "Visual Studio Code",
+                ),
+                mac_preset(&discovered_preset_id("Sublime Text"), "Sublime Text"),
+                mac_preset("vscode", "Other"),
+            ],
+        );
+        let ids: Vec<&str> = merged.iter().map(|preset| preset.id.as_str()).collect();
+        assert_eq!(ids, ["vscode", "app-sublime-text"]);
+        assert_eq!(discovered_preset_id("Nova 11 (Beta)"), "app-nova-11-beta");
+    }
+
+    #[test]
+    fn declares_source_code_documents_reads_document_types() {
+        let editor = serde_json::json!({
+            "CFBundleDocumentTypes": [
+                { "LSItemContentTypes": ["public.plain-text"] },
+                { "LSItemContentTypes": ["public.source-code"] }
+            ]
+        });
+        let viewer = serde_json::json!({
+            "CFBundleDocumentTypes": [{ "LSItemContentTypes": ["public.image"] }]
+        });
+        assert!(declares_source_code_documents(&editor));
+        assert!(!declares_source_code_documents(&viewer));
+        assert!(!declares_source_code_documents(&serde_json::json!({})));
+    }
+
+    #[test]
+    fn preferred_presets_for_extension_maps_languages() {
+        assert_eq!(
+            preferred_presets_for_extension("py"),
+            Some(&["pycharm"][..])
+        );
+        assert_eq!(preferred_presets_for_extension("go"), Some(&["goland"][..]));
+        assert_eq!(
+            preferred_presets_for_extension("kt"),
+            Some(&["intellij-idea"][..])
+        );
+        assert_eq!(preferred_presets_for_extension("sln"), Some(&["rider"][..]));
+        assert_eq!(preferred_presets_for_extension("md"), None);
+    }
+
+    #[test]
+    fn detect_project_language_weighs_markers_and_extensions() {
+        let root = std::env::temp_dir().join(format!("devhaven_lang_{}", uuid::Uuid::new_v4()));
+        let root_str = root.to_string_lossy().to_string();
+        fs::create_dir_all(root.join("src")).expect("create src");
+        fs::create_dir_all(root.join("scripts")).expect("create scripts");
+        fs::create_dir_all(root.join("node_modules/pkg")).expect("create node_modules");
+        assert_eq!(detect_project_language(&root_str), None);
+
+        fs::write(root.join("scripts/a.py"), "").expect("write py");
+        fs::write(root.join("scripts/b.py"), "").expect("write py");
+        for name in ["a.js", "b.js", "c.js"] {
+            fs::write(root.join("node_modules/pkg").join(name), "").expect("write js");
+        }
+        assert_eq!(detect_project_language(&root_str), Some(Language::Python));
+
+        fs::write(root.join("Cargo.toml"), "").expect("write Cargo.toml");
+        fs::write(root.join("src/main.rs"), "").expect("write rs");
+        assert_eq!(detect_project_language(&root_str), Some(Language::Rust));
+        assert_eq!(
+            suggest_preset_for_path(&root_str, &[preset("vscode"), preset("clion")]).as_deref(),
+            Some("clion")
+        );
+        assert_eq!(
+            detect_project_language(&root.join("src/main.rs").to_string_lossy()),
+            None
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn suggest_preset_for_path_falls_back_to_general_editor() {
+        let presets = vec![preset("vscode"), preset("pycharm"), preset("goland")];
+        assert_eq!(
+            suggest_preset_for_path("/p/main.PY", &presets).as_deref(),
+            Some("pycharm")
+        );
+        assert_eq!(
+            suggest_preset_for_path("/p/main.go", &presets).as_deref(),
+            Some("goland")
+        );
+        assert_eq!(
+            suggest_preset_for_path("/p/lib.rs", &presets).as_deref(),
+            Some("vscode")
+        );
+        assert_eq!(
+            suggest_preset_for_path("/p/project", &presets).as_deref(),
+            Some("vscode")
+        );
+        assert_eq!(
+            suggest_preset_for_path("/p/lib.rs", &[preset("rider")]),
+            None
+        );
+    }
+
+    #[test]
+    fn suggest_terminal_command_detects_project_types() {
+        let root = std::env::temp_dir().join(format!("devhaven-suggest-{}", uuid::Uuid::new_v4()));
+        let fixture = |name: &str, files: &[(&str, &str)], dirs: &[&str]| {
+            let dir = root.join(name);
+            fs::create_dir_all(&dir).expect("create fixture");
+            for (file, content) in files {
+                fs::write(dir.join(file), content).expect("write fixture file");
+            }
+            for sub_dir in dirs {
+                fs::create_dir_all(dir.join(sub_dir)).expect("create fixture dir");
+            }
+            dir.to_string_lossy().to_string()
+        };
+        let suggest = |dir: &str| suggest_terminal_command(dir);
+
+        let fresh_node = fixture(
+            "fresh-node",
+            &[("package.json", "{}"), ("pnpm-lock.yaml", "")],
+            &[],
+        );
+        assert_eq!(suggest(&fresh_node).as_deref(), Some("pnpm install"));
+        let node = fixture(
+            "node",
+            &[(
+                "package.json",
+                r#"{"scripts":{"start":"node .","dev":"vite"}}"#,
+            )],
+            &["node_modules"],
+        );
+        assert_eq!(suggest(&node).as_deref(), Some("npm run dev"));
+        // 没有可运行的脚本时继续检查后续标记文件。
+        let node_make = fixture(
+            "node-make",
+            &[("package.json", "{}"), ("Makefile", "all:")],
+            &["node_modules"],
+        );
+        assert_eq!(suggest(&node_make).as_deref(), Some("make"));
+
+        let rust = fixture("rust", &[("Cargo.toml", ""), ("Makefile", "")], &[]);
+        assert_eq!(suggest(&rust).as_deref(), Some("cargo run"));
+        let rust_file = Path::new(&rust).join("Cargo.toml");
+        assert_eq!(
+            suggest(&rust_file.to_string_lossy()).as_deref(),
+            Some("cargo run")
+        );
+
+        let python = fixture("python", &[("requirements.txt", "")], &[]);
+        assert_eq!(suggest(&python).as_deref(), Some("python3 -m venv .venv"));
+        let poetry = fixture(
+            "poetry",
+            &[("pyproject.toml", ""), ("poetry.lock", "")],
+            &[],
+        );
+        assert_eq!(suggest(&poetry).as_deref(), Some("poetry install"));
+        let venv = fixture("venv", &[("pyproject.toml", "")], &[".venv"]);
+        assert!(suggest(&venv).is_some_and(|command| command.contains(".venv")));
+
+        let empty = fixture("empty", &[], &[]);
+        assert_eq!(suggest(&empty), None);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn import_presets_rejects_invalid_entries_individually() {
+        let mut valid = preset("vscode");
+        valid.command_path = "/bin/sh".to_string();
+        let mut missing = preset("zed");
+        missing.command_path = "/nonexistent/devhaven-editor".to_string();
+        let mut duplicate = valid.clone();
+        duplicate.name = "Copy".to_string();
+        let exported = export_presets(&[valid.clone(), missing, duplicate]);
+        let mut entries: Vec<serde_json::Value> =
+            serde_json::from_str(&exported).expect("exported json");
+        entries.push(serde_json::json!({ "id": "broken", "arguments": "{path}" }));
+
+        let result = import_presets(&serde_json::to_string(&entries).unwrap()).expect("import");
+        assert_eq!(result.presets.len(), 1);
+        assert_eq!(result.presets[0].id, valid.id);
+        assert_eq!(result.presets[0].command_path, valid.command_path);
+        assert_eq!(result.rejected.len(), 3);
+        assert_eq!(result.rejected[0].index, 1);
+        assert_eq!(
+            result.rejected[0].warnings,
+            [PresetWarning::CommandNotFound {
+                command: "/nonexistent/devhaven-editor".to_string()
+            }]
+        );
+        assert_eq!(result.rejected[1].index, 2);
+        assert_eq!(
+            result.rejected[1].error.as_deref(),
+            Some("预设 ID 重复: vscode")
+        );
+        assert_eq!(result.rejected[2].id.as_deref(), Some("broken"));
+        assert!(result.rejected[2].error.is_some());
+
+        assert!(import_presets("{}").is_err());
+        assert!(import_presets("[]").expect("empty").presets.is_empty());
+    }
+
+    #[test]
+    fn validate_preset_reports_template_mistakes() {
+        let preset = |command_path: &str, arguments: &[&str]| DevToolPreset {
+            id: "custom".to_string(),
+            name: "Custom".to_string(),
+            command_path: command_path.to_string(),
+            arguments: arguments.iter().map(|value| value.to_string()).collect(),
+            success_exit_codes: None,
+            kind: PresetKind::Command,
+            install_kind: InstallKind::default(),
+        };
+        assert!(validate_preset(&preset("/bin/sh", &["-c", "{path}"])).is_empty());
+        assert!(validate_preset(&preset("/bin/sh", &[])).is_empty());
+        assert_eq!(
+            validate_preset(&preset("/bin/sh", &["--login"])),
+            [PresetWarning::PathNotReferenced]
+        );
+        assert_eq!(
+            validate_preset(&preset(" ", &["{path}"])),
+            [PresetWarning::EmptyCommandPath]
+        );
+        assert_eq!(
+            validate_preset(&preset(
+                "/nonexistent/devhaven-editor",
+                &["--goto", "{file}:{line}"]
+            )),
+            [
+                PresetWarning::CommandNotFound {
+                    command: "/nonexistent/devhaven-editor".to_string()
+                },
+                PresetWarning::PathNotReferenced,
+                PresetWarning::UnknownPlaceholder {
+                    placeholder: "{file}".to_string()
+                },
+                PresetWarning::UnknownPlaceholder {
+                    placeholder: "{line}".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            validate_preset(&preset(
+                "/bin/sh",
+                &[
+                    "\"{path}\"",
+                    "--title=It's",
+                    "'{path}'",
+                    "--name=\"a",
+                    r#"{"a":1}"#,
+                    "{{path}}"
+                ]
+            )),
+            [
+                PresetWarning::SuspiciousQuotes {
+                    argument: "\"{path}\"".to_string()
+                },
+                PresetWarning::SuspiciousQuotes {
+                    argument: "'{path}'".to_string()
+                },
+                PresetWarning::SuspiciousQuotes {
+                    argument: "--name=\"a".to_string()
+                },
+            ]
+        );
+
+        let code_server = DevToolPreset {
+            id: "my-code-server".to_string(),
+            kind: PresetKind::CodeServer,
+            ..preset("http://localhost:8080", &[])
+        };
+        assert!(validate_preset(&code_server).is_empty());
+    }
+
+    #[test]
+    fn write_bootstrap_files_never_overwrites_or_escapes_root() {
+        let root =
+            std::env::temp_dir().join(format!("devhaven-bootstrap-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("create root");
+        fs::write(root.join(".editorconfig"), "root = true\n").expect("write existing");
+
+        let files = vec![
+            (".vscode/settings.json".to_string(), "{}".to_string()),
+            (".editorconfig".to_string(), "overwritten".to_string()),
+        ];
+        let result = write_bootstrap_files(&root, &files).expect("bootstrap");
+        assert_eq!(result.written, [".vscode/settings.json"]);
+        assert_eq!(result.skipped, [".editorconfig"]);
+        assert_eq!(
+            fs::read_to_string(root.join(".vscode/settings.json")).unwrap(),
+            "{}"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join(".editorconfig")).unwrap(),
+            "root = true\n"
+        );
+
+        for invalid in ["../outside.txt", "a/../../b", "/etc/passwd", "./a", ""] {
+            let files = vec![
+                ("ok.txt".to_string(), String::new()),
+                (invalid.to_string(), String::new()),
+            ];
+            assert!(write_bootstrap_files(&root, &files).is_err(), "{invalid}");
+        }
+        assert!(!root.join("ok.txt").exists());
+
+        #[cfg(unix)]
+        {
+            let outside =
+                std::env::temp_dir().join(format!("devhaven-outside-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&outside).expect("create outside");
+            std::os::unix::fs::symlink(&outside, root.join("linked")).expect("symlink");
+            let files = vec![
+                ("ok.txt".to_string(), String::new()),
+                ("linked/sub/settings.json".to_string(), String::new()),
+            ];
+            assert!(write_bootstrap_files(&root, &files).is_err());
+            assert!(!outside.join("sub").exists());
+            assert!(!root.join("ok.txt").exists());
+            let _ = fs::remove_dir_all(&outside);
+        }
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn cached_path_lookup_memoizes_only_within_a_scan() {
+        let lookups = std::cell::Cell::new(0);
+        let lookup = || {
+            lookups.set(lookups.get() + 1);
+            Some(format!("/bin/{}", lookups.get()))
+        };
+        assert_eq!(
+            cached_path_lookup("code", false, lookup).as_deref(),
+            Some("/bin/1")
+        );
+        assert_eq!(
+            cached_path_lookup("code", false, lookup).as_deref(),
+            Some("/bin/2")
+        );
+
+        with_path_lookup_cache(|| {
+            assert_eq!(
+                cached_path_lookup("code", false, lookup).as_deref(),
+                Some("/bin/3")
+            );
+            assert_eq!(
+                cached_path_lookup("code", false, lookup).as_deref(),
+                Some("/bin/3")
+            );
+            assert_eq!(
+                cached_path_lookup("code.cmd", false, lookup).as_deref(),
+                Some("/bin/4")
+            );
+            assert_eq!(
+                cached_path_lookup("code", true, lookup).as_deref(),
+                Some("/bin/5")
+            );
+            with_path_lookup_cache(|| {
+                assert_eq!(
+                    cached_path_lookup("code", false, lookup).as_deref(),
+                    Some("/bin/6")
+                );
+            });
+            assert_eq!(
+                cached_path_lookup("code", false, lookup).as_deref(),
+                Some("/bin/3")
+            );
+        });
+        assert_eq!(
+            cached_path_lookup("code", false, lookup).as_deref(),
+            Some("/bin/7")
+        );
+    }
+
+    #[test]
+    fn parse_codesign_status_maps_tool_output() {
+        assert_eq!(
+            parse_codesign_status(
+                false,
+                "/usr/local/bin/tool: code object is not signed at all\n",
+                None
+            ),
+            CodesignStatus::Unsigned
+        );
+        assert_eq!(
+            parse_codesign_status(
+                false,
+                "/Applications/X.app: a sealed resource is missing or invalid\n",
+                None
+            ),
+            CodesignStatus::Rejected
+        );
+        assert_eq!(
+            parse_codesign_status(
+                true,
+                "",
+                Some((
+                    true,
+                    "/Applications/X.app: accepted\nsource=Notarized Developer ID\norigin=Developer ID Application: X\n"
+                ))
+            ),
+            CodesignStatus::Notarized
+        );
+        assert_eq!(
+            parse_codesign_status(
+                true,
+                "",
+                Some((false, "/usr/local/bin/tool: rejected (the code is valid but does not seem to be an app)\n"))
+            ),
+            CodesignStatus::Signed
+        );
+        assert_eq!(
+            parse_codesign_status(
+                true,
+                "",
+                Some((true, "/X.app: accepted\nsource=Apple System\n"))
+            ),
+            CodesignStatus::Signed
+        );
+        assert_eq!(
+            parse_codesign_status(true, "", None),
+            CodesignStatus::Signed
+        );
+    }
+
+    #[test]
+    fn find_recent_subproject_picks_latest_modified_files() {
+        let root = std::env::temp_dir().join(format!("devhaven-system-{}", uuid::Uuid::new_v4()));
+        let old = root.join("old");
+        let recent = root.join("recent");
+        let deps = root.join("noisy");
+        fs::create_dir_all(old.join("src")).expect("create old");
+        fs::create_dir_all(recent.join("src/deep/deeper")).expect("create recent");
+        fs::create_dir_all(deps.join("node_modules")).expect("create noisy");
+        let write_at = |path: &Path, seconds_ago: u64| {
+            fs::write(path, "").expect("write file");
+            let modified = std::time::SystemTime::now() - Duration::from_secs(seconds_ago);
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_modified(modified))
+                .expect("set mtime");
+        };
+        write_at(&old.join("src/main.rs"), 100);
+        write_at(&recent.join("src/lib.rs"), 200);
+        write_at(&recent.join("src/deep/deeper/new.rs"), 10);
+        write_at(&deps.join("package.json"), 500);
+        write_at(&deps.join("node_modules/index.js"), 1);
+
+        let options = RecentSubprojectOptions::default();
+        assert_eq!(
+            find_recent_subproject(&root, &options).expect("find"),
+            recent
+        );
+
+        let shallow = RecentSubprojectOptions {
+            depth: Some(1),
+            ..RecentSubprojectOptions::default()
+        };
+        assert_eq!(find_recent_subproject(&root, &shallow).expect("find"), old);
+
+        let include_deps = RecentSubprojectOptions {
+            ignore: Some(Vec::new()),
+            ..RecentSubprojectOptions::default()
+        };
+        assert_eq!(
+            find_recent_subproject(&root, &include_deps).expect("find"),
+            deps
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn success_exit_codes_accept_quirky_launchers() {
+        let quirky = DevToolPreset {
+            success_exit_codes: Some(vec![1]),
+            ..preset("/bin/sh")
+        };
+        let run = |preset: &DevToolPreset, script: &str| {
+            let options = LaunchOptions {
+                success_exit_codes: preset.success_exit_codes.clone(),
+                ..LaunchOptions::default()
+            };
+            run_command_with_shell_support(
+                &preset.command_path,
+                &["-c".to_string(), script.to_string()],
+                &options,
+                "启动失败:",
+                "启动失败",
+            )
+        };
+        assert!(run(&quirky, "exit 1").is_ok());
+        assert!(run(&quirky, "exit 0").is_ok());
+        assert_eq!(run(&quirky, "exit 2").unwrap_err(), "启动失败");
+        assert_eq!(run(&preset("/bin/sh"), "exit 1").unwrap_err(), "启动失败");
+    }
+
+    #[test]
+    fn capture_output_with_timeout_reports_output_and_exit_code() {
+        let child = Command::new("/bin/sh")
+            .args(["-c", "echo 1.90.0; echo warning >&2; exit 3"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("spawn sh");
+        let result = capture_output_with_timeout(child, Duration::from_secs(5)).expect("output");
+        assert_eq!(result.output, "1.90.0\nwarning\n");
+        assert_eq!(result.exit_code, Some(3));
+
+        let child = Command::new("/bin/sh")
+            .args(["-c", "sleep 5"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("spawn sh");
+        assert!(capture_output_with_timeout(child, Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn iterm_detection_uses_default_handler_and_configured_command() {
+        let handlers = |shell: &str| {
+            format!(
+                r#"[{{"LSHandlerURLScheme":"https","LSHandlerRoleAll":"com.googlecode.iterm2"}},
+                    {{"LSHandlerContentType":"public.unix-executable","LSHandlerRoleShell":"{shell}"}}]"#
+            )
+        };
+        assert!(shell_handler_is_iterm(&handlers("com.googlecode.iTerm2")));
+        assert!(!shell_handler_is_iterm(&handlers("com.apple.Terminal")));
+        assert!(!shell_handler_is_iterm(
+            r#"[{"LSHandlerURLScheme":"ssh","LSHandlerRoleAll":"com.googlecode.iterm2"}]"#
+        ));
+        assert!(!shell_handler_is_iterm("not json"));
+
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert!(is_iterm_command("/Applications/iTerm.app", &[]));
+        assert!(is_iterm_command(
+            "/usr/bin/open",
+            &args(&["-a", "iTerm", "{path}"])
+        ));
+        assert!(is_iterm_command(
+            "open",
+            &args(&["-b", "com.googlecode.iterm2", "{path}"])
+        ));
+        assert!(!is_iterm_command(
+            "/usr/bin/open",
+            &args(&["-a", "Terminal", "{path}"])
+        ));
+        assert!(!is_iterm_command(
+            "/usr/local/bin/wezterm",
+            &args(&["start"])
+        ));
+    }
+
+    #[test]
+    fn space_window_app_name_and_yabai_window_lookup() {
+        let outcome = |args: &[&str]| OpenOutcome {
+            strategy: LaunchStrategy::MacOpenApp,
+            command: "open".to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            warnings: Vec::new(),
+        };
+        assert_eq!(
+            space_window_app_name(&outcome(&["-a", "Visual Studio Code", "/p"]), None).as_deref(),
+            Some("Visual Studio Code")
+        );
+        assert_eq!(
+            space_window_app_name(&outcome(&["-a", "/Applications/Zed.app/", "/p"]), None)
+                .as_deref(),
+            Some("Zed")
+        );
+        assert_eq!(
+            space_window_app_name(&outcome(&["/p"]), Some("Cursor.app")).as_deref(),
+            Some("Cursor")
+        );
+        assert_eq!(space_window_app_name(&outcome(&["/p"]), None), None);
+
+        let windows = r#"[
+            {"id":10,"pid":1,"app":"DevHaven","has-focus":true},
+            {"id":12,"pid":2,"app":"Code","has-focus":false},
+            {"id":15,"pid":2,"app":"Code","has-focus":false},
+            {"id":20,"pid":3,"app":"Zed","has-focus":true}
+        ]"#;
+        assert_eq!(find_yabai_app_window(windows, "code"), Some(15));
+        assert_eq!(find_yabai_app_window(windows, "Zed"), Some(20));
+        assert_eq!(find_yabai_app_window(windows, "Cursor"), None);
+        assert_eq!(find_yabai_app_window("not json", "Code"), None);
+    }
+
+    #[test]
+    fn verify_all_presets_reports_in_input_order() {
+        let root = std::env::temp_dir().join(format!("devhaven-health-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("create dir");
+        let code = root.join("code");
+        fs::write(&code, "#!/bin/sh\necho 1.95.0\necho abc123\n").expect("write code");
+        fs::set_permissions(&code, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .expect("chmod");
+
+        let mut vscode = preset("vscode");
+        vscode.command_path = code.to_string_lossy().to_string();
+        let mut no_path = preset("sh");
+        no_path.command_path = "/bin/sh".to_string();
+        no_path.arguments = vec!["--login".to_string()];
+        let mut missing = preset("missing");
+        missing.command_path = root.join("missing").to_string_lossy().to_string();
+
+        let reports = verify_all_presets(vec![vscode, no_path, missing]);
+        let summary: Vec<(&str, PresetHealthStatus, Option<&str>)> = reports
+            .iter()
+            .map(|report| (report.id.as_str(), report.status, report.version.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("vscode", PresetHealthStatus::Ok, Some("1.95.0")),
+                ("sh", PresetHealthStatus::Warning, None),
+                ("missing", PresetHealthStatus::Missing, None),
+            ]
+        );
+        assert_eq!(
+            reports[0].resolved_path.as_deref(),
+            Some(code.to_string_lossy().as_ref())
+        );
+        assert_eq!(reports[1].warnings, [PresetWarning::PathNotReferenced]);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn launch_with_deadline_captures_output_or_kills_process_group() {
+        let shell = |script: &str| DevToolPreset {
+            id: "sh".to_string(),
+            name: "sh".to_string(),
+            command_path: "/bin/sh".to_string(),
+            arguments: vec!["-c".to_string(), script.to_string()],
+            success_exit_codes: None,
+            kind: PresetKind::Command,
+            install_kind: InstallKind::default(),
+        };
+
+        let result =
+            launch_with_deadline(&shell("echo out; echo err >&2; exit 2"), "/tmp", 5000).unwrap();
+        assert_eq!(
+            result,
+            DeadlineRunResult {
+                stdout: "out\n".to_string(),
+                stderr: "err\n".to_string(),
+                exit_code: Some(2),
+                timed_out: false,
+            }
+        );
+
+        let started = Instant::now();
+        let result = launch_with_deadline(&shell("sleep 5"), "/tmp", 100).unwrap();
+        assert!(result.timed_out);
+        assert_eq!(result.exit_code, None);
+
+        // 直接子进程已退出，后台进程仍持有输出管道，也应在截止时间被结束。
+        let result = launch_with_deadline(&shell("sleep 5 & echo started"), "/tmp", 300).unwrap();
+        assert!(result.timed_out);
+        assert_eq!(result.stdout, "started\n");
+        assert_eq!(result.exit_code, Some(0));
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn read_output_with_timeout_collects_stdout_or_gives_up() {
+        let child = Command::new("/bin/sh")
+            .args(["-c", "echo ms-python.python; echo rust-lang.rust-analyzer"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("spawn sh");
+        let output = read_output_with_timeout(child, Duration::from_secs(5)).expect("output");
+        assert_eq!(
+            non_empty_lines(&output),
+            ["ms-python.python", "rust-lang.rust-analyzer"]
+        );
+
+        let child = Command::new("/bin/sh")
+            .args(["-c", "sleep 5"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("spawn sh");
+        assert!(read_output_with_timeout(child, Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn parse_login_shell_path_skips_rc_file_output() {
+        let output = "Welcome!\n__DEVHAVEN_PATH__/opt/homebrew/bin:/usr/bin\n";
+        assert_eq!(
+            parse_login_shell_path(output).as_deref(),
+            Some("/opt/homebrew/bin:/usr/bin")
+        );
+        assert_eq!(parse_login_shell_path("no marker"), None);
+        assert_eq!(parse_login_shell_path("__DEVHAVEN_PATH__"), None);
+    }
+
+    #[test]
+    fn tools_missing_from_process_lists_login_only_tools() {
+        let root = std::env::temp_dir().join(format!("devhaven-path-{}", uuid::Uuid::new_v4()));
+        let process_dir = root.join("process");
+        let login_dir = root.join("login");
+        fs::create_dir_all(&process_dir).expect("create process dir");
+        fs::create_dir_all(&login_dir).expect("create login dir");
+        fs::write(process_dir.join("git"), "").expect("write git");
+        fs::write(login_dir.join("git"), "").expect("write git");
+        fs::write(login_dir.join("code"), "").expect("write code");
+
+        let missing = tools_missing_from_process(
+            &["code", "git", "zed"],
+            std::slice::from_ref(&process_dir),
+            &[process_dir.clone(), login_dir.clone()],
+        );
+        assert_eq!(missing, ["code"]);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn apply_argument_hooks_runs_hooks_in_registration_order() {
+        let hooks: Vec<ArgumentHook> = vec![
+            Box::new(|preset, arguments| {
+                if preset.id == "vscode" {
+                    arguments.insert(0, "--proxy-server=http://proxy:8080".to_string());
+                }
+            }),
+            Box::new(|_, arguments| arguments.retain(|argument| argument != "--new-window")),
+            Box::new(|_, arguments| arguments.push(format!("count={}", arguments.len()))),
+        ];
+        let preset = DevToolPreset {
+            id: "vscode".to_string(),
+            name: String::new(),
+            command_path: "code".to_string(),
+            arguments: Vec::new(),
+            success_exit_codes: None,
+            kind: PresetKind::Command,
+            install_kind: InstallKind::default(),
+        };
+        let mut arguments = vec!["--new-window".to_string(), "/src".to_string()];
+        apply_argument_hooks(&hooks, &preset, &mut arguments);
+        assert_eq!(
+            arguments,
+            ["--proxy-server=http://proxy:8080", "/src", "count=2"]
+        );
+    }
+
+    #[test]
+    fn find_msix_app_matches_package_family_name() {
+        let output = r#"[{"Name":"Visual Studio Code","AppID":"Microsoft.VisualStudioCode_8wekyb3d8bbwe!App"},
+            {"Name":"Visual Studio Code - Insiders","AppID":"Microsoft.VisualStudioCodeInsiders_8wekyb3d8bbwe!App"},
+            {"Name":"Notepad++","AppID":"C:\\Program Files\\Notepad++\\notepad++.exe"}]"#;
+        let apps = parse_start_apps(output);
+        assert_eq!(apps.len(), 3);
+        assert_eq!(
+            find_msix_app(&apps, "microsoft.visualstudiocode").map(|app| app.name.as_str()),
+            Some("Visual Studio Code")
+        );
+        assert_eq!(
+            find_msix_app(&apps, "Microsoft.VisualStudioCodeInsiders").map(|app| app.name.as_str()),
+            Some("Visual Studio Code - Insiders")
+        );
+        assert_eq!(find_msix_app(&apps, "Notepad++"), None);
+
+        let single =
+            parse_start_apps(r#"{"Name":"Code","AppID":"Microsoft.VisualStudioCode_x!App"}"#);
+        assert_eq!(single.len(), 1);
+        assert!(parse_start_apps("").is_empty());
+    }
+
+    #[test]
+    fn preset_test_result_maps_exit_states() {
+        assert_eq!(
+            preset_test_result(Ok(None)),
+            PresetTestResult {
+                started: true,
+                stayed_open: true,
+                ..PresetTestResult::default()
+            }
+        );
+        let failed = preset_test_result(Err(std::io::Error::other("gone")));
+        assert!(failed.started && !failed.stayed_open);
+        assert!(failed.error.is_some_and(|error| error.contains("gone")));
+
+        let ok = test_launch_preset_with(&preset("editor"), &FakeRunner::exiting(0));
+        assert_eq!(
+            (ok.started, ok.stayed_open, ok.exit_code),
+            (true, false, Some(0))
+        );
+        assert_eq!(ok.error, None);
+        let runner = FakeRunner::exiting(3);
+        let exited = test_launch_preset_with(&preset("editor"), &runner);
+        assert_eq!(exited.exit_code, Some(3));
+        assert!(exited.error.is_some());
+        let launched = runner.launched.lock().unwrap().clone().unwrap();
+        assert_eq!(launched.0, "editor");
+        assert!(!Path::new(launched.1.last().unwrap()).exists());
+
+        let missing = test_launch_preset_with(&preset("editor"), &FakeRunner::missing());
+        assert!(!missing.started && missing.error.is_some());
+    }
+
+    // 记录启动参数并返回预设退出状态的进程启动器，不启动真实进程。
+    struct FakeRunner {
+        exit: Option<ExitStatus>,
+        launched: Mutex<Option<(String, Vec<String>)>>,
+    }
+
+    impl FakeRunner {
+        fn exiting(code: i32) -> Self {
+            #[cfg(unix)]
+            let status = std::os::unix::process::ExitStatusExt::from_raw(code << 8);
+            #[cfg(windows)]
+            let status = std::os::windows::process::ExitStatusExt::from_raw(code as u32);
+            FakeRunner {
+                exit: Some(status),
+                launched: Mutex::new(None),
+            }
+        }
+
+        fn missing() -> Self {
+            FakeRunner {
+                exit: None,
+                launched: Mutex::new(None),
+            }
+        }
+    }
+
+    impl CommandRunner for FakeRunner {
+        fn spawn(
+            &self,
+            command_path: &str,
+            arguments: &[String],
+        ) -> std::io::Result<Box<dyn RunningProcess>> {
+            *self.launched.lock().unwrap() = Some((command_path.to_string(), arguments.to_vec()));
+            let status = self
+                .exit
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))?;
+            Ok(Box::new(ExitedProcess(status)))
+        }
+    }
+
+    struct ExitedProcess(ExitStatus);
+
+    impl RunningProcess for ExitedProcess {
+        fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>> {
+            Ok(Some(self.0))
+        }
+
+        fn wait(&mut self) -> std::io::Result<ExitStatus> {
+            Ok(self.0)
+        }
+    }
+
+    #[test]
+    fn wait_for_exit_stops_at_exit_or_timeout() {
+        let mut polls = 0;
+        let exited = wait_for_exit(
+            || {
+                polls += 1;
+                Ok((polls == 3).then_some(0))
+            },
+            Duration::from_secs(5),
+            Duration::ZERO,
+        );
+        assert_eq!(exited.unwrap(), Some(0));
+        assert_eq!(polls, 3);
+
+        let running = wait_for_exit(|| Ok(None::<i32>), Duration::ZERO, Duration::ZERO);
+        assert_eq!(running.unwrap(), None);
+    }
+
+    #[test]
+    fn join_path_list_applies_separator_and_quotes() {
+        let paths = vec!["/a".to_string(), "/b c".to_string()];
+        assert_eq!(
+            join_path_list(&paths, &PathListOptions::default()),
+            "/a\n/b c"
+        );
+        let crlf = PathListOptions {
+            crlf: Some(true),
+            ..PathListOptions::default()
+        };
+        assert_eq!(join_path_list(&paths, &crlf), "/a\r\n/b c");
+        let custom = PathListOptions {
+            separator: Some(" ".to_string()),
+            crlf: Some(true),
+            ..PathListOptions::default()
+        };
+        assert_eq!(join_path_list(&paths, &custom), "/a /b c");
+        assert_eq!(join_path_list(&[], &PathListOptions::default()), "");
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn join_path_list_quotes_for_posix_shells() {
+        let quoted = PathListOptions {
+            separator: Some(" ".to_string()),
+            quote: Some(true),
+            ..PathListOptions::default()
+        };
+        let paths = vec!["/it's".to_string(), "/b c".to_string()];
+        assert_eq!(join_path_list(&paths, &quoted), r"'/it'\''s' '/b c'");
+    }
+
+    #[test]
+    fn parse_uri_list_decodes_file_uris() {
+        let content = "# comment\r\nfile:///home/me/My%20Project\r\nfile://localhost/tmp/%E4%B8%AD\nhttps://example.com/x\nfile://remote/share/a\n";
+        assert_eq!(parse_uri_list(content), ["/home/me/My Project", "/tmp/中"]);
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%41"), "%zzA");
+    }
+
+    #[test]
+    fn resolve_launch_command_rejects_relative_paths_in_strict_mode() {
+        let strict = LaunchOptions {
+            strict_path_lookup: true,
+            ..LaunchOptions::default()
+        };
+        let absolute = std::env::temp_dir()
+            .join("code")
+            .to_string_lossy()
+            .to_string();
+        assert_eq!(
+            resolve_launch_command(&absolute, &strict).unwrap(),
+            absolute
+        );
+        for relative in ["./code", "bin/code", "../code"] {
+            assert_eq!(
+                resolve_launch_command(relative, &strict)
+                    .unwrap_err()
+                    .kind(),
+                std::io::ErrorKind::InvalidInput
+            );
+            assert_eq!(
+                resolve_launch_command(relative, &LaunchOptions::default()).unwrap(),
+                relative
+            );
+        }
+        assert_eq!(
+            resolve_launch_command("devhaven-missing-command", &strict)
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn launch_with_shell_support_reports_strategy_and_command() {
+        let arguments = vec!["a b".to_string()];
+        let ((), outcome) =
+            launch_with_shell_support("/bin/echo", &arguments, &LaunchOptions::default(), |_| {
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            outcome,
+            OpenOutcome {
+                strategy: LaunchStrategy::CommandPath,
+                command: "/bin/echo".to_string(),
+                args: arguments.clone(),
+                warnings: Vec::new(),
+            }
+        );
+
+        let login = LaunchOptions {
+            use_login_shell: true,
+            ..LaunchOptions::default()
+        };
+        let ((), outcome) =
+            launch_with_shell_support("/bin/echo", &arguments, &login, |_| Ok(())).unwrap();
+        assert_eq!(outcome.strategy, LaunchStrategy::LoginShell);
+        assert_eq!(outcome.args, ["-lc", "exec '/bin/echo' 'a b'"]);
+    }
+
+    #[test]
+    fn launch_with_shell_support_applies_working_dir() {
+        let root = std::env::temp_dir();
+        let options = LaunchOptions {
+            working_dir: Some(root.clone()),
+            ..LaunchOptions::default()
+        };
+        let (current_dir, _) = launch_with_shell_support("/bin/echo", &[], &options, |command| {
+            Ok(command.get_current_dir().map(Path::to_path_buf))
+        })
+        .unwrap();
+        assert_eq!(current_dir, Some(root));
+
+        let (current_dir, _) =
+            launch_with_shell_support("/bin/echo", &[], &LaunchOptions::default(), |command| {
+                Ok(command.get_current_dir().map(Path::to_path_buf))
+            })
+            .unwrap();
+        assert_eq!(current_dir, None);
+    }
+
+    #[test]
+    fn parse_jupyter_url_picks_local_server_address() {
+        assert_eq!(
+            parse_jupyter_url(
+                "[I 2024-05-01 10:00:00.000 ServerApp]     http://127.0.0.1:8888/lab?token=abc123"
+            )
+            .as_deref(),
+            Some("http://127.0.0.1:8888/lab?token=abc123")
+        );
+        assert_eq!(
+            parse_jupyter_url("     or http://localhost:8889/tree?token=def").as_deref(),
+            Some("http://localhost:8889/tree?token=def")
+        );
+        assert_eq!(
+            parse_jupyter_url("    http://devbox:8888/lab?token=abc123"),
+            None
+        );
+        assert_eq!(
+            parse_jupyter_url("    file:///home/me/.local/share/jupyter/runtime/jpserver.html"),
+            None
+        );
+    }
+
+    #[test]
+    fn terminal_working_dir_prefers_explicit_dir_then_file_parent() {
+        let root = std::env::temp_dir().join(format!("devhaven-cwd-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("create dir");
+        let file = root.join("main.rs");
+        fs::write(&file, "").expect("write file");
+        let file = file.to_string_lossy().to_string();
+        let root_str = root.to_string_lossy().to_string();
+
+        assert_eq!(terminal_working_dir(&file, None), root);
+        assert_eq!(terminal_working_dir(&root_str, None), root);
+        assert_eq!(
+            terminal_working_dir(&file, Some("/srv/app")),
+            PathBuf::from("/srv/app")
+        );
+
+        let new_file = root.join("new.rs").to_string_lossy().to_string();
+        assert_eq!(
+            terminal_command_working_dir(&new_file, None),
+            Ok(root.clone())
+        );
+        assert_eq!(terminal_command_working_dir(&file, None), Ok(root.clone()));
+        let missing = root.join("missing/new.rs").to_string_lossy().to_string();
+        assert!(terminal_command_working_dir(&missing, None)
+            .unwrap_err()
+            .starts_with("工作目录不存在"));
+        let missing_dir = root.join("missing").to_string_lossy().to_string();
+        assert!(terminal_command_working_dir(&file, Some(&missing_dir))
+            .unwrap_err()
+            .contains("missing"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn login_shell_script_quotes_command_and_arguments() {
+        let arguments = vec!["--wait".to_string(), "/tmp/it's a dir".to_string()];
+        assert_eq!(
+            login_shell_script("/usr/local/bin/code", &arguments),
+            r"exec '/usr/local/bin/code' '--wait' '/tmp/it'\''s a dir'"
+        );
+        assert_eq!(quote_posix_argument("$HOME"), "'$HOME'");
+    }
+
+    #[test]
+    fn validate_browser_url_accepts_only_http_links() {
+        assert_eq!(
+            validate_browser_url(" http://localhost:5173/ "),
+            Ok("http://localhost:5173/")
+        );
+        assert!(validate_browser_url("HTTPS://example.com?q=1").is_ok());
+        assert!(validate_browser_url("file:///etc/passwd").is_err());
+        assert!(validate_browser_url("javascript:alert(1)").is_err());
+        assert!(validate_browser_url("http:///path").is_err());
+        assert!(validate_browser_url("http://exa mple.com").is_err());
+    }
+
+    #[test]
+    fn code_server_url_encodes_folder_path() {
+        assert_eq!(
+            code_server_url("https://dev.example.com:8080/", "/home/me/my app").unwrap(),
+            "https://dev.example.com:8080/?folder=/home/me/my%20app"
+        );
+        assert_eq!(
+            code_server_url("http://localhost:8080", "C:\\work\\项目").unwrap(),
+            "http://localhost:8080/?folder=C%3A%5Cwork%5C%E9%A1%B9%E7%9B%AE"
+        );
+        assert!(code_server_url("localhost:8080", "/tmp").is_err());
+        assert!(code_server_url("http://localhost:8080/?tkn=1", "/tmp").is_err());
+    }
+
+    #[test]
+    fn windows_terminal_arguments_target_last_window_when_reusing() {
+        assert_eq!(
+            windows_terminal_arguments("C:\\work", false),
+            vec!["-d", "C:\\work"]
+        );
+        assert_eq!(
+            windows_terminal_arguments("C:\\work", true),
+            vec!["-w", "0", "-d", "C:\\work"]
+        );
+    }
+
+    #[test]
+    fn macos_terminal_script_targets_front_window_when_reusing() {
+        let reused = macos_terminal_script("/tmp/my \"app\"", true, None);
+        assert!(reused.contains(r#"do script "cd '/tmp/my \"app\"'" in front window"#));
+        assert!(reused.contains("if (count of windows) = 0 then"));
+
+        let fresh = macos_terminal_script("/tmp/app", false, None);
+        assert!(fresh.contains(r#"do script "cd '/tmp/app'""#));
+        assert!(!fresh.contains("front window"));
+    }
+
+    #[test]
+    fn repl_command_line_keeps_path_out_unless_requested() {
+        let mut repl = preset("repl-python");
+        repl.command_path = "/usr/bin/python3".to_string();
+        repl.arguments = Vec::new();
+        assert_eq!(repl_command_line(&repl, "/work/app"), ["/usr/bin/python3"]);
+
+        repl.arguments = vec!["-i".to_string(), "{path}/startup.py".to_string()];
+        assert_eq!(
+            repl_command_line(&repl, "/work/app"),
+            ["/usr/bin/python3", "-i", "/work/app/startup.py"]
+        );
+    }
+
+    #[test]
+    fn sandbox_display_paths_bind_only_display_sockets() {
+        let vars = |values: &'static [(&'static str, &'static str)]| {
+            move |key: &str| {
+                values
+                    .iter()
+                    .find(|(name, _)| *name == key)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert_eq!(
+            sandbox_display_paths(vars(&[
+                ("XDG_RUNTIME_DIR", "/run/user/1000"),
+                ("WAYLAND_DISPLAY", "wayland-0"),
+                ("XAUTHORITY", "/run/user/1000/.mutter-Xwaylandauth.ABC"),
+            ])),
+            [
+                PathBuf::from("/run/user/1000/wayland-0"),
+                PathBuf::from("/run/user/1000/.mutter-Xwaylandauth.ABC"),
+            ]
+        );
+        assert_eq!(
+            sandbox_display_paths(vars(&[("WAYLAND_DISPLAY", "/tmp/wayland-1")])),
+            [PathBuf::from("/tmp/wayland-1")]
+        );
+        assert!(sandbox_display_paths(vars(&[
+            ("WAYLAND_DISPLAY", "wayland-0"),
+            ("XAUTHORITY", ""),
+        ]))
+        .is_empty());
+    }
+
+    #[test]
+    fn bwrap_arguments_mount_project_last_and_end_options() {
+        let arguments = bwrap_arguments(
+            Path::new("/work/app"),
+            &[PathBuf::from("/usr")],
+            &[PathBuf::from("/home/me/.vscode")],
+        );
+        assert_eq!(arguments[..2], ["--unshare-all", "--share-net"]);
+        assert_eq!(
+            arguments[arguments.len() - 10..],
+            [
+                "--ro-bind-try",
+                "/usr",
+                "/usr",
+                "--bind-try",
+                "/home/me/.vscode",
+                "/home/me/.vscode",
+                "--bind",
+                "/work/app",
+                "/work/app",
+                "--",
+            ]
+        );
+        let tmpfs = arguments.iter().position(|argument| argument == "--tmpfs");
+        let first_bind = arguments
+            .iter()
+            .position(|argument| argument == "--ro-bind-try");
+        assert!(tmpfs < first_bind);
+    }
+
+    #[test]
+    fn terminal_session_name_replaces_tmux_separators() {
+        assert_eq!(terminal_session_name("my.app:dev", "/tmp/x"), "my_app_dev");
+        assert_eq!(
+            terminal_session_name("  ", "/work/api.server"),
+            "api_server"
+        );
+    }
+
+    #[test]
+    fn terminal_startup_command_attaches_tmux_session() {
+        let session = tmux_session_arguments("/usr/bin/tmux", "app", "/tmp/my app");
+        assert_eq!(
+            terminal_startup_command("/tmp/my app", Some(&session)),
+            "cd '/tmp/my app' && '/usr/bin/tmux' 'new-session' '-A' '-s' 'app' '-c' '/tmp/my app'"
+        );
+        assert_eq!(terminal_startup_command("/tmp/app", None), "cd '/tmp/app'");
+    }
+
+    #[test]
+    fn finder_tab_script_escapes_path_and_falls_back_to_reveal() {
+        let script = finder_tab_script("/Users/me/a \"quoted\" dir");
+        assert!(script.starts_with(
+            "set targetItem to (POSIX file \"/Users/me/a \\\"quoted\\\" dir\") as alias\n"
+        ));
+        assert!(script.contains("reveal targetItem"));
+        assert!(script.contains("keystroke \"t\" using command down"));
+    }
+
+    #[test]
+    fn escape_applescript_string_escapes_quotes_and_backslashes() {
+        assert_eq!(
+            escape_applescript_string(r#"cd '/tmp/a "b"\c'"#),
+            r#"cd '/tmp/a \"b\"\\c'"#
+        );
+    }
+
+    #[test]
+    fn format_command_line_quotes_for_posix_shells() {
+        let args = vec![
+            "--new-window".to_string(),
+            "/Users/me/My Project".to_string(),
+            "it's".to_string(),
+            "$HOME".to_string(),
+            String::new(),
+        ];
+        assert_eq!(
+            format_command_line("/usr/local/bin/code", &args, Shell::Posix),
+            r#"/usr/local/bin/code --new-window '/Users/me/My Project' 'it'\''s' '$HOME' ''"#
+        );
+        assert_eq!(
+            format_command_line("/Applications/My Editor/bin/edit", &[], Shell::Posix),
+            "'/Applications/My Editor/bin/edit'"
+        );
+    }
+
+    #[test]
+    fn format_command_line_quotes_for_powershell() {
+        let args = vec![
+            "-n".to_string(),
+            r"C:\Users\me\it's here".to_string(),
+            "a,b".to_string(),
+        ];
+        assert_eq!(
+            format_command_line(r"C:\Program Files\Code\code.exe", &args, Shell::Powershell),
+            r"& 'C:\Program Files\Code\code.exe' -n 'C:\Users\me\it''s here' 'a,b'"
+        );
+        assert_eq!(
+            format_command_line("code", &[r"C:\src".to_string()], Shell::Powershell),
+            r"code C:\src"
+        );
+    }
+
+    #[test]
+    fn format_command_line_quotes_for_cmd() {
+        let args = vec![
+            r"C:\src\app".to_string(),
+            r"C:\My Projects\".to_string(),
+            r#"say "hi" & 100%"#.to_string(),
+        ];
+        assert_eq!(
+            format_command_line(r"C:\Program Files\Code\code.cmd", &args, Shell::Cmd),
+            r#""C:\Program Files\Code\code.cmd" C:\src\app ^"C:\My Projects\\^" ^"say \^"hi\^" ^& 100^%^""#
+        );
+    }
+
+    #[test]
+    fn quote_powershell_literal_doubles_single_quotes() {
+        assert_eq!(
+            quote_powershell_literal("C:\\it's here"),
+            "'C:\\it''s here'"
+        );
+        assert_eq!(quote_powershell_literal(""), "''");
+    }
+
+    #[test]
+    fn parse_env_file_accepts_plain_assignments_only() {
+        let content = "# comment\n\nexport API_URL=https://example.com/v1\nNAME='My App'\nGREETING=\"hi there\"\nEMPTY=\n";
+        assert_eq!(
+            parse_env_file(content).unwrap(),
+            [
+                ("API_URL".to_string(), "https://example.com/v1".to_string()),
+                ("NAME".to_string(), "My App".to_string()),
+                ("GREETING".to_string(), "hi there".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
+        );
+        for suspicious in [
+            "TOKEN=$(curl evil)",
+            "TOKEN=\"`id`\"",
+            "A=b; rm -rf ~",
+            "A=b c",
+            "rm -rf /",
+            "1A=b",
+            "A='it's'",
+        ] {
+            assert_eq!(parse_env_file(suspicious), None, "{suspicious}");
+        }
+    }
+
+    #[test]
+    fn project_env_file_defers_to_direnv_and_skips_missing_files() {
+        let root = std::env::temp_dir().join(format!("devhaven-env-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("create root");
+        assert_eq!(project_env_file(&root, false), None);
+
+        fs::write(root.join(".env"), "PORT=3000\n").expect("write .env");
+        let (env_file, variables) = project_env_file(&root, false).expect("env file");
+        assert_eq!(env_file, root.join(".env"));
+        assert_eq!(variables, [("PORT".to_string(), "3000".to_string())]);
+
+        fs::write(root.join(".envrc"), "dotenv\n").expect("write .envrc");
+        assert_eq!(project_env_file(&root, true), None);
+        assert!(project_env_file(&root, false).is_some());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn env_file_commands_load_variables_before_the_program() {
+        let program = vec!["npm".to_string(), "run".to_string(), "dev".to_string()];
+        assert_eq!(
+            posix_session_command(Some(Path::new("/work/it's/.env")), None, Some(&program)),
+            [
+                "/bin/sh",
+                "-c",
+                r#"set -a; . '/work/it'\''s/.env'; set +a; exec "$@""#,
+                "sh",
+                "npm",
+                "run",
+                "dev"
+            ]
+        );
+        assert_eq!(
+            posix_session_command(Some(Path::new("/work/.env")), None, None)[2],
+            r#"set -a; . '/work/.env'; set +a; exec "${SHELL:-/bin/sh}""#
+        );
+
+        let variables = vec![("NAME".to_string(), "it's".to_string())];
+        let command = powershell_session_command(&variables, None, Some(&program));
+        assert_eq!(
+            command[..3],
+            ["powershell.exe", "-NoExit", "-EncodedCommand"]
+        );
+        assert_eq!(
+            command[3],
+            encode_powershell_command(
+                "[Environment]::SetEnvironmentVariable('NAME', 'it''s', 'Process'); & 'npm' 'run' 'dev'"
+            )
+        );
+    }
+
+    #[test]
+    fn venv_activation_runs_before_the_program() {
+        let root = std::env::temp_dir().join(format!("devhaven-venv-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(root.join(".venv/bin")).expect("create .venv");
+        assert_eq!(python_venv_activate_script(&root, false), None);
+        fs::create_dir_all(root.join("venv/Scripts")).expect("create venv");
+        fs::write(root.join("venv/Scripts/Activate.ps1"), "").expect("write Activate.ps1");
+        fs::write(root.join(".venv/bin/activate"), "").expect("write activate");
+        assert_eq!(
+            python_venv_activate_script(&root, false),
+            Some(root.join(".venv/bin/activate"))
+        );
+        assert_eq!(
+            python_venv_activate_script(&root, true),
+            Some(root.join("venv").join("Scripts").join("Activate.ps1"))
+        );
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(
+            posix_session_command(
+                Some(Path::new("/work/.env")),
+                Some(Path::new("/work/my app/.venv/bin/activate")),
+                None
+            )[2],
+            r#"set -a; . '/work/.env'; set +a; . '/work/my app/.venv/bin/activate'; exec "${SHELL:-/bin/sh}""#
+        );
+        let command = powershell_session_command(
+            &[],
+            Some(Path::new(r"C:\it's\.venv\Scripts\Activate.ps1")),
+            None,
+        );
+        assert_eq!(
+            command[..4],
+            ["powershell.exe", "-NoExit", "-ExecutionPolicy", "Bypass"]
+        );
+        assert_eq!(
+            command[5],
+            encode_powershell_command(r"& 'C:\it''s\.venv\Scripts\Activate.ps1'; ")
+        );
+    }
+
+    #[test]
+    fn encode_powershell_command_uses_utf16le_base64() {
+        assert_eq!(encode_powershell_command("dir"), "ZABpAHIA");
+        assert_eq!(encode_powershell_command("ls"), "bABzAA==");
+        assert_eq!(encode_powershell_command("中"), "LU4=");
     }
 }
@@ -24,6 +24,13 @@ pub fn system_time_to_unix_seconds(time: SystemTime) -> f64 {
     system_time_to_unix(time)
 }
 
+/// 将系统时间转换为 Unix 毫秒数，早于 1970 年时返回 None。
+pub fn system_time_to_unix_millis(time: SystemTime) -> Option<i64> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_millis() as i64)
+}
+
 // 将系统时间统一转为 Unix 秒数。
 fn system_time_to_unix(time: SystemTime) -> f64 {
     match time.duration_since(UNIX_EPOCH) {